@@ -6,7 +6,7 @@
 //!
 //! - **Paragraphs**: Text content with inline formatting (bold, italic, colors, etc.)
 //! - **Block Types**: Headings (H1-H4), blockquotes, and regular paragraphs
-//! - **Lists**: Bulleted and numbered lists with proper counter management
+//! - **Lists**: Bulleted, numbered and checklist items with proper counter management
 //! - **Images**: Embedded images with positioning, sizing, and text wrapping options
 //! - **Page Breaks**: Explicit page break markers for document pagination
 //!
@@ -20,25 +20,46 @@
 //! │   ├── text: String
 //! │   ├── meta: ParagraphMeta (alignment, block type, list type)
 //! │   └── styles: Vec<TextStyle> (inline formatting ranges)
-//! └── images: Vec<DocumentImage>
-//!     └── (id, src, dimensions, wrapping options)
+//! ├── images: Vec<DocumentImage>
+//! │   └── (id, src, dimensions, wrapping options)
+//! └── tables: Vec<DocumentTable>
+//!     └── rows → cells → paragraphs: Vec<Paragraph>
 //! ```
 //!
+//! Table cells hold paragraphs of their own, which may in turn be image or
+//! table markers: a nested table is a table of `tables` referenced from a cell.
+//!
 //! # Special Markers
 //!
 //! The document uses Unicode characters as markers for special content:
 //! - `U+FFFD` (Replacement Character): Page break marker
 //! - `U+FFFC` (Object Replacement Character): Image placeholder, followed by image ID
+//! - `U+FFFB` (Interlinear Annotation Terminator): Table placeholder, followed by table ID
+//!
+//! # Offsets
+//!
+//! All text offsets (style ranges, cursor positions, edit ranges) are counted in
+//! Unicode scalar values (Rust `char`s), never in bytes. JavaScript callers
+//! working with UTF-16 indices must convert positions that follow characters
+//! outside the Basic Multilingual Plane (e.g. emoji).
 //!
 //! # Serialization
 //!
 //! All types implement `Serialize` and `Deserialize` for JSON persistence,
 //! enabling document save/load functionality.
 
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
 use serde::{Deserialize, Serialize};
 
+use crate::text::{char_to_byte_index, to_alphabetic, to_roman};
+
+/// Deepest list nesting level. Levels are 0-based, so lists have 9 levels.
+pub const MAX_LIST_LEVEL: u8 = 8;
+
 /// The root document structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Document {
     /// Document version for compatibility
     pub version: u32,
@@ -49,6 +70,9 @@ pub struct Document {
     /// All tables in the document
     #[serde(default)]
     pub tables: Vec<DocumentTable>,
+    /// Named paragraph and character styles
+    #[serde(default = "default_styles")]
+    pub styles: Vec<NamedStyle>,
 }
 
 impl Document {
@@ -58,10 +82,214 @@ impl Document {
             paragraphs: vec![Paragraph::new(String::new())],
             images: Vec::new(),
             tables: Vec::new(),
+            styles: default_styles(),
+        }
+    }
+
+    /// Find a named style by ID
+    pub fn style(&self, id: &str) -> Option<&NamedStyle> {
+        self.styles.iter().find(|s| s.id == id)
+    }
+
+    /// Properties of a named style with everything it inherits filled in
+    ///
+    /// Unknown IDs resolve to no properties; a `based_on` chain that loops
+    /// stops at the first repeated style.
+    pub fn resolve_style(&self, id: &str) -> StyleProperties {
+        let mut properties = StyleProperties::default();
+        let mut visited: Vec<&str> = Vec::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            if visited.contains(&id) {
+                break;
+            }
+            visited.push(id);
+            let Some(style) = self.style(id) else { break };
+            properties.inherit(&style.properties);
+            next = style.based_on.as_deref();
         }
+        properties
+    }
+
+    /// Check whether making `id` based on `parent` would create a loop
+    pub fn would_cycle(&self, id: &str, parent: &str) -> bool {
+        let mut visited: Vec<&str> = Vec::new();
+        let mut next = Some(parent);
+        while let Some(current) = next {
+            if current == id || visited.contains(&current) {
+                return true;
+            }
+            visited.push(current);
+            next = self.style(current).and_then(|s| s.based_on.as_deref());
+        }
+        false
+    }
+
+    /// IDs of the tables holding the table `table_id` in a cell, directly or
+    /// in a table nested in one of their cells
+    pub fn enclosing_tables(&self, table_id: &str) -> Vec<&str> {
+        let mut found: Vec<&str> = Vec::new();
+        let mut current = table_id;
+        let mut index = 0;
+        loop {
+            for table in &self.tables {
+                let encloses = table.cell_paragraphs().any(|p| p.table_id() == Some(current));
+                if encloses && table.id != table_id && !found.contains(&table.id.as_str()) {
+                    found.push(&table.id);
+                }
+            }
+            let Some(&next) = found.get(index) else { break };
+            current = next;
+            index += 1;
+        }
+        found
+    }
+
+    /// A text style with its character style (if any) applied
+    ///
+    /// Direct formatting wins over the character style. A style font size is
+    /// used as is; a font scale is relative to `paragraph_size`.
+    pub fn resolve_text_style(&self, style: &TextStyle, paragraph_size: f64) -> TextStyle {
+        let Some(id) = &style.style_id else {
+            return style.clone();
+        };
+        let properties = self.resolve_style(id);
+        let mut resolved = style.clone();
+        resolved.bold |= properties.bold.unwrap_or(false);
+        resolved.italic |= properties.italic.unwrap_or(false);
+        resolved.underline |= properties.underline.unwrap_or(false);
+        resolved.strikethrough |= properties.strikethrough.unwrap_or(false);
+        resolved.color = style.color.clone().or(properties.color);
+        resolved.background = style.background.clone().or(properties.background);
+        resolved.font_family = style.font_family.clone().or(properties.font_family);
+        resolved.font_weight = style.font_weight.or(properties.font_weight);
+        resolved.font_size = style
+            .font_size
+            .or(properties.font_size)
+            .or(properties.font_scale.map(|scale| paragraph_size * scale));
+        resolved
     }
 }
 
+/// Whether a named style applies to whole paragraphs or to runs of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StyleKind {
+    Paragraph,
+    Character,
+}
+
+/// Named style in the document's style sheet, e.g. "Heading 1"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedStyle {
+    /// Unique style ID, referenced by paragraphs and text styles
+    pub id: String,
+    /// Display name
+    pub name: String,
+    pub kind: StyleKind,
+    /// Style this one inherits unset properties from
+    #[serde(default)]
+    pub based_on: Option<String>,
+    #[serde(flatten)]
+    pub properties: StyleProperties,
+}
+
+/// Formatting a named style sets; `None` means inherited
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleProperties {
+    /// Paragraph alignment (paragraph styles only)
+    pub align: Option<TextAlign>,
+    /// Font size in pixels
+    pub font_size: Option<f64>,
+    /// Font size relative to the document's (paragraph styles) or the
+    /// paragraph's (character styles) size, used when `font_size` is unset
+    pub font_scale: Option<f64>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub font_family: Option<String>,
+    pub font_weight: Option<u16>,
+    /// Space above and below the paragraph in pixels (paragraph styles only)
+    pub space_before: Option<f64>,
+    pub space_after: Option<f64>,
+    /// Line spacing (paragraph styles only)
+    pub line_spacing: Option<LineSpacing>,
+    /// Pagination rules (paragraph styles only), see `ParagraphMeta`
+    pub keep_with_next: Option<bool>,
+    pub keep_together: Option<bool>,
+    pub page_break_before: Option<bool>,
+    pub widows: Option<u8>,
+    pub orphans: Option<u8>,
+}
+
+impl StyleProperties {
+    /// Fill the properties this style leaves unset from `parent`
+    pub fn inherit(&mut self, parent: &StyleProperties) {
+        self.align = self.align.or(parent.align);
+        self.font_size = self.font_size.or(parent.font_size);
+        self.font_scale = self.font_scale.or(parent.font_scale);
+        self.bold = self.bold.or(parent.bold);
+        self.italic = self.italic.or(parent.italic);
+        self.underline = self.underline.or(parent.underline);
+        self.strikethrough = self.strikethrough.or(parent.strikethrough);
+        self.color = self.color.take().or_else(|| parent.color.clone());
+        self.background = self.background.take().or_else(|| parent.background.clone());
+        self.font_family = self.font_family.take().or_else(|| parent.font_family.clone());
+        self.font_weight = self.font_weight.or(parent.font_weight);
+        self.space_before = self.space_before.or(parent.space_before);
+        self.space_after = self.space_after.or(parent.space_after);
+        self.line_spacing = self.line_spacing.or(parent.line_spacing);
+        self.keep_with_next = self.keep_with_next.or(parent.keep_with_next);
+        self.keep_together = self.keep_together.or(parent.keep_together);
+        self.page_break_before = self.page_break_before.or(parent.page_break_before);
+        self.widows = self.widows.or(parent.widows);
+        self.orphans = self.orphans.or(parent.orphans);
+    }
+}
+
+/// ID of the style paragraphs use when they name none and are plain paragraphs
+pub const NORMAL_STYLE_ID: &str = "normal";
+
+/// The built-in style sheet: "Normal" plus one style per block type, based on it
+pub fn default_styles() -> Vec<NamedStyle> {
+    let block_types = [
+        BlockType::Paragraph,
+        BlockType::Heading1,
+        BlockType::Heading2,
+        BlockType::Heading3,
+        BlockType::Heading4,
+        BlockType::Blockquote,
+    ];
+    block_types
+        .iter()
+        .map(|block_type| {
+            let id = block_type.style_id();
+            let properties = if id == NORMAL_STYLE_ID {
+                StyleProperties::default()
+            } else {
+                StyleProperties {
+                    font_scale: Some(block_type.font_size_multiplier()),
+                    bold: block_type.is_bold().then_some(true),
+                    italic: block_type.is_italic().then_some(true),
+                    keep_with_next: block_type.is_heading().then_some(true),
+                    ..Default::default()
+                }
+            };
+            NamedStyle {
+                id: id.to_string(),
+                name: block_type.style_name().to_string(),
+                kind: StyleKind::Paragraph,
+                based_on: (id != NORMAL_STYLE_ID).then(|| NORMAL_STYLE_ID.to_string()),
+                properties,
+            }
+        })
+        .collect()
+}
+
 impl Default for Document {
     fn default() -> Self {
         Self::new()
@@ -69,7 +297,7 @@ impl Default for Document {
 }
 
 /// A single paragraph in the document
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Paragraph {
     /// The text content
     pub text: String,
@@ -81,7 +309,7 @@ pub struct Paragraph {
 }
 
 /// Inline text style for a range of characters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextStyle {
     /// Start character index (inclusive)
     pub start: usize,
@@ -105,6 +333,111 @@ pub struct TextStyle {
     /// Background/highlight color (CSS color string)
     #[serde(default)]
     pub background: Option<String>,
+    /// Hyperlink target
+    #[serde(default)]
+    pub link: Option<Link>,
+    /// Font family (None = the document's default family)
+    #[serde(default)]
+    pub font_family: Option<String>,
+    /// Font size in pixels (None = the paragraph's size)
+    #[serde(default)]
+    pub font_size: Option<f64>,
+    /// Numeric font weight, 100-900 (None = bold ? 700 : the paragraph's weight)
+    #[serde(default)]
+    pub font_weight: Option<u16>,
+    /// Superscript/subscript baseline shift
+    #[serde(default)]
+    pub vertical_align: VerticalAlign,
+    /// Small caps / all caps transform
+    #[serde(default)]
+    pub caps: CapsStyle,
+    /// Character style of the range; the fields above override it
+    #[serde(default)]
+    pub style_id: Option<String>,
+}
+
+/// Size of superscript and subscript text relative to the surrounding text
+pub const SCRIPT_SIZE_RATIO: f64 = 0.65;
+
+/// Size of small-caps letters (lowercase drawn as capitals) relative to the text
+pub const SMALL_CAPS_SIZE_RATIO: f64 = 0.8;
+
+/// Vertical position of a run relative to the line's baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalAlign {
+    #[default]
+    Baseline,
+    Superscript,
+    Subscript,
+}
+
+impl VerticalAlign {
+    /// How far text of font size `size` (before the script reduction) is
+    /// raised above the baseline; negative for subscript
+    pub fn rise(self, size: f64) -> f64 {
+        match self {
+            VerticalAlign::Baseline => 0.0,
+            VerticalAlign::Superscript => size * 0.35,
+            VerticalAlign::Subscript => -size * 0.15,
+        }
+    }
+}
+
+/// Capitalization transform of a run; the stored text keeps its case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CapsStyle {
+    #[default]
+    Normal,
+    /// Lowercase letters are drawn as smaller capitals
+    SmallCaps,
+    /// Every letter is drawn as a capital
+    AllCaps,
+}
+
+/// Hyperlink target of a style range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    /// http(s) or mailto URL, or an internal "#anchor"
+    pub url: String,
+    /// Optional tooltip title
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl Link {
+    /// Create a link, or None if the URL is not allowed
+    pub fn new(url: &str, title: Option<String>) -> Option<Self> {
+        let url = url.trim();
+        is_safe_link_url(url).then(|| Link {
+            url: url.to_string(),
+            title: title.filter(|t| !t.is_empty()),
+        })
+    }
+
+    /// Whether the link's URL is allowed (see `is_safe_link_url`)
+    pub fn is_safe(&self) -> bool {
+        is_safe_link_url(&self.url)
+    }
+}
+
+/// Check that a link URL is one we are willing to open
+///
+/// Only http://, https:// and mailto: URLs and internal "#anchor" links are
+/// allowed; everything else (javascript:, data:, relative paths, ...) is
+/// rejected. Whitespace and control characters are never allowed.
+pub fn is_safe_link_url(url: &str) -> bool {
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    if let Some(anchor) = url.strip_prefix('#') {
+        return !anchor.is_empty();
+    }
+    let lower = url.to_ascii_lowercase();
+    ["https://", "http://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme) && lower.len() > scheme.len())
 }
 
 impl TextStyle {
@@ -118,13 +451,23 @@ impl TextStyle {
             strikethrough: false,
             color: None,
             background: None,
+            link: None,
+            font_family: None,
+            font_size: None,
+            font_weight: None,
+            vertical_align: VerticalAlign::Baseline,
+            caps: CapsStyle::Normal,
+            style_id: None,
         }
     }
 
     /// Check if this style has any formatting applied
     pub fn has_formatting(&self) -> bool {
         self.bold || self.italic || self.underline || self.strikethrough
-            || self.color.is_some() || self.background.is_some()
+            || self.color.is_some() || self.background.is_some() || self.link.is_some()
+            || self.font_family.is_some() || self.font_size.is_some() || self.font_weight.is_some()
+            || self.vertical_align != VerticalAlign::Baseline || self.caps != CapsStyle::Normal
+            || self.style_id.is_some()
     }
 
     /// Check if this style overlaps with a range
@@ -151,6 +494,79 @@ impl Paragraph {
         Paragraph { text, meta, styles: Vec::new() }
     }
 
+    /// Length of the text in characters
+    pub fn char_len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Replace the whole text, dropping the parts of styles beyond its end
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+        let len = self.char_len();
+        for style in &mut self.styles {
+            style.end = style.end.min(len);
+        }
+        self.styles.retain(|s| s.start < s.end);
+    }
+
+    /// Insert text at a character offset
+    ///
+    /// Styles after the insertion point are shifted. Inserted text takes the
+    /// formatting of the character before it, so typing at the end of a bold
+    /// run continues the run; at the start of the paragraph it takes the
+    /// formatting of the first character.
+    pub fn insert_text(&mut self, offset: usize, text: &str) {
+        let inserted = text.chars().count();
+        if inserted == 0 {
+            return;
+        }
+        let offset = offset.min(self.char_len());
+        let byte_index = char_to_byte_index(&self.text, offset);
+        self.text.insert_str(byte_index, text);
+
+        for style in &mut self.styles {
+            if (style.start < offset && style.end >= offset) || (offset == 0 && style.start == 0) {
+                // Style continues through the inserted text
+                style.end += inserted;
+            } else if style.start >= offset {
+                style.start += inserted;
+                style.end += inserted;
+            }
+        }
+    }
+
+    /// Delete the characters in `[start, end)`
+    ///
+    /// Styles inside the range are removed, styles spanning it are shortened,
+    /// and styles after it are shifted back.
+    pub fn delete_text(&mut self, start: usize, end: usize) {
+        let len = self.char_len();
+        let end = end.min(len);
+        if start >= end {
+            return;
+        }
+        let start_byte = char_to_byte_index(&self.text, start);
+        let end_byte = char_to_byte_index(&self.text, end);
+        self.text.replace_range(start_byte..end_byte, "");
+
+        let removed = end - start;
+        let map = |pos: usize| {
+            if pos <= start {
+                pos
+            } else if pos >= end {
+                pos - removed
+            } else {
+                start
+            }
+        };
+        for style in &mut self.styles {
+            style.start = map(style.start);
+            style.end = map(style.end);
+        }
+        self.styles.retain(|s| s.start < s.end);
+        self.styles = Self::merge_adjacent_styles(std::mem::take(&mut self.styles));
+    }
+
     /// Apply a style to a range of text
     /// This handles merging and splitting existing styles
     pub fn apply_style<F>(&mut self, start: usize, end: usize, modifier: F)
@@ -246,6 +662,13 @@ impl Paragraph {
                     && last.strikethrough == style.strikethrough
                     && last.color == style.color
                     && last.background == style.background
+                    && last.link == style.link
+                    && last.font_family == style.font_family
+                    && last.font_size == style.font_size
+                    && last.font_weight == style.font_weight
+                    && last.vertical_align == style.vertical_align
+                    && last.caps == style.caps
+                    && last.style_id == style.style_id
                 {
                     // Merge
                     last.end = style.end;
@@ -263,11 +686,98 @@ impl Paragraph {
         self.styles.iter().find(|s| s.start <= pos && s.end > pos)
     }
 
+    /// Get the link covering the character at `pos`
+    ///
+    /// Links with a disallowed URL (e.g. from a hand-edited document) are
+    /// ignored.
+    pub fn link_at(&self, pos: usize) -> Option<&Link> {
+        self.styles
+            .iter()
+            .filter(|s| s.start <= pos && s.end > pos)
+            .filter_map(|s| s.link.as_ref())
+            .find(|link| link.is_safe())
+    }
+
+    /// Split the paragraph at a character offset, returning the text after it
+    ///
+    /// The returned paragraph keeps the styles of the moved text and a copy of
+    /// the paragraph metadata, so list membership and formatting carry over.
+    /// Numbering restarts (`list_start`, `list_continue`) stay with the first
+    /// half, and a split checklist item starts a new unticked item.
+    pub fn split_off(&mut self, offset: usize) -> Paragraph {
+        let offset = offset.min(self.char_len());
+        let byte_index = char_to_byte_index(&self.text, offset);
+        let tail_text = self.text.split_off(byte_index);
+
+        let mut tail_styles = Vec::new();
+        for style in &mut self.styles {
+            if style.end > offset {
+                let mut tail = style.clone();
+                tail.start = style.start.max(offset) - offset;
+                tail.end = style.end - offset;
+                tail_styles.push(tail);
+                style.end = offset;
+            }
+        }
+        self.styles.retain(|s| s.start < s.end);
+
+        // The tail continues the list rather than restarting it
+        let mut meta = self.meta.clone();
+        meta.list_start = None;
+        meta.list_continue = false;
+        meta.checked = false;
+
+        Paragraph {
+            text: tail_text,
+            meta,
+            styles: tail_styles,
+        }
+    }
+
+    /// Append another paragraph's text and styles to this one
+    ///
+    /// The metadata of this paragraph is kept.
+    pub fn append(&mut self, other: Paragraph) {
+        let offset = self.char_len();
+        self.text.push_str(&other.text);
+        self.styles.extend(other.styles.into_iter().map(|mut style| {
+            style.start += offset;
+            style.end += offset;
+            style
+        }));
+        self.styles = Self::merge_adjacent_styles(std::mem::take(&mut self.styles));
+    }
+
     /// Get all styles that overlap with a range
     pub fn styles_in_range(&self, start: usize, end: usize) -> Vec<&TextStyle> {
         self.styles.iter().filter(|s| s.overlaps(start, end)).collect()
     }
 
+    /// Check if every character in a range is covered by a style matching `check`
+    pub fn range_has_style<F>(&self, start: usize, end: usize, check: F) -> bool
+    where
+        F: Fn(&TextStyle) -> bool,
+    {
+        if start >= end {
+            return false;
+        }
+        let mut matching: Vec<&TextStyle> = self
+            .styles
+            .iter()
+            .filter(|s| s.overlaps(start, end) && check(s))
+            .collect();
+        matching.sort_by_key(|s| s.start);
+
+        let mut pos = start;
+        for style in matching {
+            if style.start > pos {
+                return false;
+            }
+            pos = pos.max(style.end);
+        }
+        pos >= end
+    }
+
     /// Check if this paragraph is a page break marker
     /// Uses Unicode replacement character U+FFFD to match JavaScript implementation
     pub fn is_page_break(&self) -> bool {
@@ -290,6 +800,11 @@ impl Paragraph {
         }
     }
 
+    /// Check if this paragraph is a page break, image or table marker
+    pub fn is_marker(&self) -> bool {
+        self.is_page_break() || self.is_image() || self.is_table()
+    }
+
     /// Check if this paragraph is a table marker
     /// Uses Unicode annotation terminator U+FFFB as marker
     pub fn is_table(&self) -> bool {
@@ -308,26 +823,136 @@ impl Paragraph {
 }
 
 /// Paragraph formatting metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParagraphMeta {
-    /// Text alignment
-    pub align: TextAlign,
+    /// Paragraph style (None = the built-in style of the block type)
+    #[serde(default)]
+    pub style_id: Option<String>,
+    /// Text alignment (None = the style's alignment)
+    pub align: Option<TextAlign>,
     /// Block type (paragraph, heading, etc.)
     pub block_type: BlockType,
     /// List type
     pub list_type: ListType,
-    /// Custom font size (if different from default)
+    /// Nesting level of a list item (0 = top level, up to `MAX_LIST_LEVEL`)
+    #[serde(default)]
+    pub list_level: u8,
+    /// Number format of a numbered item (None = by level: 1., a., i.)
+    pub number_format: Option<NumberFormat>,
+    /// Marker template of a numbered item, e.g. "(%1)", "Article %1." or
+    /// "%1.%2.%3" (None = "%N." for level N)
+    pub number_template: Option<String>,
+    /// Restart numbering of this item's level at the given value
+    pub list_start: Option<usize>,
+    /// Continue the numbering of the previous list when this item starts a
+    /// list after non-list paragraphs
+    #[serde(default)]
+    pub list_continue: bool,
+    /// Whether a checklist item is ticked
+    #[serde(default)]
+    pub checked: bool,
+    /// Left, right, first-line and hanging indents
+    #[serde(default)]
+    pub indent: ParagraphIndent,
+    /// Space above the paragraph in pixels (None = the style's, or none)
+    #[serde(default)]
+    pub space_before: Option<f64>,
+    /// Space below the paragraph in pixels (None = the style's, or the
+    /// layout's paragraph spacing)
+    #[serde(default)]
+    pub space_after: Option<f64>,
+    /// Line spacing (None = the style's, or the layout's line height)
+    #[serde(default)]
+    pub line_spacing: Option<LineSpacing>,
+    /// Keep the paragraph on the same page or column as the next one
+    /// (None = the style's: on for headings)
+    #[serde(default)]
+    pub keep_with_next: Option<bool>,
+    /// Never split the paragraph across pages or columns (None = the style's)
+    #[serde(default)]
+    pub keep_together: Option<bool>,
+    /// Start the paragraph on a new page (None = the style's)
+    #[serde(default)]
+    pub page_break_before: Option<bool>,
+    /// Minimum number of lines carried over to the next page or column when
+    /// the paragraph is split (None = the style's, or 2)
+    #[serde(default)]
+    pub widows: Option<u8>,
+    /// Minimum number of lines left at the bottom of a page or column when
+    /// the paragraph is split (None = the style's, or 2)
+    #[serde(default)]
+    pub orphans: Option<u8>,
+    /// Custom font size (if different from the style's)
     pub font_size: Option<f64>,
-    /// Text color
+    /// Text color (None = the style's color)
     pub text_color: Option<String>,
 }
 
+/// How the height of a paragraph's lines is determined
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "value", rename_all = "kebab-case")]
+pub enum LineSpacing {
+    /// Multiple of the largest font size on the line
+    Multiple(f64),
+    /// Exactly this many pixels, whatever the fonts on the line
+    Exact(f64),
+    /// The line's natural height, but at least this many pixels
+    AtLeast(f64),
+}
+
+/// Step by which increase/decrease indent move a paragraph's left indent, in pixels
+pub const INDENT_STEP: f64 = 48.0;
+
+/// Indents of a paragraph's text in pixels, on top of any list indent
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParagraphIndent {
+    /// Indent of every line from the left edge of the column
+    pub left: f64,
+    /// Indent of every line from the right edge of the column
+    pub right: f64,
+    /// Extra indent of the first line
+    pub first_line: f64,
+    /// Extra indent of every line but the first (hanging indent)
+    pub hanging: f64,
+}
+
+impl ParagraphIndent {
+    /// Offset of a line's start from the left edge of the column
+    pub fn line_start(&self, first_line: bool) -> f64 {
+        self.left + if first_line { self.first_line } else { self.hanging }
+    }
+}
+
+impl ParagraphMeta {
+    /// ID of the paragraph style that applies to the paragraph
+    pub fn effective_style_id(&self) -> &str {
+        self.style_id.as_deref().unwrap_or(self.block_type.style_id())
+    }
+}
+
 impl Default for ParagraphMeta {
     fn default() -> Self {
         ParagraphMeta {
-            align: TextAlign::Left,
+            style_id: None,
+            align: None,
             block_type: BlockType::Paragraph,
             list_type: ListType::None,
+            list_level: 0,
+            number_format: None,
+            number_template: None,
+            list_start: None,
+            list_continue: false,
+            checked: false,
+            indent: ParagraphIndent::default(),
+            space_before: None,
+            space_after: None,
+            line_spacing: None,
+            keep_with_next: None,
+            keep_together: None,
+            page_break_before: None,
+            widows: None,
+            orphans: None,
             font_size: None,
             text_color: None,
         }
@@ -387,10 +1012,45 @@ impl BlockType {
         )
     }
 
+    /// Check if this block type is a heading
+    pub fn is_heading(&self) -> bool {
+        matches!(
+            self,
+            BlockType::Heading1
+                | BlockType::Heading2
+                | BlockType::Heading3
+                | BlockType::Heading4
+        )
+    }
+
     /// Check if this block type should be italic
     pub fn is_italic(&self) -> bool {
         matches!(self, BlockType::Blockquote)
     }
+
+    /// ID of the built-in style for this block type
+    pub fn style_id(&self) -> &'static str {
+        match self {
+            BlockType::Paragraph => NORMAL_STYLE_ID,
+            BlockType::Heading1 => "heading1",
+            BlockType::Heading2 => "heading2",
+            BlockType::Heading3 => "heading3",
+            BlockType::Heading4 => "heading4",
+            BlockType::Blockquote => "blockquote",
+        }
+    }
+
+    /// Display name of the built-in style for this block type
+    pub fn style_name(&self) -> &'static str {
+        match self {
+            BlockType::Paragraph => "Normal",
+            BlockType::Heading1 => "Heading 1",
+            BlockType::Heading2 => "Heading 2",
+            BlockType::Heading3 => "Heading 3",
+            BlockType::Heading4 => "Heading 4",
+            BlockType::Blockquote => "Quote",
+        }
+    }
 }
 
 /// List type options
@@ -400,6 +1060,82 @@ pub enum ListType {
     None,
     Bullet,
     Numbered,
+    /// To-do item with a checkbox
+    Checklist,
+}
+
+impl ListType {
+    /// Marker text for a list item at `level` with the given number
+    ///
+    /// Markers cycle every three levels: bullets go •, ◦, ▪ and numbers go
+    /// 1., a., i.
+    pub fn marker(&self, level: u8, number: usize) -> Option<String> {
+        match self {
+            ListType::None => None,
+            ListType::Bullet => Some(["•", "◦", "▪"][level as usize % 3].to_string()),
+            ListType::Numbered => Some(format!("{}.", NumberFormat::for_level(level).format(number))),
+            ListType::Checklist => Some("☐".to_string()),
+        }
+    }
+}
+
+/// Number formats for numbered list items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum NumberFormat {
+    #[default]
+    Decimal,
+    /// Decimal padded to two digits: 01, 02, ... 10
+    DecimalZero,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl NumberFormat {
+    /// Default format of a nesting level: 1., a., i., repeating
+    pub fn for_level(level: u8) -> Self {
+        match level % 3 {
+            0 => NumberFormat::Decimal,
+            1 => NumberFormat::LowerAlpha,
+            _ => NumberFormat::LowerRoman,
+        }
+    }
+
+    /// Format a list number
+    pub fn format(&self, number: usize) -> String {
+        match self {
+            NumberFormat::Decimal => number.to_string(),
+            NumberFormat::DecimalZero => format!("{:02}", number),
+            NumberFormat::LowerAlpha => to_alphabetic(number),
+            NumberFormat::UpperAlpha => to_alphabetic(number).to_uppercase(),
+            NumberFormat::LowerRoman => to_roman(number),
+            NumberFormat::UpperRoman => to_roman(number).to_uppercase(),
+        }
+    }
+}
+
+/// Expand a number template: `%1` to `%9` are replaced by the formatted
+/// number of that nesting level
+///
+/// Levels without a number expand to nothing.
+pub fn expand_number_template(template: &str, numbers: &[String]) -> String {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        let level = chars.peek().and_then(|d| d.to_digit(10)).filter(|d| *d > 0);
+        match level {
+            Some(level) if c == '%' => {
+                chars.next();
+                if let Some(number) = numbers.get(level as usize - 1) {
+                    result.push_str(number);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+    result
 }
 
 /// Image wrap style options
@@ -446,7 +1182,7 @@ pub enum ImagePositionMode {
 }
 
 /// An image in the document
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentImage {
     /// Unique identifier
@@ -533,18 +1269,100 @@ pub enum TableWidthMode {
     Auto,
 }
 
-/// A single table cell
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TableCell {
-    /// Cell text content
-    pub text: String,
-    /// Text styles for inline formatting
+/// Default padding between a table cell's border and its content, on each side
+pub const CELL_TEXT_PADDING: f64 = 4.0;
+
+/// Vertical position of a cell's content when its row is taller than it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CellVerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Space between the edges of a table cell and its content, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CellPadding {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl CellPadding {
+    /// The same padding on all four sides
+    pub fn uniform(padding: f64) -> Self {
+        CellPadding { top: padding, right: padding, bottom: padding, left: padding }
+    }
+
+    /// Left plus right padding
+    pub fn horizontal(&self) -> f64 {
+        self.left + self.right
+    }
+
+    /// Top plus bottom padding
+    pub fn vertical(&self) -> f64 {
+        self.top + self.bottom
+    }
+}
+
+impl Default for CellPadding {
+    fn default() -> Self {
+        CellPadding::uniform(CELL_TEXT_PADDING)
+    }
+}
+
+/// Line style of a table border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    Double,
+    /// No line; a cell side set to none hides the table's border there
+    None,
+}
+
+/// The border on one side of a table cell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellBorder {
+    /// Line width in pixels; drawn at most as wide as the table's
+    /// `border_width`, the room layout leaves between cells
+    pub width: f64,
+    pub color: String,
     #[serde(default)]
-    pub styles: Vec<TextStyle>,
-    /// Cell text alignment
+    pub style: BorderStyle,
+}
+
+/// Borders set on the sides of a table cell; sides left unset use the
+/// table's border
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CellBorders {
+    #[serde(default)]
+    pub top: Option<CellBorder>,
     #[serde(default)]
-    pub align: TextAlign,
+    pub right: Option<CellBorder>,
+    #[serde(default)]
+    pub bottom: Option<CellBorder>,
+    #[serde(default)]
+    pub left: Option<CellBorder>,
+}
+
+/// A single table cell
+///
+/// A cell holds paragraphs like the document body does, so its content can
+/// have several paragraphs, lists, and image and table markers. Images and
+/// tables in a cell are kept in the document's `images` and `tables` like
+/// any other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "TableCellJson")]
+pub struct TableCell {
+    /// Cell content; never empty
+    pub paragraphs: Vec<Paragraph>,
     /// Cell background color (optional)
     #[serde(default)]
     pub background: Option<String>,
@@ -563,53 +1381,154 @@ pub struct TableCell {
     /// If covered, the column of the cell that covers this one
     #[serde(default)]
     pub covered_by_col: Option<usize>,
+    /// Vertical position of the content in the cell
+    #[serde(default)]
+    pub vertical_align: CellVerticalAlign,
+    /// Padding of this cell; None = the table's cell padding
+    #[serde(default)]
+    pub padding: Option<CellPadding>,
+    /// Borders of this cell
+    #[serde(default)]
+    pub borders: CellBorders,
 }
 
 fn default_span() -> usize {
     1
 }
 
-impl TableCell {
-    pub fn new() -> Self {
+/// Serialized table cell, which may hold the single `text` of documents saved
+/// before cells had paragraphs
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TableCellJson {
+    #[serde(default)]
+    paragraphs: Vec<Paragraph>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    styles: Vec<TextStyle>,
+    #[serde(default)]
+    align: TextAlign,
+    #[serde(default)]
+    background: Option<String>,
+    #[serde(default = "default_span")]
+    col_span: usize,
+    #[serde(default = "default_span")]
+    row_span: usize,
+    #[serde(default)]
+    covered: bool,
+    #[serde(default)]
+    covered_by_row: Option<usize>,
+    #[serde(default)]
+    covered_by_col: Option<usize>,
+    #[serde(default)]
+    vertical_align: CellVerticalAlign,
+    #[serde(default)]
+    padding: Option<CellPadding>,
+    #[serde(default)]
+    borders: CellBorders,
+}
+
+impl From<TableCellJson> for TableCell {
+    fn from(json: TableCellJson) -> Self {
+        let mut paragraphs = json.paragraphs;
+        if paragraphs.is_empty() {
+            // Each line of the old text becomes a paragraph with the cell's alignment
+            let mut para = Paragraph::new(json.text);
+            para.styles = json.styles;
+            if json.align != TextAlign::Left {
+                para.meta.align = Some(json.align);
+            }
+            paragraphs = split_lines(para);
+        }
         TableCell {
-            text: String::new(),
-            styles: Vec::new(),
-            align: TextAlign::Left,
-            background: None,
-            col_span: 1,
-            row_span: 1,
-            covered: false,
-            covered_by_row: None,
-            covered_by_col: None,
+            paragraphs,
+            background: json.background,
+            col_span: json.col_span,
+            row_span: json.row_span,
+            covered: json.covered,
+            covered_by_row: json.covered_by_row,
+            covered_by_col: json.covered_by_col,
+            vertical_align: json.vertical_align,
+            padding: json.padding,
+            borders: json.borders,
+        }
+    }
+}
+
+/// Split a paragraph into one paragraph per line of its text
+fn split_lines(para: Paragraph) -> Vec<Paragraph> {
+    split_at_delimiter(para, "\n")
+}
+
+/// Split a paragraph at every occurrence of `delimiter`, which is dropped
+fn split_at_delimiter(mut para: Paragraph, delimiter: &str) -> Vec<Paragraph> {
+    let mut parts = Vec::new();
+    if !delimiter.is_empty() {
+        let delimiter_len = delimiter.chars().count();
+        while let Some(byte_index) = para.text.find(delimiter) {
+            let mut rest = para.split_off(para.text[..byte_index].chars().count());
+            rest.delete_text(0, delimiter_len);
+            parts.push(std::mem::replace(&mut para, rest));
         }
     }
+    parts.push(para);
+    parts
+}
+
+/// Order of two cell texts when sorting a table: empty cells last, then
+/// numbers before other text when sorting numerically, text ignoring case
+fn compare_cell_text(a: &str, b: &str, ascending: bool, numeric: bool) -> Ordering {
+    let (a, b) = (a.trim(), b.trim());
+    // Thousands separators are allowed in numbers
+    let number = |text: &str| text.replace(',', "").parse::<f64>().ok().filter(|n| n.is_finite());
+    let order = match (a.is_empty(), b.is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) if numeric => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => a.to_lowercase().cmp(&b.to_lowercase()),
+        },
+        (false, false) => a.to_lowercase().cmp(&b.to_lowercase()),
+    };
+    if ascending {
+        order
+    } else {
+        order.reverse()
+    }
+}
+
+impl TableCell {
+    pub fn new() -> Self {
+        TableCell::with_text(String::new())
+    }
 
+    /// Create a cell with a paragraph for each line of `text`
     pub fn with_text(text: String) -> Self {
         TableCell {
-            text,
-            styles: Vec::new(),
-            align: TextAlign::Left,
+            paragraphs: split_lines(Paragraph::new(text)),
             background: None,
             col_span: 1,
             row_span: 1,
             covered: false,
             covered_by_row: None,
             covered_by_col: None,
+            vertical_align: CellVerticalAlign::Top,
+            padding: None,
+            borders: CellBorders::default(),
         }
     }
 
     /// Create a covered cell (part of a merged cell region)
     pub fn covered(covered_by_row: usize, covered_by_col: usize) -> Self {
         TableCell {
-            text: String::new(),
-            styles: Vec::new(),
-            align: TextAlign::Left,
-            background: None,
-            col_span: 1,
-            row_span: 1,
             covered: true,
             covered_by_row: Some(covered_by_row),
             covered_by_col: Some(covered_by_col),
+            ..TableCell::new()
         }
     }
 
@@ -617,6 +1536,33 @@ impl TableCell {
     pub fn is_merge_origin(&self) -> bool {
         !self.covered && (self.col_span > 1 || self.row_span > 1)
     }
+
+    /// Text of the cell, with its paragraphs separated by newlines
+    pub fn text(&self) -> String {
+        let texts: Vec<&str> = self.paragraphs.iter().map(|p| p.text.as_str()).collect();
+        texts.join("\n")
+    }
+
+    /// Replace the content with a paragraph for each line of `text`
+    ///
+    /// The new paragraphs take the metadata of the first paragraph, so the
+    /// cell's alignment and style are kept.
+    pub fn set_text(&mut self, text: &str) {
+        let meta = self.paragraphs.first().map(|p| p.meta.clone()).unwrap_or_default();
+        self.paragraphs = text.split('\n').map(|line| Paragraph::with_meta(line.to_string(), meta.clone())).collect();
+    }
+
+    /// Whether the cell holds nothing but an empty paragraph
+    pub fn is_empty(&self) -> bool {
+        self.paragraphs.len() <= 1 && self.paragraphs.iter().all(|p| p.text.is_empty())
+    }
+
+    /// Set the alignment of every paragraph of the cell
+    pub fn set_align(&mut self, align: TextAlign) {
+        for para in &mut self.paragraphs {
+            para.meta.align = Some(align);
+        }
+    }
 }
 
 impl Default for TableCell {
@@ -626,7 +1572,7 @@ impl Default for TableCell {
 }
 
 /// A table row containing cells
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TableRow {
     /// Cells in this row
@@ -646,7 +1592,7 @@ impl TableRow {
 }
 
 /// A table in the document
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentTable {
     /// Unique identifier
@@ -664,6 +1610,13 @@ pub struct DocumentTable {
     /// Width calculation mode
     #[serde(default)]
     pub width_mode: TableWidthMode,
+    /// Number of rows at the top repeated on every page or column the table
+    /// continues on
+    #[serde(default)]
+    pub header_rows: usize,
+    /// Padding of the cells that do not set their own
+    #[serde(default)]
+    pub cell_padding: CellPadding,
 }
 
 fn default_border_width() -> f64 {
@@ -676,18 +1629,43 @@ fn default_border_color() -> String {
 
 impl DocumentTable {
     /// Create a new table with the specified dimensions
-    pub fn new(id: String, num_rows: usize, num_cols: usize, _column_width: f64) -> Self {
-        // Default column widths: evenly distributed percentages
-        let col_width = 100.0 / num_cols as f64;
+    ///
+    /// The columns share `column_width` pixels (the width of the page column
+    /// the table goes in) as fixed widths. Without a positive width they are
+    /// equal percentages of the page column instead.
+    pub fn new(id: String, num_rows: usize, num_cols: usize, column_width: f64) -> Self {
+        let border_width = 1.0;
+        let content_width = column_width - (num_cols + 1) as f64 * border_width;
+        let (width_mode, col_width) = if content_width > 0.0 {
+            (TableWidthMode::Fixed, content_width / num_cols as f64)
+        } else {
+            (TableWidthMode::Percentage, 100.0 / num_cols as f64)
+        };
         let column_widths: Vec<f64> = (0..num_cols).map(|_| col_width).collect();
 
         DocumentTable {
             id,
             rows: (0..num_rows).map(|_| TableRow::new(num_cols)).collect(),
             column_widths,
-            border_width: 1.0,
+            border_width,
             border_color: "#000000".to_string(),
-            width_mode: TableWidthMode::Percentage,
+            width_mode,
+            header_rows: 0,
+            cell_padding: CellPadding::default(),
+        }
+    }
+
+    /// Padding of a cell of this table
+    pub fn padding_of(&self, cell: &TableCell) -> CellPadding {
+        cell.padding.unwrap_or(self.cell_padding)
+    }
+
+    /// The table's own border, used on cell sides that set none
+    pub fn default_border(&self) -> CellBorder {
+        CellBorder {
+            width: self.border_width,
+            color: self.border_color.clone(),
+            style: BorderStyle::Solid,
         }
     }
 
@@ -711,6 +1689,169 @@ impl DocumentTable {
         self.rows.get_mut(row).and_then(|r| r.cells.get_mut(col))
     }
 
+    /// Create a table from paragraphs of delimited text, like pasted
+    /// tab-separated values: a row per paragraph and a cell per field
+    ///
+    /// Fields keep their styles and paragraph formatting. Rows with fewer
+    /// fields than others get empty cells, and image, table and page break
+    /// paragraphs go whole into the first cell of their row.
+    pub fn from_paragraphs(id: String, paragraphs: &[Paragraph], delimiter: &str, column_width: f64) -> Self {
+        let rows: Vec<Vec<Paragraph>> = paragraphs
+            .iter()
+            .map(|para| {
+                if para.is_marker() {
+                    vec![para.clone()]
+                } else {
+                    split_at_delimiter(para.clone(), delimiter)
+                }
+            })
+            .collect();
+        let num_cols = rows.iter().map(Vec::len).max().unwrap_or(1);
+        let mut table = DocumentTable::new(id, rows.len().max(1), num_cols, column_width);
+        for (row, fields) in rows.into_iter().enumerate() {
+            for (col, field) in fields.into_iter().enumerate() {
+                table.rows[row].cells[col].paragraphs = vec![field];
+            }
+        }
+        table
+    }
+
+    /// The table's content as paragraphs of delimited text, a paragraph per
+    /// row and a field per cell: the inverse of `from_paragraphs()`
+    ///
+    /// The paragraphs of a cell are joined with spaces. Image and table
+    /// paragraphs cannot be part of a line of text, so they follow the
+    /// paragraph of their row. Cells covered by a merged cell are left out.
+    pub fn to_paragraphs(&self, delimiter: &str) -> Vec<Paragraph> {
+        let mut paragraphs = Vec::new();
+        for row in &self.rows {
+            let cells: Vec<&TableCell> = row.cells.iter().filter(|cell| !cell.covered).collect();
+            let meta = cells
+                .first()
+                .and_then(|cell| cell.paragraphs.iter().find(|p| !p.is_marker()))
+                .map(|p| p.meta.clone())
+                .unwrap_or_default();
+            let mut line = Paragraph::with_meta(String::new(), meta);
+            let mut markers = Vec::new();
+            for (index, cell) in cells.iter().enumerate() {
+                if index > 0 {
+                    line.append(Paragraph::new(delimiter.to_string()));
+                }
+                let (cell_markers, texts): (Vec<&Paragraph>, Vec<&Paragraph>) =
+                    cell.paragraphs.iter().partition(|p| p.is_marker());
+                for (index, para) in texts.into_iter().enumerate() {
+                    if index > 0 {
+                        line.append(Paragraph::new(" ".to_string()));
+                    }
+                    line.append(para.clone());
+                }
+                markers.extend(cell_markers.into_iter().cloned());
+            }
+            paragraphs.push(line);
+            paragraphs.extend(markers);
+        }
+        paragraphs
+    }
+
+    /// Sort the rows below the header rows by the text in column `col`
+    ///
+    /// Text is compared ignoring case; numeric sorting compares numbers and
+    /// puts cells that are not numbers after them. Empty cells go last either
+    /// way, and rows that compare equal keep their order. Returns false
+    /// without sorting for an unknown column, or if a cell is merged across
+    /// rows that would have to move apart.
+    pub fn sort_rows(&mut self, col: usize, ascending: bool, numeric: bool) -> bool {
+        let first = self.header_rows.min(self.num_rows());
+        if col >= self.num_cols() || first == self.num_rows() || self.has_row_merges(first..=self.num_rows() - 1) {
+            return false;
+        }
+        // A cell covered by a cell merged along its row sorts by that cell
+        let key = |row: &TableRow| -> String {
+            let cell = row.cells.get(col);
+            let col = cell.and_then(|cell| cell.covered_by_col).unwrap_or(col);
+            row.cells.get(col).map(TableCell::text).unwrap_or_default()
+        };
+        let mut body: Vec<(String, TableRow)> = self.rows.drain(first..).map(|row| (key(&row), row)).collect();
+        body.sort_by(|(a, _), (b, _)| compare_cell_text(a, b, ascending, numeric));
+        self.rows.extend(body.into_iter().map(|(_, row)| row));
+        self.renumber_covered_rows(first..=self.num_rows() - 1);
+        true
+    }
+
+    /// Move a row to another position, pushing the rows in between aside
+    ///
+    /// Returns false if either position is out of range, or if a cell in the
+    /// rows that move is merged with another row.
+    pub fn move_row(&mut self, from: usize, to: usize) -> bool {
+        let moved = from.min(to)..=from.max(to);
+        if to >= self.num_rows() || from >= self.num_rows() || self.has_row_merges(moved.clone()) {
+            return false;
+        }
+        let row = self.rows.remove(from);
+        self.rows.insert(to, row);
+        self.renumber_covered_rows(moved);
+        true
+    }
+
+    /// Move a column to another position, with its width, pushing the
+    /// columns in between aside
+    ///
+    /// Returns false if either position is out of range, or if a cell in the
+    /// columns that move is merged with another column.
+    pub fn move_column(&mut self, from: usize, to: usize) -> bool {
+        let moved = from.min(to)..=from.max(to);
+        if to >= self.num_cols() || from >= self.num_cols() || self.has_column_merges(moved.clone()) {
+            return false;
+        }
+        let width = self.column_widths.remove(from);
+        self.column_widths.insert(to, width);
+        for row in &mut self.rows {
+            if from < row.cells.len() && to < row.cells.len() {
+                let cell = row.cells.remove(from);
+                row.cells.insert(to, cell);
+            }
+        }
+        // Cells merged down a column now point at the column's new position
+        for row in &mut self.rows {
+            for (col, cell) in row.cells.iter_mut().enumerate().filter(|(col, _)| moved.contains(col)) {
+                if cell.covered {
+                    cell.covered_by_col = Some(col);
+                }
+            }
+        }
+        true
+    }
+
+    /// Whether a cell in `rows` is merged with a row before or after them
+    /// or with another of them
+    fn has_row_merges(&self, rows: RangeInclusive<usize>) -> bool {
+        self.rows[rows.clone()].iter().zip(rows).any(|(row, index)| {
+            row.cells
+                .iter()
+                .any(|cell| cell.row_span > 1 || (cell.covered && cell.covered_by_row != Some(index)))
+        })
+    }
+
+    /// Whether a cell in `cols` is merged with a column before or after
+    /// them or with another of them
+    fn has_column_merges(&self, cols: RangeInclusive<usize>) -> bool {
+        self.rows.iter().any(|row| {
+            row.cells.iter().enumerate().filter(|(col, _)| cols.contains(col)).any(|(col, cell)| {
+                cell.col_span > 1 || (cell.covered && cell.covered_by_col != Some(col))
+            })
+        })
+    }
+
+    /// Point the covered cells of `rows`, which are merged only along their
+    /// row, at the row's new position
+    fn renumber_covered_rows(&mut self, rows: RangeInclusive<usize>) {
+        for (index, row) in self.rows.iter_mut().enumerate().filter(|(index, _)| rows.contains(index)) {
+            for cell in row.cells.iter_mut().filter(|cell| cell.covered) {
+                cell.covered_by_row = Some(index);
+            }
+        }
+    }
+
     /// Add a row at the specified index
     pub fn add_row(&mut self, at_index: usize) {
         let num_cols = self.num_cols();
@@ -727,14 +1868,27 @@ impl DocumentTable {
             row.cells.insert(index, TableCell::new());
         }
 
-        // Redistribute column widths
-        let new_width = 100.0 / (self.num_cols() + 1) as f64;
-        self.column_widths.insert(index, new_width);
+        // Redistribute column widths, keeping the table as wide as it was
+        let width = self.total_column_width();
+        self.column_widths.insert(index, width / self.num_cols() as f64);
+        self.normalize_column_widths(width);
+    }
+
+    /// Sum of the column widths: 100 for percentages, pixels for fixed widths
+    fn total_column_width(&self) -> f64 {
+        match self.width_mode {
+            TableWidthMode::Fixed => self.column_widths.iter().sum(),
+            TableWidthMode::Percentage | TableWidthMode::Auto => 100.0,
+        }
+    }
 
-        // Normalize widths to 100%
-        let total: f64 = self.column_widths.iter().sum();
-        for w in &mut self.column_widths {
-            *w = *w / total * 100.0;
+    /// Scale the column widths so they add up to `total`
+    fn normalize_column_widths(&mut self, total: f64) {
+        let sum: f64 = self.column_widths.iter().sum();
+        if sum > 0.0 {
+            for w in &mut self.column_widths {
+                *w = *w / sum * total;
+            }
         }
     }
 
@@ -756,13 +1910,9 @@ impl DocumentTable {
                     row.cells.remove(col);
                 }
             }
+            let width = self.total_column_width();
             self.column_widths.remove(col);
-
-            // Normalize widths to 100%
-            let total: f64 = self.column_widths.iter().sum();
-            for w in &mut self.column_widths {
-                *w = *w / total * 100.0;
-            }
+            self.normalize_column_widths(width);
             true
         } else {
             false
@@ -810,20 +1960,20 @@ impl DocumentTable {
             }
         }
 
-        // Collect text from all cells being merged (only non-covered cells)
-        let mut combined_text = String::new();
+        // Collect the content of all cells being merged (only non-covered cells)
+        let mut combined: Vec<Paragraph> = Vec::new();
         for row_idx in start_row..=end_row {
             for col_idx in start_col..=end_col {
                 if let Some(cell) = self.get_cell(row_idx, col_idx) {
-                    if !cell.covered && !cell.text.is_empty() {
-                        if !combined_text.is_empty() {
-                            combined_text.push('\n');
-                        }
-                        combined_text.push_str(&cell.text);
+                    if !cell.covered && !cell.is_empty() {
+                        combined.extend(cell.paragraphs.iter().cloned());
                     }
                 }
             }
         }
+        if combined.is_empty() {
+            combined.push(Paragraph::new(String::new()));
+        }
 
         // Calculate span sizes
         let row_span = end_row - start_row + 1;
@@ -831,7 +1981,7 @@ impl DocumentTable {
 
         // Update the origin cell (top-left)
         if let Some(origin) = self.get_cell_mut(start_row, start_col) {
-            origin.text = combined_text;
+            origin.paragraphs = combined;
             origin.row_span = row_span;
             origin.col_span = col_span;
             origin.covered = false;
@@ -846,7 +1996,7 @@ impl DocumentTable {
                     continue; // Skip origin cell
                 }
                 if let Some(cell) = self.get_cell_mut(row_idx, col_idx) {
-                    cell.text.clear();
+                    cell.paragraphs = vec![Paragraph::new(String::new())];
                     cell.covered = true;
                     cell.covered_by_row = Some(start_row);
                     cell.covered_by_col = Some(start_col);
@@ -926,5 +2076,10 @@ impl DocumentTable {
             false
         }
     }
+
+    /// All paragraphs in the table's cells, row by row
+    pub fn cell_paragraphs(&self) -> impl Iterator<Item = &Paragraph> {
+        self.rows.iter().flat_map(|row| row.cells.iter()).flat_map(|cell| cell.paragraphs.iter())
+    }
 }
 
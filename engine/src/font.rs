@@ -0,0 +1,314 @@
+//! Font Registry and Text Shaping
+//!
+//! This module lets the engine measure text with real font data instead of
+//! relying on the browser's `measureText`. Fonts are registered as raw TTF/OTF
+//! bytes and shaped with rustybuzz (a Rust port of HarfBuzz), so widths include
+//! kerning, ligatures and other OpenType features. Because shaping happens inside
+//! the engine, line breaks are identical in every environment (browser, server,
+//! tests).
+//!
+//! # Components
+//!
+//! - `FontRegistry`: Owns the font data, indexed by family name, weight and style
+//! - `ShapingMeasurer`: `TextMeasurer` implementation that shapes text with the
+//!   registered fonts
+//! - `ShapedText`: Glyph-level shaping result (glyph ids, clusters, advances)
+//!
+//! # Font Matching
+//!
+//! Weight and style are read from the font's `OS/2` table when it is registered.
+//! When measuring, the registry picks the face of the requested family with the
+//! same style and the closest weight. Unknown families fall back to the first
+//! registered family; if no font is registered at all, `ShapingMeasurer` falls
+//! back to `FixedWidthMeasurer`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut registry = FontRegistry::new();
+//! registry.register("Inter", std::fs::read("Inter-Regular.ttf")?)?;
+//!
+//! let measurer = ShapingMeasurer::new(&registry);
+//! let width = measurer.measure("Office", &FontSpec::new(16.0));
+//! ```
+
+use std::fmt;
+
+use crate::measure::{FixedWidthMeasurer, FontMetrics, FontSpec, TextMeasurer};
+
+/// Error returned when registering a font fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontError {
+    /// The family name was empty
+    EmptyName,
+    /// The data could not be parsed as a TrueType/OpenType font
+    InvalidData,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::EmptyName => write!(f, "Font family name must not be empty"),
+            FontError::InvalidData => write!(f, "Invalid font data"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// A font face registered with the engine
+#[derive(Debug, Clone)]
+pub struct RegisteredFont {
+    /// Family name the font was registered under
+    pub family: String,
+    /// Numeric weight from the OS/2 table (400 = normal, 700 = bold)
+    pub weight: u16,
+    /// Whether the face is italic/oblique
+    pub italic: bool,
+    /// Raw font file data
+    data: Vec<u8>,
+}
+
+impl RegisteredFont {
+    /// Raw font file data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Collection of fonts available for shaping
+#[derive(Debug, Clone, Default)]
+pub struct FontRegistry {
+    fonts: Vec<RegisteredFont>,
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        FontRegistry { fonts: Vec::new() }
+    }
+
+    /// Register a font face under a family name
+    ///
+    /// Registering a face with the same family, weight and style as an existing
+    /// one replaces it.
+    pub fn register(&mut self, family: &str, data: Vec<u8>) -> Result<(), FontError> {
+        let family = family.trim();
+        if family.is_empty() {
+            return Err(FontError::EmptyName);
+        }
+
+        let (weight, italic) = {
+            let face = ttf_parser::Face::parse(&data, 0).map_err(|_| FontError::InvalidData)?;
+            // rustybuzz needs the same data to be shapeable
+            if rustybuzz::Face::from_slice(&data, 0).is_none() {
+                return Err(FontError::InvalidData);
+            }
+            (face.weight().to_number(), face.is_italic() || face.is_oblique())
+        };
+
+        self.fonts.retain(|f| {
+            !(f.family.eq_ignore_ascii_case(family) && f.weight == weight && f.italic == italic)
+        });
+        self.fonts.push(RegisteredFont {
+            family: family.to_string(),
+            weight,
+            italic,
+            data,
+        });
+        Ok(())
+    }
+
+    /// Number of registered faces
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    /// Check if no font has been registered
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    /// Get the distinct family names, in registration order
+    pub fn families(&self) -> Vec<&str> {
+        let mut families: Vec<&str> = Vec::new();
+        for font in &self.fonts {
+            if !families.iter().any(|f| f.eq_ignore_ascii_case(&font.family)) {
+                families.push(&font.family);
+            }
+        }
+        families
+    }
+
+    /// Get all registered faces
+    pub fn fonts(&self) -> &[RegisteredFont] {
+        &self.fonts
+    }
+
+    /// Find the index of the best matching face for a family, weight and style
+    pub fn find(&self, family: Option<&str>, weight: u16, italic: bool) -> Option<usize> {
+        let family = family
+            .filter(|name| self.fonts.iter().any(|f| f.family.eq_ignore_ascii_case(name)))
+            .or_else(|| self.fonts.first().map(|f| f.family.as_str()))?;
+
+        self.fonts
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.family.eq_ignore_ascii_case(family))
+            .min_by_key(|(_, f)| {
+                let style_penalty = if f.italic == italic { 0 } else { 1000 };
+                style_penalty + (f.weight as i32 - weight as i32).unsigned_abs()
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+/// A single shaped glyph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    /// Glyph id in the font
+    pub glyph_id: u32,
+    /// Byte offset of the first character this glyph belongs to
+    pub cluster: u32,
+    /// Horizontal advance in pixels
+    pub x_advance: f64,
+    /// Horizontal offset from the pen position in pixels
+    pub x_offset: f64,
+    /// Vertical offset from the baseline in pixels
+    pub y_offset: f64,
+}
+
+/// Result of shaping a piece of text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShapedText {
+    /// Shaped glyphs in visual order
+    pub glyphs: Vec<ShapedGlyph>,
+    /// Total advance width in pixels
+    pub width: f64,
+}
+
+/// Text measurer that shapes text with registered fonts
+///
+/// Faces are parsed once when the measurer is created, so a single measurer
+/// should be reused for a whole layout pass.
+pub struct ShapingMeasurer<'a> {
+    registry: &'a FontRegistry,
+    faces: Vec<rustybuzz::Face<'a>>,
+    family: Option<String>,
+    fallback: FixedWidthMeasurer,
+}
+
+impl<'a> ShapingMeasurer<'a> {
+    pub fn new(registry: &'a FontRegistry) -> Self {
+        let faces = registry
+            .fonts
+            .iter()
+            .filter_map(|f| rustybuzz::Face::from_slice(&f.data, 0))
+            .collect();
+
+        ShapingMeasurer {
+            registry,
+            faces,
+            family: None,
+            fallback: FixedWidthMeasurer::default(),
+        }
+    }
+
+    /// Set the family used for text without an explicit family (defaults to the
+    /// first registered family)
+    pub fn with_family(mut self, family: &str) -> Self {
+        self.family = Some(family.to_string());
+        self
+    }
+
+    /// Family to look up for `font`: its own family, else the measurer's
+    fn family_for<'f>(&'f self, font: &'f FontSpec) -> Option<&'f str> {
+        font.family.as_deref().or(self.family.as_deref())
+    }
+
+    /// Shape text with the best matching face
+    ///
+    /// Returns `None` if no font is registered.
+    pub fn shape(&self, text: &str, font: &FontSpec) -> Option<ShapedText> {
+        let index = self.registry.find(self.family_for(font), font.weight, font.italic)?;
+        let face = self.faces.get(index)?;
+        Some(shape_with_face(face, text, font.size))
+    }
+}
+
+impl TextMeasurer for ShapingMeasurer<'_> {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        match self.shape(text, font) {
+            Some(shaped) => shaped.width,
+            None => self.fallback.measure(text, font),
+        }
+    }
+
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        let Some(shaped) = self.shape(text, font) else {
+            return self.fallback.prefix_widths(text, font);
+        };
+        // A prefix ends at a character boundary and holds every glyph of the
+        // clusters before it; a ligature counts once its first character is in
+        let mut glyphs = shaped.glyphs;
+        glyphs.sort_by_key(|g| g.cluster);
+        let mut glyphs = glyphs.iter().peekable();
+        let mut width = 0.0;
+        let mut widths = vec![0.0];
+        for (i, c) in text.char_indices() {
+            let end = (i + c.len_utf8()) as u32;
+            while let Some(glyph) = glyphs.next_if(|g| g.cluster < end) {
+                width += glyph.x_advance;
+            }
+            widths.push(width);
+        }
+        widths
+    }
+
+    fn metrics(&self, font: &FontSpec) -> FontMetrics {
+        let face = self
+            .registry
+            .find(self.family_for(font), font.weight, font.italic)
+            .and_then(|index| self.faces.get(index));
+        match face {
+            Some(face) => {
+                let scale = font.size / face.units_per_em().max(1) as f64;
+                FontMetrics {
+                    ascent: face.ascender() as f64 * scale,
+                    descent: -(face.descender() as f64) * scale,
+                }
+            }
+            None => self.fallback.metrics(font),
+        }
+    }
+}
+
+/// Shape text with a face and scale the result to `font_size` pixels
+fn shape_with_face(face: &rustybuzz::Face, text: &str, font_size: f64) -> ShapedText {
+    if text.is_empty() {
+        return ShapedText::default();
+    }
+
+    let units_per_em = face.units_per_em().max(1) as f64;
+    let scale = font_size / units_per_em;
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+    let output = rustybuzz::shape(face, &[], buffer);
+
+    let glyphs: Vec<ShapedGlyph> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, pos)| ShapedGlyph {
+            glyph_id: info.glyph_id,
+            cluster: info.cluster,
+            x_advance: pos.x_advance as f64 * scale,
+            x_offset: pos.x_offset as f64 * scale,
+            y_offset: pos.y_offset as f64 * scale,
+        })
+        .collect();
+    let width = glyphs.iter().map(|g| g.x_advance).sum();
+
+    ShapedText { glyphs, width }
+}
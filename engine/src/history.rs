@@ -0,0 +1,401 @@
+//! Undo/Redo History
+//!
+//! Every change the `Engine` makes to the document is recorded as an
+//! `Operation`: an invertible step that stores the state of the affected
+//! paragraph, image, table or style sheet before and after the change. Operations are
+//! collected into `Transaction`s, which are the unit of undo and redo.
+//!
+//! # Grouping
+//!
+//! By default each engine call that changes the document becomes its own
+//! transaction. Calls made between `begin_group()` and `end_group()` are
+//! recorded into a single transaction instead, so that for example typing a
+//! word or a multi-step table edit is undone in one step. Groups may be nested;
+//! only the outermost `end_group()` closes the transaction.
+//!
+//! # Compaction
+//!
+//! Within a transaction, consecutive changes to the same paragraph, image or
+//! table are folded into one operation that keeps the oldest "before" and the
+//! newest "after" state.
+//!
+//! # Example
+//!
+//! ```ignore
+//! engine.begin_group();
+//! engine.set_paragraph(0, "Hello".to_string());
+//! engine.toggle_bold(0, 0, 5);
+//! engine.end_group();
+//!
+//! engine.undo(); // Reverts both changes
+//! engine.redo(); // Re-applies both changes
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::{Document, DocumentImage, DocumentTable, NamedStyle, Paragraph};
+
+/// Default maximum number of transactions kept on the undo stack
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// A single invertible change to the document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Operation {
+    /// Replace the paragraph at `index`
+    SetParagraph {
+        index: usize,
+        before: Paragraph,
+        after: Paragraph,
+    },
+    /// Insert a paragraph at `index`
+    InsertParagraph { index: usize, paragraph: Paragraph },
+    /// Remove the paragraph at `index`
+    RemoveParagraph { index: usize, paragraph: Paragraph },
+    /// Replace the image with the same ID
+    SetImage {
+        before: DocumentImage,
+        after: DocumentImage,
+    },
+    /// Insert an image at `index` in `Document::images`
+    InsertImage { index: usize, image: DocumentImage },
+    /// Remove the image at `index` in `Document::images`
+    RemoveImage { index: usize, image: DocumentImage },
+    /// Replace the table with the same ID
+    SetTable {
+        before: DocumentTable,
+        after: DocumentTable,
+    },
+    /// Insert a table at `index` in `Document::tables`
+    InsertTable { index: usize, table: DocumentTable },
+    /// Remove the table at `index` in `Document::tables`
+    RemoveTable { index: usize, table: DocumentTable },
+    /// Replace the style sheet (`Document::styles`)
+    SetStyles {
+        before: Vec<NamedStyle>,
+        after: Vec<NamedStyle>,
+    },
+}
+
+impl Operation {
+    /// Get the operation that reverts this one
+    pub fn invert(&self) -> Operation {
+        match self.clone() {
+            Operation::SetParagraph { index, before, after } => Operation::SetParagraph {
+                index,
+                before: after,
+                after: before,
+            },
+            Operation::InsertParagraph { index, paragraph } => {
+                Operation::RemoveParagraph { index, paragraph }
+            }
+            Operation::RemoveParagraph { index, paragraph } => {
+                Operation::InsertParagraph { index, paragraph }
+            }
+            Operation::SetImage { before, after } => Operation::SetImage {
+                before: after,
+                after: before,
+            },
+            Operation::InsertImage { index, image } => Operation::RemoveImage { index, image },
+            Operation::RemoveImage { index, image } => Operation::InsertImage { index, image },
+            Operation::SetTable { before, after } => Operation::SetTable {
+                before: after,
+                after: before,
+            },
+            Operation::InsertTable { index, table } => Operation::RemoveTable { index, table },
+            Operation::RemoveTable { index, table } => Operation::InsertTable { index, table },
+            Operation::SetStyles { before, after } => Operation::SetStyles {
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    /// Apply the operation to a document
+    ///
+    /// Returns false (and leaves the document unchanged) if the document is not
+    /// in the state the operation expects, e.g. an index is out of range or an
+    /// image/table ID does not exist.
+    pub fn apply(&self, document: &mut Document) -> bool {
+        match self {
+            Operation::SetParagraph { index, after, .. } => match document.paragraphs.get_mut(*index) {
+                Some(para) => {
+                    *para = after.clone();
+                    true
+                }
+                None => false,
+            },
+            Operation::InsertParagraph { index, paragraph } => {
+                if *index > document.paragraphs.len() {
+                    return false;
+                }
+                document.paragraphs.insert(*index, paragraph.clone());
+                true
+            }
+            Operation::RemoveParagraph { index, .. } => {
+                if *index >= document.paragraphs.len() {
+                    return false;
+                }
+                document.paragraphs.remove(*index);
+                true
+            }
+            Operation::SetImage { after, .. } => {
+                match document.images.iter_mut().find(|img| img.id == after.id) {
+                    Some(image) => {
+                        *image = after.clone();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Operation::InsertImage { index, image } => {
+                if *index > document.images.len() {
+                    return false;
+                }
+                document.images.insert(*index, image.clone());
+                true
+            }
+            Operation::RemoveImage { index, .. } => {
+                if *index >= document.images.len() {
+                    return false;
+                }
+                document.images.remove(*index);
+                true
+            }
+            Operation::SetTable { after, .. } => {
+                match document.tables.iter_mut().find(|t| t.id == after.id) {
+                    Some(table) => {
+                        *table = after.clone();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Operation::InsertTable { index, table } => {
+                if *index > document.tables.len() {
+                    return false;
+                }
+                document.tables.insert(*index, table.clone());
+                true
+            }
+            Operation::RemoveTable { index, .. } => {
+                if *index >= document.tables.len() {
+                    return false;
+                }
+                document.tables.remove(*index);
+                true
+            }
+            Operation::SetStyles { after, .. } => {
+                document.styles = after.clone();
+                true
+            }
+        }
+    }
+
+    /// Fold `next` into this operation if both replace the same entity
+    fn merge(&mut self, next: &Operation) -> bool {
+        match (self, next) {
+            (
+                Operation::SetParagraph { index, after, .. },
+                Operation::SetParagraph { index: next_index, after: next_after, .. },
+            ) if index == next_index => {
+                *after = next_after.clone();
+                true
+            }
+            (
+                Operation::SetImage { after, .. },
+                Operation::SetImage { after: next_after, .. },
+            ) if after.id == next_after.id => {
+                *after = next_after.clone();
+                true
+            }
+            (
+                Operation::SetTable { after, .. },
+                Operation::SetTable { after: next_after, .. },
+            ) if after.id == next_after.id => {
+                *after = next_after.clone();
+                true
+            }
+            (Operation::SetStyles { after, .. }, Operation::SetStyles { after: next_after, .. }) => {
+                *after = next_after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A group of operations that is undone and redone as a unit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Operations in the order they were applied
+    pub operations: Vec<Operation>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Transaction { operations: Vec::new() }
+    }
+
+    /// Check if the transaction contains no operations
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Append an operation, folding it into the previous one when possible
+    pub fn push(&mut self, operation: Operation) {
+        if let Some(last) = self.operations.last_mut() {
+            if last.merge(&operation) {
+                return;
+            }
+        }
+        self.operations.push(operation);
+    }
+
+    /// Get the transaction that reverts this one
+    pub fn invert(&self) -> Transaction {
+        Transaction {
+            operations: self.operations.iter().rev().map(Operation::invert).collect(),
+        }
+    }
+}
+
+/// Undo and redo stacks
+#[derive(Debug, Clone)]
+pub struct History {
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    /// Transaction collecting operations while a group is open
+    group: Option<Transaction>,
+    group_depth: usize,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::with_limit(DEFAULT_HISTORY_LIMIT)
+    }
+
+    /// Create a history that keeps at most `limit` undo steps
+    pub fn with_limit(limit: usize) -> Self {
+        History {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            group: None,
+            group_depth: 0,
+            limit: limit.max(1),
+        }
+    }
+
+    /// Record an operation that has been applied to the document
+    ///
+    /// Recording a new change discards the redo stack.
+    pub fn record(&mut self, operation: Operation) {
+        self.redo_stack.clear();
+        match self.group.as_mut() {
+            Some(group) => group.push(operation),
+            None => {
+                let mut transaction = Transaction::new();
+                transaction.push(operation);
+                self.push_undo(transaction);
+            }
+        }
+    }
+
+    /// Start grouping operations into a single transaction
+    pub fn begin_group(&mut self) {
+        if self.group_depth == 0 {
+            self.group = Some(Transaction::new());
+        }
+        self.group_depth += 1;
+    }
+
+    /// Close the current group; the outermost call commits the transaction
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth == 0 {
+            if let Some(group) = self.group.take() {
+                if !group.is_empty() {
+                    self.push_undo(group);
+                }
+            }
+        }
+    }
+
+    /// Check if a group is currently open
+    pub fn in_group(&self) -> bool {
+        self.group_depth > 0
+    }
+
+    /// Pop the transaction to undo, moving it to the redo stack
+    ///
+    /// Any open group is closed first. The caller applies the inverse of the
+    /// returned transaction.
+    pub fn undo(&mut self) -> Option<Transaction> {
+        self.close_groups();
+        let transaction = self.undo_stack.pop()?;
+        self.redo_stack.push(transaction.clone());
+        Some(transaction)
+    }
+
+    /// Pop the transaction to redo, moving it back to the undo stack
+    ///
+    /// The caller applies the returned transaction as is.
+    pub fn redo(&mut self) -> Option<Transaction> {
+        self.close_groups();
+        let transaction = self.redo_stack.pop()?;
+        self.undo_stack.push(transaction.clone());
+        Some(transaction)
+    }
+
+    /// Check if there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty() || self.group.as_ref().is_some_and(|g| !g.is_empty())
+    }
+
+    /// Check if there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Number of transactions on the undo stack
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Number of transactions on the redo stack
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    /// Drop all history
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group = None;
+        self.group_depth = 0;
+    }
+
+    fn close_groups(&mut self) {
+        if self.group_depth > 0 {
+            self.group_depth = 1;
+            self.end_group();
+        }
+    }
+
+    fn push_undo(&mut self, transaction: Transaction) {
+        self.undo_stack.push(transaction);
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.remove(0);
+        }
+    }
+}
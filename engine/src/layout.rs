@@ -16,10 +16,14 @@
 //! ```text
 //! Document → compute_layout() → Vec<DisplayLine>
 //!                    ↓
-//!            1. layout_paragraph()    - Wrap each paragraph into lines
-//!            2. assign_page_positions() - Assign page/column/y positions
+//!            1. layout_paragraph()  - Wrap each paragraph into lines
+//!            2. PageCursor::place() - Assign page/column/y positions
 //! ```
 //!
+//! Every line carries its own height, ascent and descent, taken from the font
+//! it is set in, so headings and paragraphs with a larger font size get taller
+//! line boxes.
+//!
 //! # Configuration
 //!
 //! Layout is controlled by `LayoutConfig` which specifies:
@@ -42,9 +46,14 @@
 //! - `get_page_for_position()`: Find which page contains a position
 
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
 
-use crate::document::{BlockType, Document, DocumentTable, HorizontalAlign, ImagePositionMode, ImageWrapStyle, ListType, Paragraph, TableWidthMode};
+use crate::document::{
+    expand_number_template, BlockType, CapsStyle, CellVerticalAlign, Document, DocumentTable, HorizontalAlign,
+    ImagePositionMode, ImageWrapStyle, LineSpacing, ListType, NumberFormat, Paragraph, ParagraphIndent, ParagraphMeta,
+    TableCell, TableWidthMode, TextAlign, TextStyle, VerticalAlign, MAX_LIST_LEVEL, SCRIPT_SIZE_RATIO,
+    SMALL_CAPS_SIZE_RATIO,
+};
+use crate::measure::{FontSpec, TextMeasurer, FONT_WEIGHT_BOLD};
 
 /// Configuration for page layout
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +74,8 @@ pub struct LayoutConfig {
     pub columns: u8,
     /// Gap between columns in pixels
     pub column_gap: f64,
+    /// Default font family, for text without a family of its own
+    pub font_family: String,
     /// Base font size in pixels
     pub font_size: f64,
     /// Line height multiplier
@@ -73,6 +84,9 @@ pub struct LayoutConfig {
     pub letter_spacing: f64,
     /// Paragraph spacing in pixels
     pub paragraph_spacing: f64,
+    /// Draw ticked checklist items struck through
+    #[serde(default)]
+    pub strike_checked_items: bool,
 }
 
 impl Default for LayoutConfig {
@@ -86,10 +100,12 @@ impl Default for LayoutConfig {
             margin_left: 96.0,
             columns: 1,
             column_gap: 48.0,
+            font_family: "Arial".to_string(),
             font_size: 16.0,
             line_height: 1.5,
             letter_spacing: 0.0,
             paragraph_spacing: 12.0,
+            strike_checked_items: true,
         }
     }
 }
@@ -137,6 +153,23 @@ pub struct DisplayLine {
     pub x_position: f64,
     /// Y position on the page
     pub y_position: f64,
+    /// Height of the line box in pixels, including line spacing
+    #[serde(default)]
+    pub height: f64,
+    /// Distance from the baseline to the top of the line's tallest font
+    #[serde(default)]
+    pub ascent: f64,
+    /// Distance from the baseline to the bottom of the line's tallest font
+    #[serde(default)]
+    pub descent: f64,
+    /// X offset of each character boundary from the start of the line's text
+    /// (one entry more than the line has characters). Not serialized.
+    #[serde(skip)]
+    pub char_positions: Vec<f64>,
+    /// Fonts of the line's text, as character ranges of the line. Text lines
+    /// always have at least one run. Not serialized.
+    #[serde(skip)]
+    pub runs: Vec<LineRun>,
     /// Whether this is a page break marker
     pub is_page_break: bool,
     /// Whether this is an image line
@@ -153,6 +186,12 @@ pub struct DisplayLine {
     pub block_type: BlockType,
     /// List type for markers
     pub list_type: ListType,
+    /// Nesting level of the list item
+    #[serde(default)]
+    pub list_level: u8,
+    /// List marker text ("•", "1.", "a.", ...), on the first line of a list item
+    #[serde(default)]
+    pub list_marker: Option<String>,
     /// Float reduction for text wrapping around images
     pub float_reduction: Option<FloatReduction>,
     /// Whether this is a table line
@@ -164,6 +203,111 @@ pub struct DisplayLine {
     /// Computed table layout (for rendering)
     #[serde(default)]
     pub table_layout: Option<TableLayout>,
+    /// Part of the table on this line when the table is split across columns
+    /// or pages (None = the whole table)
+    #[serde(default)]
+    pub table_fragment: Option<TableFragment>,
+}
+
+impl DisplayLine {
+    /// A zero-height line with no text that stands in for paragraph
+    /// `para_index` (a float image, a page break, ...). Table and inline
+    /// image lines start from one and set their height.
+    pub fn marker(para_index: usize, end_offset: usize, meta: &ParagraphMeta) -> Self {
+        DisplayLine {
+            para_index,
+            start_offset: 0,
+            end_offset,
+            text: String::new(),
+            page_index: 0,
+            column_index: 0,
+            x_position: 0.0,
+            y_position: 0.0,
+            height: 0.0,
+            ascent: 0.0,
+            descent: 0.0,
+            char_positions: Vec::new(),
+            runs: Vec::new(),
+            is_page_break: false,
+            is_image: false,
+            image_id: None,
+            image_height: Some(0.0),
+            list_number: None,
+            is_last_line: true,
+            block_type: meta.block_type,
+            list_type: ListType::None,
+            list_level: 0,
+            list_marker: None,
+            float_reduction: None,
+            is_table: false,
+            table_id: None,
+            table_layout: None,
+            table_fragment: None,
+        }
+    }
+
+    /// Offset of the baseline from the top of the line box
+    ///
+    /// Extra line spacing is split evenly above and below the glyphs.
+    pub fn baseline(&self) -> f64 {
+        (self.height - self.ascent - self.descent) / 2.0 + self.ascent
+    }
+
+    /// X offset of the boundary before character `index` of the line,
+    /// relative to the start of the line's text
+    pub fn char_x(&self, index: usize) -> f64 {
+        self.char_positions
+            .get(index)
+            .or(self.char_positions.last())
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Width of the line's text, not counting trailing spaces
+    pub fn content_width(&self) -> f64 {
+        let trimmed = self.text.trim_end_matches(' ').chars().count();
+        self.char_x(trimmed)
+    }
+
+    /// Font run of the character before boundary `index` (the first run at
+    /// the start of the line), i.e. the font that text typed there gets
+    pub fn run_at(&self, index: usize) -> Option<&LineRun> {
+        let char_index = index.saturating_sub(1);
+        self.runs
+            .iter()
+            .find(|run| char_index < run.end)
+            .or(self.runs.last())
+    }
+}
+
+/// Characters of a display line that share a font
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineRun {
+    /// Start character index within the line (inclusive)
+    pub start: usize,
+    /// End character index within the line (exclusive)
+    pub end: usize,
+    /// Font the characters are measured and drawn with
+    pub font: FontSpec,
+    /// Ascent above the line's baseline, including any superscript rise
+    pub ascent: f64,
+    /// Descent below the line's baseline, including any subscript drop
+    pub descent: f64,
+    /// Distance the run's own baseline is raised (negative = lowered)
+    pub rise: f64,
+    /// The characters are measured and drawn in uppercase (caps transforms)
+    pub uppercase: bool,
+}
+
+impl LineRun {
+    /// Text of the run as drawn, with its caps transform applied
+    pub fn display_text(&self, text: &str) -> String {
+        if self.uppercase {
+            text.to_uppercase()
+        } else {
+            text.to_string()
+        }
+    }
 }
 
 /// Describes width reduction due to a floating image
@@ -197,25 +341,58 @@ pub struct TableLayout {
     pub total_height: f64,
     /// Total table width in pixels
     pub total_width: f64,
-    /// Cell text layouts (row, col) -> wrapped lines
-    pub cell_lines: Vec<Vec<Vec<String>>>,
+    /// Laid out content of each cell (row, col), placed relative to the
+    /// cell's text area inside its border and padding. Covered cells have no
+    /// lines, and the `para_index` of a line is that of the cell paragraph.
+    pub cell_lines: Vec<Vec<Vec<DisplayLine>>>,
+}
+
+/// The part of a table shown by one display line of a split table
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableFragment {
+    /// Top and bottom of the part, in pixels from the top of the table
+    pub start: f64,
+    pub end: f64,
+    /// Height of the header rows repeated above the part (0 = none)
+    pub header_height: f64,
+}
+
+impl TableFragment {
+    /// Ranges of the table drawn on the line from top to bottom, in pixels
+    /// from the top of the table
+    pub fn bands(&self) -> Vec<(f64, f64)> {
+        let mut bands = Vec::with_capacity(2);
+        if self.header_height > 0.0 {
+            bands.push((0.0, self.header_height));
+        }
+        bands.push((self.start, self.end));
+        bands
+    }
+
+    /// Position in the table of the point `y` pixels below the top of the line
+    pub fn table_y(&self, y: f64) -> f64 {
+        if y < self.header_height {
+            y
+        } else {
+            self.start + y - self.header_height
+        }
+    }
 }
 
 /// Active floating image for text wrapping
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ActiveFloat {
     pub id: String,
-    /// For move-with-text floats: line indices
-    pub start_line: usize,
-    pub end_line: usize,
     pub width: f64,
     pub side: FloatSide,
-    /// For positioned floats, the page they're on
+    /// The page the float is on
     pub page_index: Option<usize>,
-    /// For fixed-position floats: Y coordinates (relative to margin)
-    pub y_start: Option<f64>,
-    pub y_end: Option<f64>,
-    /// X position of the float (relative to margin, for fixed-position floats)
+    /// Y coordinates covered by the float (relative to margin)
+    pub y_start: f64,
+    pub y_end: f64,
+    /// X position of the float (relative to margin, for fixed-position floats).
+    /// Move-with-text floats are aligned to their side of the column.
     pub x_position: Option<f64>,
 }
 
@@ -227,27 +404,111 @@ fn align_to_float_side(align: HorizontalAlign) -> FloatSide {
     }
 }
 
-/// Text measurement function signature (called from JS)
-type MeasureFn<'a> = &'a js_sys::Function;
+/// Indent of a list item's text: one step of 1.5em per nesting level
+pub fn list_indent(list_type: ListType, level: u8, font_size: f64) -> f64 {
+    if list_type == ListType::None {
+        return 0.0;
+    }
+    (level.min(MAX_LIST_LEVEL) as f64 + 1.0) * font_size * 1.5
+}
+
+/// Formatting of a paragraph after resolving its named style, its direct
+/// formatting and the layout defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphFormat {
+    pub align: TextAlign,
+    /// Font of text without inline styles
+    pub font: FontSpec,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// Text color (None = the default text color)
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub indent: ParagraphIndent,
+    /// Space above and below the paragraph in pixels
+    pub space_before: f64,
+    pub space_after: f64,
+    pub line_spacing: LineSpacing,
+    /// Pagination rules, see `ParagraphMeta`
+    pub keep_with_next: bool,
+    pub keep_together: bool,
+    pub page_break_before: bool,
+    pub widows: usize,
+    pub orphans: usize,
+}
+
+/// Minimum number of lines of a split paragraph on either side of the break,
+/// unless its style or metadata says otherwise
+pub const DEFAULT_WIDOW_ORPHAN_LINES: u8 = 2;
+
+/// Resolve the effective formatting of a paragraph with metadata `meta`
+///
+/// The paragraph's style chain supplies everything its direct formatting
+/// (`align`, `font_size`, `text_color`, spacing, pagination) leaves unset; the layout's
+/// font size, family, line height and paragraph spacing fill in the rest.
+pub fn paragraph_format(document: &Document, meta: &ParagraphMeta, config: &LayoutConfig) -> ParagraphFormat {
+    let style = document.resolve_style(meta.effective_style_id());
+    let size = meta.font_size.or(style.font_size).unwrap_or(config.font_size) * style.font_scale.unwrap_or(1.0);
+    let mut font = FontSpec::with_style(size, style.bold.unwrap_or(false), style.italic.unwrap_or(false))
+        .with_family(style.font_family.as_deref().unwrap_or(&config.font_family));
+    if let Some(weight) = style.font_weight {
+        font.weight = weight;
+    }
+    ParagraphFormat {
+        align: meta.align.or(style.align).unwrap_or_default(),
+        font,
+        underline: style.underline.unwrap_or(false),
+        strikethrough: style.strikethrough.unwrap_or(false),
+        color: meta.text_color.clone().or(style.color),
+        background: style.background,
+        indent: meta.indent,
+        space_before: meta.space_before.or(style.space_before).unwrap_or(0.0),
+        space_after: meta.space_after.or(style.space_after).unwrap_or(config.paragraph_spacing),
+        line_spacing: meta
+            .line_spacing
+            .or(style.line_spacing)
+            .unwrap_or(LineSpacing::Multiple(config.line_height)),
+        keep_with_next: meta.keep_with_next.or(style.keep_with_next).unwrap_or(false),
+        keep_together: meta.keep_together.or(style.keep_together).unwrap_or(false),
+        page_break_before: meta.page_break_before.or(style.page_break_before).unwrap_or(false),
+        widows: meta.widows.or(style.widows).unwrap_or(DEFAULT_WIDOW_ORPHAN_LINES).max(1) as usize,
+        orphans: meta.orphans.or(style.orphans).unwrap_or(DEFAULT_WIDOW_ORPHAN_LINES).max(1) as usize,
+    }
+}
+
+/// Shared inputs for a layout pass
+struct LayoutContext<'a, M: TextMeasurer + ?Sized> {
+    document: &'a Document,
+    config: &'a LayoutConfig,
+    measurer: &'a M,
+    /// Number of table cells the content being laid out is nested in
+    depth: usize,
+}
+
+/// Deepest nesting of tables in table cells that is laid out. Only a
+/// document with a table inside itself goes deeper.
+const MAX_TABLE_NESTING: usize = 8;
 
 /// Compute the layout for the entire document
-pub fn compute_layout(
+pub fn compute_layout<M: TextMeasurer + ?Sized>(
     document: &Document,
     config: &LayoutConfig,
-    measure_fn: MeasureFn,
+    measurer: &M,
 ) -> Vec<DisplayLine> {
     let mut display_lines: Vec<DisplayLine> = Vec::new();
-    let mut active_floats: Vec<ActiveFloat> = Vec::new();
-    let mut list_counters: Vec<usize> = Vec::new();
+    update_layout(document, config, measurer, &mut LayoutCache::new(), &mut display_lines);
+    display_lines
+}
 
-    // Pre-pass: Collect all images with fixed positions (already positioned floats)
-    // These affect text layout based on their absolute Y position
+/// Collect all images with fixed positions (already positioned floats)
+/// These affect text layout based on their absolute Y position
+fn collect_fixed_floats(document: &Document, config: &LayoutConfig) -> Vec<ActiveFloat> {
+    let mut floats = Vec::new();
     for image in &document.images {
-        if image.wrap_style.is_float()
-            && image.position_mode == ImagePositionMode::FixedPosition
-            && image.y.is_some()
-        {
-            let y = image.y.unwrap();
+        if !image.wrap_style.is_float() || image.position_mode != ImagePositionMode::FixedPosition {
+            continue;
+        }
+        if let Some(y) = image.y {
             let x = image.x.unwrap_or(0.0);
             let image_height = image.cropped_height();
             let image_width = image.width.min(config.column_width());
@@ -264,108 +525,616 @@ pub fn compute_layout(
 
             // Store Y coordinates for fixed-position floats
             // These will be checked against line Y positions during layout
-            active_floats.push(ActiveFloat {
+            floats.push(ActiveFloat {
                 id: image.id.clone(),
-                start_line: 0, // Not used for fixed-position
-                end_line: 0,   // Not used for fixed-position
                 width: image_width,
                 side,
                 page_index: image.page_index,
-                y_start: Some(y),
-                y_end: Some(y + image_height),
+                y_start: y,
+                y_end: y + image_height,
                 x_position: Some(x),
             });
         }
     }
+    floats
+}
+
+// ============================================================================
+// Incremental Layout
+// ============================================================================
+
+/// Layout state carried from one paragraph to the next
+#[derive(Clone)]
+struct FlowState {
+    active_floats: Vec<ActiveFloat>,
+    list_counters: ListCounters,
+}
+
+/// Counter of one list nesting level
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct ListCounter {
+    value: usize,
+    format: NumberFormat,
+}
 
-    // First pass: Generate display lines for each paragraph
-    for (para_idx, para) in document.paragraphs.iter().enumerate() {
-        let lines = layout_paragraph(
-            para_idx,
-            para,
-            document,
-            config,
-            measure_fn,
-            &mut active_floats,
-            &mut list_counters,
-            display_lines.len(),
-        );
-        display_lines.extend(lines);
+/// List numbering state: one counter per nesting level
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ListCounters {
+    levels: Vec<ListCounter>,
+    /// Counters of the last list, kept across non-list paragraphs so a
+    /// `list_continue` item can pick up its numbering
+    previous: Vec<ListCounter>,
+}
+
+impl ListCounters {
+    /// Advance the counters for a paragraph, returning its number and marker
+    ///
+    /// Entering a level restarts the counters below it, so numbering of a
+    /// level continues after a nested sublist ends.
+    fn advance(&mut self, meta: &ParagraphMeta) -> (Option<usize>, Option<String>) {
+        let level = meta.list_level.min(MAX_LIST_LEVEL) as usize;
+        match meta.list_type {
+            ListType::Numbered => {
+                if self.levels.is_empty() && meta.list_continue {
+                    self.levels = std::mem::take(&mut self.previous);
+                }
+                self.levels.truncate(level + 1);
+                self.levels.resize(level + 1, ListCounter::default());
+                let format = meta.number_format.unwrap_or_else(|| NumberFormat::for_level(level as u8));
+                let counter = &mut self.levels[level];
+                counter.value = meta.list_start.unwrap_or(counter.value + 1);
+                counter.format = format;
+                let number = counter.value;
+
+                let marker = match &meta.number_template {
+                    Some(template) => {
+                        let numbers: Vec<String> = self.levels.iter().map(|c| c.format.format(c.value)).collect();
+                        expand_number_template(template, &numbers)
+                    }
+                    None => format!("{}.", format.format(number)),
+                };
+                (Some(number), Some(marker))
+            }
+            ListType::Bullet | ListType::Checklist => {
+                self.levels.truncate(level + 1);
+                self.levels.resize(level + 1, ListCounter::default());
+                self.levels[level].value = 0;
+                let marker = if meta.checked && meta.list_type == ListType::Checklist {
+                    Some("☑".to_string())
+                } else {
+                    meta.list_type.marker(level as u8, 0)
+                };
+                (None, marker)
+            }
+            ListType::None => {
+                if !self.levels.is_empty() {
+                    self.previous = std::mem::take(&mut self.levels);
+                }
+                (None, None)
+            }
+        }
     }
+}
 
-    // Second pass: Assign page and column positions
-    assign_page_positions(&mut display_lines, config);
+/// Pagination cursor: where the next display line will be placed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct PageCursor {
+    page: usize,
+    column: usize,
+    y: f64,
+}
 
-    display_lines
+impl PageCursor {
+    /// Move to the next column or page if a line of `height` does not fit
+    fn fit(&mut self, height: f64, config: &LayoutConfig) {
+        if self.y + height > config.content_height() {
+            self.next_column(config);
+        }
+    }
+
+    /// Move to the top of the next column, or of the next page after the last one
+    fn next_column(&mut self, config: &LayoutConfig) {
+        if config.columns > 1 && self.column < (config.columns - 1) as usize {
+            self.column += 1;
+        } else {
+            self.page += 1;
+            self.column = 0;
+        }
+        self.y = 0.0;
+    }
+
+    /// Move to the top of the next page, unless already there
+    fn next_page(&mut self) {
+        if self.column > 0 || self.y > 0.0 {
+            self.page += 1;
+            self.column = 0;
+            self.y = 0.0;
+        }
+    }
+
+    /// Add the space above a paragraph, unless at the top of a column where
+    /// it collapses
+    fn space_before(&mut self, space: f64) {
+        if self.y > 0.0 {
+            self.y += space;
+        }
+    }
+
+    /// Assign page, column and position to a display line and advance
+    fn place(&mut self, dl: &mut DisplayLine, config: &LayoutConfig) {
+        // Handle page breaks
+        if dl.is_page_break {
+            dl.page_index = self.page;
+            dl.y_position = self.y;
+            dl.column_index = self.column;
+            // Move to next page
+            self.page += 1;
+            self.column = 0;
+            self.y = 0.0;
+            return;
+        }
+
+        self.fit(dl.height, config);
+
+        // Assign position
+        dl.page_index = self.page;
+        dl.column_index = self.column;
+        dl.y_position = self.y;
+
+        // Calculate X position
+        let column_offset = self.column as f64 * (config.column_width() + config.column_gap);
+        dl.x_position = config.margin_left + column_offset;
+
+        // Advance Y
+        self.y += dl.height;
+    }
+
+    /// Place a paragraph's lines, honouring its widow, orphan and
+    /// keep-together rules
+    ///
+    /// Returns false, leaving the cursor where it was, if the paragraph should
+    /// start in the next column instead. A paragraph that already starts at
+    /// the top of a column is split wherever it has to be.
+    fn place_paragraph(&mut self, lines: &mut [DisplayLine], format: &ParagraphFormat, config: &LayoutConfig) -> bool {
+        let mut forced_break = None;
+        loop {
+            let mut cursor = *self;
+            for (i, dl) in lines.iter_mut().enumerate() {
+                if forced_break == Some(i) {
+                    cursor.next_column(config);
+                }
+                cursor.place(dl, config);
+            }
+
+            // Lines that start a new column; tables break wherever they have to
+            let splits: Vec<usize> = if lines.first().is_some_and(|dl| dl.is_table) {
+                Vec::new()
+            } else {
+                (1..lines.len())
+                    .filter(|&i| (lines[i].page_index, lines[i].column_index) != (lines[i - 1].page_index, lines[i - 1].column_index))
+                    .collect()
+            };
+            let (Some(&first), Some(&last)) = (splits.first(), splits.last()) else {
+                *self = cursor;
+                return true;
+            };
+            let movable = lines[0].y_position > 0.0;
+            if movable && (format.keep_together || first < format.orphans) {
+                return false;
+            }
+            if forced_break.is_none() && lines.len() - last < format.widows {
+                // Carry lines over from the previous column, leaving enough behind
+                let previous = splits.iter().rev().nth(1).copied().unwrap_or(0);
+                let min_left = if previous == 0 { format.orphans } else { 1 };
+                let break_at = lines.len().saturating_sub(format.widows);
+                if break_at >= previous + min_left {
+                    forced_break = Some(break_at);
+                    continue;
+                }
+                if previous == 0 && movable {
+                    return false;
+                }
+            }
+            *self = cursor;
+            return true;
+        }
+    }
+}
+
+/// Layout state captured at the start of a paragraph
+#[derive(Debug, Clone, PartialEq)]
+struct ParagraphEntry {
+    list_counters: ListCounters,
+    /// Move-with-text floats that can still affect the following lines
+    floats: Vec<ActiveFloat>,
+    cursor: PageCursor,
+}
+
+impl ParagraphEntry {
+    fn capture(state: &FlowState, cursor: PageCursor) -> Self {
+        ParagraphEntry {
+            list_counters: state.list_counters.clone(),
+            floats: state
+                .active_floats
+                .iter()
+                .filter(|f| {
+                    f.x_position.is_none() && f.page_index == Some(cursor.page) && f.y_end > cursor.y
+                })
+                .cloned()
+                .collect(),
+            cursor,
+        }
+    }
+
+    /// Rebuild the flow state at the start of the paragraph
+    fn restore(&self, fixed_floats: &[ActiveFloat]) -> FlowState {
+        let mut active_floats = fixed_floats.to_vec();
+        active_floats.extend(self.floats.iter().cloned());
+        FlowState {
+            active_floats,
+            list_counters: self.list_counters.clone(),
+        }
+    }
+}
+
+/// Cached layout information for one paragraph
+#[derive(Debug, Clone)]
+struct CachedParagraph {
+    /// Paragraph content changed since the last layout
+    dirty: bool,
+    /// Number of display lines attributed to this paragraph in the current layout
+    line_count: usize,
+    /// State at the start of the paragraph during the last layout
+    entry: Option<ParagraphEntry>,
+}
+
+impl CachedParagraph {
+    fn new_dirty() -> Self {
+        CachedParagraph { dirty: true, line_count: 0, entry: None }
+    }
+}
+
+/// Per-paragraph layout cache used for incremental relayout
+///
+/// The cache mirrors `Document::paragraphs`: every structural edit of the
+/// document must be reported through `insert_paragraph`/`remove_paragraph`,
+/// and every content edit through `mark_dirty`. Anything that affects the whole
+/// document (page setup, fonts, positioned floats, loading a document) should
+/// call `invalidate`.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutCache {
+    paragraphs: Vec<CachedParagraph>,
+    valid: bool,
+}
+
+impl LayoutCache {
+    pub fn new() -> Self {
+        LayoutCache { paragraphs: Vec::new(), valid: false }
+    }
+
+    /// Force a full relayout on the next update
+    pub fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    /// Check if the next update has any work to do
+    pub fn needs_layout(&self) -> bool {
+        !self.valid || self.paragraphs.iter().any(|p| p.dirty)
+    }
+
+    /// Mark a paragraph's content as changed
+    pub fn mark_dirty(&mut self, index: usize) {
+        match self.paragraphs.get_mut(index) {
+            Some(p) => p.dirty = true,
+            None => self.valid = false,
+        }
+    }
+
+    /// Record that a paragraph was inserted at `index`
+    pub fn insert_paragraph(&mut self, index: usize) {
+        if !self.valid || index > self.paragraphs.len() {
+            self.valid = false;
+            return;
+        }
+        self.paragraphs.insert(index, CachedParagraph::new_dirty());
+    }
+
+    /// Record that the paragraph at `index` was removed
+    pub fn remove_paragraph(&mut self, index: usize) {
+        if !self.valid || index >= self.paragraphs.len() {
+            self.valid = false;
+            return;
+        }
+        // The removed paragraph's lines are still in the display list; attribute
+        // them to a neighbour that is relaid out, so they get replaced.
+        // The following paragraph now starts where the removed one did.
+        let removed = self.paragraphs.remove(index);
+        let p = if index < self.paragraphs.len() {
+            let p = &mut self.paragraphs[index];
+            p.entry = removed.entry;
+            p
+        } else if index > 0 {
+            &mut self.paragraphs[index - 1]
+        } else {
+            self.valid = false;
+            return;
+        };
+        p.line_count += removed.line_count;
+        p.dirty = true;
+    }
+}
+
+/// Summary of an incremental layout update
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutUpdate {
+    /// Pages whose content or positions changed, sorted
+    pub changed_pages: Vec<usize>,
+    /// Index of the first paragraph that was laid out again
+    pub first_paragraph: usize,
+    /// Number of paragraphs that were laid out again
+    pub paragraphs_laid_out: usize,
+}
+
+/// Incrementally update `display_lines` for the paragraphs marked in `cache`
+///
+/// Layout starts at the first dirty paragraph and stops as soon as a clean
+/// paragraph is reached with the same list, float and page state as in the
+/// previous layout: from there on the old display lines are still correct.
+pub fn update_layout<M: TextMeasurer + ?Sized>(
+    document: &Document,
+    config: &LayoutConfig,
+    measurer: &M,
+    cache: &mut LayoutCache,
+    display_lines: &mut Vec<DisplayLine>,
+) -> LayoutUpdate {
+    let ctx = LayoutContext { document, config, measurer, depth: 0 };
+    let fixed_floats = collect_fixed_floats(document, config);
+
+    if cache.paragraphs.len() != document.paragraphs.len() {
+        cache.valid = false;
+    }
+    let full = !cache.valid;
+    if full {
+        cache.paragraphs = (0..document.paragraphs.len())
+            .map(|_| CachedParagraph::new_dirty())
+            .collect();
+        cache.valid = true;
+    }
+
+    // Each region runs from a dirty paragraph to the point where layout converges
+    let mut update: Option<LayoutUpdate> = None;
+    while let Some(region) = update_region(&ctx, &fixed_floats, cache, display_lines, full) {
+        update = Some(match update {
+            None => region,
+            Some(mut total) => {
+                total.changed_pages.extend(region.changed_pages);
+                total.changed_pages.sort_unstable();
+                total.changed_pages.dedup();
+                total.first_paragraph = total.first_paragraph.min(region.first_paragraph);
+                total.paragraphs_laid_out += region.paragraphs_laid_out;
+                total
+            }
+        });
+    }
+    update.unwrap_or_default()
+}
+
+/// Relayout the region starting at the first dirty paragraph
+///
+/// Returns `None` if no paragraph is dirty.
+fn update_region<M: TextMeasurer + ?Sized>(
+    ctx: &LayoutContext<M>,
+    fixed_floats: &[ActiveFloat],
+    cache: &mut LayoutCache,
+    display_lines: &mut Vec<DisplayLine>,
+    full: bool,
+) -> Option<LayoutUpdate> {
+    let document = ctx.document;
+    let config = ctx.config;
+    let para_count = document.paragraphs.len();
+    let mut first = cache.paragraphs.iter().position(|p| p.dirty)?;
+    // Newly inserted paragraphs have no captured state: start at the closest
+    // preceding paragraph that has one. Paragraphs kept with the next one
+    // are placed depending on it, so they are laid out again as well.
+    while first > 0
+        && (cache.paragraphs[first].entry.is_none()
+            || paragraph_format(document, &document.paragraphs[first - 1].meta, config).keep_with_next)
+    {
+        first -= 1;
+    }
+
+    // Restore the state at the first dirty paragraph
+    let line_offset = if full {
+        0
+    } else {
+        cache.paragraphs[..first].iter().map(|p| p.line_count).sum()
+    };
+    let (mut state, mut cursor) = match cache.paragraphs[first].entry.as_ref() {
+        Some(entry) => (entry.restore(fixed_floats), entry.cursor),
+        None => (
+            FlowState {
+                active_floats: fixed_floats.to_vec(),
+                list_counters: ListCounters::default(),
+            },
+            PageCursor::default(),
+        ),
+    };
+
+    let mut old_region_len = 0;
+    let mut new_lines: Vec<DisplayLine> = Vec::new();
+
+    let mut para_idx = first;
+    while para_idx < para_count {
+        let entry = ParagraphEntry::capture(&state, cursor);
+        let cached = &cache.paragraphs[para_idx];
+        if para_idx > first
+            && !cached.dirty
+            && cached.entry.as_ref() == Some(&entry)
+        {
+            break;
+        }
+        old_region_len += cached.line_count;
+
+        let para = &document.paragraphs[para_idx];
+        let format = paragraph_format(document, &para.meta, config);
+        if format.page_break_before {
+            cursor.next_page();
+        }
+        // Space before moves the paragraph down; if its first line then does
+        // not fit, it starts the next column without the space
+        cursor.space_before(format.space_before);
+        let entry_state = state.clone();
+        let mut lines = layout_paragraph(ctx, para_idx, para, &format, &mut state, cursor);
+        let mut placed = cursor;
+        let mut fits = placed.place_paragraph(&mut lines, &format, config);
+        if fits && format.keep_with_next && lines.first().is_some_and(|dl| dl.y_position > 0.0) {
+            // Move to the next column if the start of the following paragraphs
+            // does not fit below this one, but would together with it there
+            let content_height = config.content_height();
+            let height: f64 = lines.iter().map(|dl| dl.height).sum();
+            let next = keep_with_next_height(ctx, para_idx + 1, &state, placed) + format.space_after;
+            fits = placed.y + next <= content_height || height + next > content_height;
+        }
+        if !fits {
+            // Lay the paragraph out again in the next column, so floats are
+            // checked against its new position
+            cursor.next_column(config);
+            state = entry_state;
+            lines = layout_paragraph(ctx, para_idx, para, &format, &mut state, cursor);
+            placed = cursor;
+            placed.place_paragraph(&mut lines, &format, config);
+        }
+        cursor = placed;
+        // Space after, but not for zero-height image markers or page breaks
+        if lines.last().is_some_and(|dl| dl.is_last_line && dl.height > 0.0 && !dl.is_page_break) {
+            cursor.y += format.space_after;
+        }
+
+        cache.paragraphs[para_idx] = CachedParagraph {
+            dirty: false,
+            line_count: lines.len(),
+            entry: Some(entry),
+        };
+        new_lines.extend(lines);
+        para_idx += 1;
+    }
+
+    // Pages touched by the old and the new lines of the relaid region
+    let old_region_end = if full { display_lines.len() } else { line_offset + old_region_len };
+    let mut changed_pages: Vec<usize> = display_lines[line_offset..old_region_end]
+        .iter()
+        .chain(new_lines.iter())
+        .map(|dl| dl.page_index)
+        .collect();
+    if full {
+        let old_pages = display_lines.iter().map(|dl| dl.page_index + 1).max().unwrap_or(1);
+        let new_pages = new_lines.iter().map(|dl| dl.page_index + 1).max().unwrap_or(1);
+        changed_pages.extend(0..old_pages.max(new_pages));
+    }
+    changed_pages.sort_unstable();
+    changed_pages.dedup();
+
+    let mut line = line_offset + new_lines.len();
+    display_lines.splice(line_offset..old_region_end, new_lines);
+
+    // Paragraph indices of the untouched tail may have shifted
+    if display_lines.get(line).is_some_and(|dl| dl.para_index != para_idx) {
+        for (index, cached) in cache.paragraphs.iter().enumerate().skip(para_idx) {
+            for dl in &mut display_lines[line..line + cached.line_count] {
+                dl.para_index = index;
+            }
+            line += cached.line_count;
+        }
+    }
+
+    Some(LayoutUpdate {
+        changed_pages,
+        first_paragraph: first,
+        paragraphs_laid_out: para_idx - first,
+    })
+}
+
+/// Height the paragraphs from `para_idx` on need below a paragraph that is
+/// kept with them: the first lines of the next paragraph (all of them if it
+/// is kept together), and more of the following ones while they are kept
+/// with their next in turn
+fn keep_with_next_height<M: TextMeasurer + ?Sized>(
+    ctx: &LayoutContext<M>,
+    para_idx: usize,
+    state: &FlowState,
+    cursor: PageCursor,
+) -> f64 {
+    let mut state = state.clone();
+    let mut height = 0.0;
+    for (index, para) in ctx.document.paragraphs.iter().enumerate().skip(para_idx) {
+        let format = paragraph_format(ctx.document, &para.meta, ctx.config);
+        if format.page_break_before {
+            break;
+        }
+        let lines = layout_paragraph(ctx, index, para, &format, &mut state, cursor);
+        if lines.first().is_none_or(|dl| dl.is_page_break) {
+            break;
+        }
+        let kept = if lines[0].is_table {
+            1
+        } else if format.keep_together || format.keep_with_next {
+            lines.len()
+        } else {
+            format.orphans.min(lines.len())
+        };
+        height += format.space_before + lines[..kept].iter().map(|dl| dl.height).sum::<f64>();
+        if !format.keep_with_next || height > ctx.config.content_height() {
+            break;
+        }
+        height += format.space_after;
+    }
+    height
 }
 
 /// Layout a single paragraph into display lines
-fn layout_paragraph(
+fn layout_paragraph<M: TextMeasurer + ?Sized>(
+    ctx: &LayoutContext<M>,
     para_idx: usize,
     para: &Paragraph,
-    document: &Document,
-    config: &LayoutConfig,
-    measure_fn: MeasureFn,
-    active_floats: &mut Vec<ActiveFloat>,
-    list_counters: &mut Vec<usize>,
-    current_line_count: usize,
+    format: &ParagraphFormat,
+    state: &mut FlowState,
+    cursor: PageCursor,
 ) -> Vec<DisplayLine> {
+    let document = ctx.document;
+    let config = ctx.config;
     let meta = &para.meta;
 
     // Handle page breaks
     if para.is_page_break() {
         return vec![DisplayLine {
-            para_index: para_idx,
-            start_offset: 0,
-            end_offset: 1,
-            text: String::new(),
-            page_index: 0,
-            column_index: 0,
-            x_position: 0.0,
-            y_position: 0.0,
             is_page_break: true,
-            is_image: false,
-            image_id: None,
             image_height: None,
-            list_number: None,
-            is_last_line: true,
-            block_type: meta.block_type,
             list_type: meta.list_type,
-            float_reduction: None,
-            is_table: false,
-            table_id: None,
-            table_layout: None,
+            ..DisplayLine::marker(para_idx, 1, meta)
         }];
     }
 
     // Handle table paragraphs
     if let Some(table_id) = para.table_id() {
-        if let Some(table) = document.tables.iter().find(|t| t.id == table_id) {
-            let table_layout = compute_table_layout(table, config, measure_fn);
+        let table = document.tables.iter().find(|t| t.id == table_id);
+        if table.is_some() && ctx.depth >= MAX_TABLE_NESTING {
+            // A table nested in itself stops here with an empty marker line
+            return vec![DisplayLine::marker(para_idx, para.text.chars().count(), meta)];
+        }
+        if let Some(table) = table {
+            let table_layout = table_layout(ctx, table);
             let table_height = table_layout.total_height;
 
-            return vec![DisplayLine {
-                para_index: para_idx,
-                start_offset: 0,
-                end_offset: para.text.len(),
-                text: String::new(),
-                page_index: 0,
-                column_index: 0,
-                x_position: 0.0,
-                y_position: 0.0,
-                is_page_break: false,
-                is_image: false,
-                image_id: None,
+            let line = DisplayLine {
+                height: table_height,
                 image_height: Some(table_height / config.line_height_px()), // Convert to line units
-                list_number: None,
-                is_last_line: true,
-                block_type: meta.block_type,
-                list_type: ListType::None,
-                float_reduction: None,
                 is_table: true,
                 table_id: Some(table_id.to_string()),
                 table_layout: Some(table_layout),
-            }];
+                ..DisplayLine::marker(para_idx, para.text.chars().count(), meta)
+            };
+            return split_table(table, line, cursor, config);
         }
     }
 
@@ -376,57 +1145,30 @@ fn layout_paragraph(
             let line_height = config.line_height_px();
             let image_height = image.cropped_height();
 
-            // For float reduction: count all lines that overlap with the image
-            // A line at Y overlaps if Y < image_height (its top is within the image bounds)
-            // Use ceil() to include any line that has partial overlap
-            let float_lines = if image_height <= 0.0 {
-                0
-            } else {
-                (image_height / line_height).ceil() as usize
-            };
-
             // For inline/top-bottom images: use ceil() for vertical space (image occupies full lines)
             let inline_image_lines = (image_height / line_height).ceil();
 
             // Check if this is a float image in move-with-text mode
             if image.wrap_style.is_float() && image.position_mode == ImagePositionMode::MoveWithText {
-                // Register as active float for text lines that overlap with the image
-                // start_line is current_line_count + 1 because the marker at current_line_count
-                // has zero height and doesn't need reduction. Text starts at the next line.
-                active_floats.push(ActiveFloat {
+                // Register as active float for text lines that overlap with the image.
+                // The marker has zero height, so the image starts where the next line does.
+                let mut marker = cursor;
+                marker.fit(0.0, config);
+                state.active_floats.push(ActiveFloat {
                     id: image_id.to_string(),
-                    start_line: current_line_count + 1,
-                    end_line: current_line_count + 1 + float_lines,
                     width: clamped_width,
                     side: align_to_float_side(image.horizontal_align),
-                    page_index: None, // Will be determined during page assignment
-                    y_start: None,    // Line-index based, not Y-based
-                    y_end: None,
+                    page_index: Some(marker.page),
+                    y_start: marker.y,
+                    y_end: marker.y + image_height,
                     x_position: None, // Will be calculated based on alignment during layout
                 });
 
                 // Float images create a zero-height marker line
                 return vec![DisplayLine {
-                    para_index: para_idx,
-                    start_offset: 0,
-                    end_offset: para.text.len(),
-                    text: String::new(),
-                    page_index: 0,
-                    column_index: 0,
-                    x_position: 0.0,
-                    y_position: 0.0,
-                    is_page_break: false,
                     is_image: true,
                     image_id: Some(image_id.to_string()),
-                    image_height: Some(0.0), // Zero height for float - doesn't take up space
-                    list_number: None,
-                    is_last_line: true,
-                    block_type: meta.block_type,
-                    list_type: ListType::None,
-                    float_reduction: None,
-                    is_table: false,
-                    table_id: None,
-                    table_layout: None,
+                    ..DisplayLine::marker(para_idx, para.text.chars().count(), meta)
                 }];
             }
 
@@ -434,116 +1176,54 @@ fn layout_paragraph(
             if image.wrap_style.is_float() && image.position_mode == ImagePositionMode::FixedPosition {
                 // Already registered in pre-pass, just create marker line
                 return vec![DisplayLine {
-                    para_index: para_idx,
-                    start_offset: 0,
-                    end_offset: para.text.len(),
-                    text: String::new(),
-                    page_index: 0,
-                    column_index: 0,
-                    x_position: 0.0,
-                    y_position: 0.0,
-                    is_page_break: false,
                     is_image: true,
                     image_id: Some(image_id.to_string()),
-                    image_height: Some(0.0), // Zero height - position is absolute
-                    list_number: None,
-                    is_last_line: true,
-                    block_type: meta.block_type,
-                    list_type: ListType::None,
-                    float_reduction: None,
-                    is_table: false,
-                    table_id: None,
-                    table_layout: None,
+                    ..DisplayLine::marker(para_idx, para.text.chars().count(), meta)
                 }];
             }
 
             // Behind/in-front images don't affect text flow
             if matches!(image.wrap_style, ImageWrapStyle::Behind | ImageWrapStyle::InFront) {
                 return vec![DisplayLine {
-                    para_index: para_idx,
-                    start_offset: 0,
-                    end_offset: para.text.len(),
-                    text: String::new(),
-                    page_index: 0,
-                    column_index: 0,
-                    x_position: 0.0,
-                    y_position: 0.0,
-                    is_page_break: false,
                     is_image: true,
                     image_id: Some(image_id.to_string()),
-                    image_height: Some(0.0), // Zero height - doesn't affect text flow
-                    list_number: None,
-                    is_last_line: true,
-                    block_type: meta.block_type,
-                    list_type: ListType::None,
-                    float_reduction: None,
-                    is_table: false,
-                    table_id: None,
-                    table_layout: None,
+                    ..DisplayLine::marker(para_idx, para.text.chars().count(), meta)
                 }];
             }
 
             // Inline or top-bottom image: takes up vertical space
             return vec![DisplayLine {
-                para_index: para_idx,
-                start_offset: 0,
-                end_offset: para.text.len(),
-                text: String::new(),
-                page_index: 0,
-                column_index: 0,
-                x_position: 0.0,
-                y_position: 0.0,
-                is_page_break: false,
+                height: inline_image_lines * line_height,
                 is_image: true,
                 image_id: Some(image_id.to_string()),
                 image_height: Some(inline_image_lines),
-                list_number: None,
-                is_last_line: true,
-                block_type: meta.block_type,
-                list_type: ListType::None,
-                float_reduction: None,
-                is_table: false,
-                table_id: None,
-                table_layout: None,
+                ..DisplayLine::marker(para_idx, para.text.chars().count(), meta)
             }];
         }
     }
 
     // Handle list numbering
-    let list_number = match meta.list_type {
-        ListType::Numbered => {
-            let num = list_counters.last().copied().unwrap_or(0) + 1;
-            if list_counters.is_empty() {
-                list_counters.push(num);
-            } else {
-                *list_counters.last_mut().unwrap() = num;
-            }
-            Some(num)
-        }
-        ListType::Bullet => None,
-        ListType::None => {
-            list_counters.clear();
-            None
-        }
-    };
+    let (list_number, list_marker) = state.list_counters.advance(meta);
 
     // Calculate base formatting
-    let font_size = meta.font_size.unwrap_or(config.font_size)
-        * meta.block_type.font_size_multiplier();
-    let list_indent = if meta.list_type != ListType::None {
-        font_size * 1.5
-    } else {
-        0.0
-    };
-    let base_available_width = config.column_width() - list_indent;
+    let font = format.font.clone();
+    let font_size = font.size;
+    let list_indent = list_indent(meta.list_type, meta.list_level, font_size);
+    let base_available_width = config.column_width() - list_indent - meta.indent.right;
 
-    // Wrap the paragraph text
+    // Split the text into runs that share a font, so mixed fonts are measured correctly
     let text = &para.text;
-    let line_height = config.line_height_px();
+    let runs = font_runs(document, para, &font);
+    let measure = RunMeasure::new(ctx.measurer, text, &runs, config.letter_spacing);
+    let char_count = measure.char_count();
     let column_width = config.column_width();
+    // Tracks where each line will be placed, so floats are checked against real positions
+    let mut line_cursor = cursor;
     if text.is_empty() {
-        let estimated_y = current_line_count as f64 * line_height;
-        let float_reduction = get_float_reduction(active_floats, current_line_count, estimated_y, line_height, column_width);
+        let line_runs = measure.line_runs(0, 0);
+        let line_height = line_box_height(&line_runs, format.line_spacing, config);
+        line_cursor.fit(line_height, config);
+        let float_reduction = get_float_reduction(&state.active_floats, &line_cursor, line_height, column_width);
         return vec![DisplayLine {
             para_index: para_idx,
             start_offset: 0,
@@ -553,6 +1233,11 @@ fn layout_paragraph(
             column_index: 0,
             x_position: 0.0,
             y_position: 0.0,
+            height: line_height,
+            ascent: max_of(&line_runs, |r| r.ascent),
+            descent: max_of(&line_runs, |r| r.descent),
+            char_positions: vec![0.0],
+            runs: line_runs,
             is_page_break: false,
             is_image: false,
             image_id: None,
@@ -561,98 +1246,61 @@ fn layout_paragraph(
             is_last_line: true,
             block_type: meta.block_type,
             list_type: meta.list_type,
+            list_level: meta.list_level,
+            list_marker: list_marker.clone(),
             float_reduction,
             is_table: false,
             table_id: None,
             table_layout: None,
+            table_fragment: None,
         }];
     }
 
     // Word wrap the text with per-line float checking
     let mut lines: Vec<DisplayLine> = Vec::new();
-    let mut current_start = 0;
-
-    while current_start < text.len() {
-        // Check for active floats affecting THIS line (not the first line)
-        let line_index = current_line_count + lines.len();
-        let estimated_y = line_index as f64 * line_height;
-        let float_reduction = get_float_reduction(active_floats, line_index, estimated_y, line_height, column_width);
-        let float_width = float_reduction.as_ref().map(|f| f.width + 10.0).unwrap_or(0.0);
-        let available_width = base_available_width - float_width;
-
-        let remaining = &text[current_start..];
-
-        // Measure remaining text
-        let remaining_width = measure_text(measure_fn, remaining, font_size, config.letter_spacing);
-
-        if remaining_width <= available_width {
-            // Entire remaining text fits
-            lines.push(DisplayLine {
-                para_index: para_idx,
-                start_offset: current_start,
-                end_offset: text.len(),
-                text: remaining.to_string(),
-                page_index: 0,
-                column_index: 0,
-                x_position: 0.0,
-                y_position: 0.0,
-                is_page_break: false,
-                is_image: false,
-                image_id: None,
-                image_height: None,
-                list_number: if lines.is_empty() { list_number } else { None },
-                is_last_line: true,
-                block_type: meta.block_type,
-                list_type: meta.list_type,
-                float_reduction,
-                is_table: false,
-                table_id: None,
-                table_layout: None,
-            });
-            break;
-        }
-
-        // Find break point
-        let mut line_end = current_start;
-        let mut last_word_boundary = current_start;
-
-        for (i, c) in text[current_start..].char_indices() {
-            let pos = current_start + i;
-            let test_text = &text[current_start..=pos];
-            let width = measure_text(measure_fn, test_text, font_size, config.letter_spacing);
-
-            if c == ' ' {
-                last_word_boundary = pos + 1;
+    let mut start = 0;
+    let base_height = line_box_height(&measure.line_runs(0, 0), format.line_spacing, config);
+
+    while start < char_count {
+        // The floats beside a line depend on its height, which depends on the
+        // fonts that end up on it: retry once if the line turns out taller
+        let mut probe_height = base_height;
+        let mut retried = false;
+        let (end, positions, line_runs, float_reduction) = loop {
+            let mut probe = line_cursor;
+            probe.fit(probe_height, config);
+            let float_reduction = get_float_reduction(&state.active_floats, &probe, probe_height, column_width);
+            let float_width = float_reduction.as_ref().map(|f| f.width + 10.0).unwrap_or(0.0);
+            // The first line and the following ones can be indented differently
+            let available_width = base_available_width - meta.indent.line_start(start == 0) - float_width;
+
+            let (end, positions) = measure.break_line(start, available_width);
+            let line_runs = measure.line_runs(start, end);
+            let height = line_box_height(&line_runs, format.line_spacing, config);
+            if height <= probe_height || retried {
+                break (end, positions, line_runs, float_reduction);
             }
+            probe_height = height;
+            retried = true;
+        };
+        let line_height = line_box_height(&line_runs, format.line_spacing, config);
+        line_cursor.fit(line_height, config);
+        line_cursor.y += line_height;
 
-            if width > available_width {
-                // Exceeded width, break at last word boundary
-                line_end = if last_word_boundary > current_start {
-                    last_word_boundary
-                } else {
-                    pos.max(current_start + 1)
-                };
-                break;
-            }
-
-            line_end = pos + c.len_utf8();
-        }
-
-        // Ensure progress
-        if line_end <= current_start {
-            line_end = current_start + 1;
-        }
-
-        let line_text = text[current_start..line_end].to_string();
         lines.push(DisplayLine {
             para_index: para_idx,
-            start_offset: current_start,
-            end_offset: line_end,
-            text: line_text,
+            start_offset: start,
+            end_offset: end,
+            text: measure.slice(start, end).to_string(),
             page_index: 0,
             column_index: 0,
             x_position: 0.0,
             y_position: 0.0,
+            height: line_height,
+            ascent: max_of(&line_runs, |r| r.ascent),
+            descent: max_of(&line_runs, |r| r.descent),
+            char_positions: positions,
+            runs: line_runs,
             is_page_break: false,
             is_image: false,
             image_id: None,
@@ -661,13 +1309,16 @@ fn layout_paragraph(
             is_last_line: false,
             block_type: meta.block_type,
             list_type: meta.list_type,
-            float_reduction: float_reduction.clone(),
+            list_level: meta.list_level,
+            list_marker: if lines.is_empty() { list_marker.clone() } else { None },
+            float_reduction,
             is_table: false,
             table_id: None,
             table_layout: None,
+            table_fragment: None,
         });
 
-        current_start = line_end;
+        start = end;
     }
 
     // Mark last line
@@ -678,57 +1329,163 @@ fn layout_paragraph(
     lines
 }
 
+/// Split a table line into the parts that go on the columns and pages the
+/// table flows through when it starts at `cursor`
+///
+/// Tables break between rows, but not inside merged cells, where they can.
+/// A row too tall for a column breaks between its text lines. Every part
+/// after the first repeats the table's header rows if there is room for them.
+fn split_table(table: &DocumentTable, line: DisplayLine, cursor: PageCursor, config: &LayoutConfig) -> Vec<DisplayLine> {
+    let Some(layout) = line.table_layout.as_ref() else {
+        return vec![line];
+    };
+    let column_height = config.content_height();
+    let total = layout.total_height;
+    if cursor.y + total <= column_height {
+        return vec![line];
+    }
+
+    let border = table.border_width;
+    let line_height = config.line_height_px();
+    let row_count = layout.row_heights.len();
+    // Top of each row's top border, and of the table's bottom border
+    let mut row_tops = vec![0.0];
+    for height in &layout.row_heights {
+        row_tops.push(row_tops[row_tops.len() - 1] + height + border);
+    }
+    let header_rows = table.header_rows.min(row_count.saturating_sub(1));
+    let mut header_height = if header_rows > 0 { row_tops[header_rows] } else { 0.0 };
+
+    // Where the table may break: between rows below the header rows, or
+    // failing that between the text lines of a row
+    let first_break_row = header_rows.max(1);
+    let row_breaks: Vec<f64> = (first_break_row..row_count)
+        .filter(|&row| {
+            !table.rows[row]
+                .cells
+                .iter()
+                .any(|cell| cell.covered && cell.covered_by_row.is_some_and(|r| r < row))
+        })
+        .map(|row| row_tops[row])
+        .collect();
+    let mut line_breaks: Vec<f64> = Vec::new();
+    for row in header_rows..row_count {
+        if row >= first_break_row {
+            line_breaks.push(row_tops[row]);
+        }
+        // Below a line of cell content that cuts no line of the other cells
+        let extents: Vec<(f64, f64)> = layout.cell_lines[row]
+            .iter()
+            .zip(&table.rows[row].cells)
+            .flat_map(|(lines, cell)| {
+                let text_top = row_tops[row] + border + table.padding_of(cell).top;
+                lines.iter().map(move |dl| (text_top + dl.y_position, text_top + dl.y_position + dl.height))
+            })
+            .collect();
+        let mut breaks: Vec<f64> = extents
+            .iter()
+            .map(|&(_, bottom)| bottom)
+            .filter(|&y| y < row_tops[row + 1] && !extents.iter().any(|&(top, bottom)| top < y && y < bottom))
+            .collect();
+        breaks.sort_by(f64::total_cmp);
+        breaks.dedup();
+        line_breaks.extend(breaks);
+    }
+    let last_break = |breaks: &[f64], start: f64, limit: f64| {
+        breaks.iter().rev().copied().find(|&b| b > start && b <= limit)
+    };
+
+    // Fill each column with as much of the table as fits
+    let mut parts: Vec<TableFragment> = Vec::new();
+    let mut start = 0.0;
+    let mut available = column_height - cursor.y;
+    while start < total {
+        let header = if parts.is_empty() { 0.0 } else { header_height };
+        let limit = start + available - header;
+        let end = if total <= limit {
+            Some(total)
+        } else {
+            last_break(&row_breaks, start, limit).or_else(|| last_break(&line_breaks, start, limit))
+        };
+        match end {
+            Some(end) => {
+                parts.push(TableFragment { start, end, header_height: header });
+                start = end;
+                available = column_height;
+            }
+            // Start in the next column
+            None if available < column_height => available = column_height,
+            // Give up repeating a header that leaves no room
+            None if header > 0.0 => header_height = 0.0,
+            // Not even one text line fits a column: let it overflow
+            None => {
+                let end = line_breaks.iter().copied().find(|&b| b > start).unwrap_or(total);
+                parts.push(TableFragment { start, end, header_height: 0.0 });
+                start = end;
+            }
+        }
+    }
+
+    if parts.len() == 1 {
+        return vec![line];
+    }
+    let count = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, fragment)| {
+            let height = fragment.header_height + fragment.end - fragment.start;
+            DisplayLine {
+                height,
+                image_height: Some(height / line_height),
+                is_last_line: index + 1 == count,
+                table_fragment: Some(fragment),
+                ..line.clone()
+            }
+        })
+        .collect()
+}
+
 /// Compute the layout for a table
-fn compute_table_layout(
+///
+/// The content of each cell is laid out like the document body, in a column
+/// as wide as the cell's text area, so tables nested in cells are laid out
+/// in turn.
+pub fn compute_table_layout<M: TextMeasurer + ?Sized>(
+    document: &Document,
     table: &DocumentTable,
     config: &LayoutConfig,
-    measure_fn: MeasureFn,
+    measurer: &M,
 ) -> TableLayout {
-    let available_width = config.column_width();
+    table_layout(&LayoutContext { document, config, measurer, depth: 0 }, table)
+}
+
+/// Compute the layout for a table in the content laid out by `ctx`
+fn table_layout<M: TextMeasurer + ?Sized>(ctx: &LayoutContext<M>, table: &DocumentTable) -> TableLayout {
+    let config = ctx.config;
     let line_height = config.line_height_px();
-    let font_size = config.font_size;
-    let cell_padding = 8.0; // 4px on each side
     let border = table.border_width;
     let num_cols = table.column_widths.len();
 
-    // Total border width used by all vertical borders
-    let total_border_width = (num_cols + 1) as f64 * border;
-
-    // Width available for cell content (total minus borders)
-    let content_width = available_width - total_border_width;
-
     // 1. Calculate column widths based on mode
-    let column_widths: Vec<f64> = match table.width_mode {
-        TableWidthMode::Fixed => table.column_widths.clone(),
-        TableWidthMode::Percentage => {
-            // Distribute content_width according to percentages
-            table.column_widths
-                .iter()
-                .map(|w| content_width * w / 100.0)
-                .collect()
-        }
-        TableWidthMode::Auto => {
-            // For now, use percentage mode for auto too
-            table.column_widths
-                .iter()
-                .map(|w| content_width * w / 100.0)
-                .collect()
-        }
-    };
+    let column_widths = table_column_widths(ctx.document, table, config, ctx.measurer);
 
-    // 2. Calculate cell text layouts and row heights
+    // 2. Lay out cell content and calculate row heights
     // First pass: calculate base row heights without considering row spans
     let mut row_heights: Vec<f64> = Vec::new();
-    let mut cell_lines: Vec<Vec<Vec<String>>> = Vec::new();
+    let mut cell_lines: Vec<Vec<Vec<DisplayLine>>> = Vec::new();
+    let mut content_heights: Vec<Vec<f64>> = Vec::new();
 
     for row in &table.rows {
-        let mut row_cell_lines: Vec<Vec<String>> = Vec::new();
-        let mut max_lines = 1;
+        let mut row_cell_lines: Vec<Vec<DisplayLine>> = Vec::new();
+        let mut row_content_heights: Vec<f64> = Vec::new();
+        let mut max_height: Option<f64> = None;
 
         for (col_idx, cell) in row.cells.iter().enumerate() {
             // Skip covered cells - they don't contribute to row height calculation
             if cell.covered {
-                row_cell_lines.push(vec![String::new()]);
+                row_cell_lines.push(Vec::new());
+                row_content_heights.push(0.0);
                 continue;
             }
 
@@ -741,24 +1498,29 @@ fn compute_table_layout(
             if cell.col_span > 1 {
                 cell_content_width += (cell.col_span - 1) as f64 * border;
             }
-            cell_content_width -= cell_padding;
+            let padding = table.padding_of(cell);
+            cell_content_width -= padding.horizontal();
 
-            // Wrap cell text
-            let lines = wrap_text_for_cell(&cell.text, cell_content_width, font_size, measure_fn, config);
+            let (lines, height) = layout_cell(ctx, cell, cell_content_width);
 
-            // Only count lines for row height if this cell doesn't span multiple rows
+            // An empty cell is one line tall plus its own padding
+            let mut cell_height = line_height + padding.vertical();
+            // Only count the content for row height if this cell doesn't span multiple rows
             if cell.row_span == 1 {
-                max_lines = max_lines.max(lines.len());
+                cell_height = cell_height.max(height + padding.vertical());
             }
+            max_height = Some(max_height.map_or(cell_height, |h| h.max(cell_height)));
             row_cell_lines.push(lines);
+            row_content_heights.push(height);
         }
 
-        // Row height = max lines * line_height + padding
-        let row_height = (max_lines as f64 * line_height + cell_padding).max(
-            row.min_height.unwrap_or(line_height + cell_padding)
-        );
+        // Row height = tallest content + padding; a fully covered row
+        // falls back to one line with the table's padding
+        let max_height = max_height.unwrap_or(line_height + table.cell_padding.vertical());
+        let row_height = max_height.max(row.min_height.unwrap_or(0.0));
         row_heights.push(row_height);
         cell_lines.push(row_cell_lines);
+        content_heights.push(row_content_heights);
     }
 
     // Second pass: adjust row heights for cells with row spans
@@ -769,11 +1531,11 @@ fn compute_table_layout(
             }
 
             // Calculate required height for this spanning cell
-            let lines_count = cell_lines.get(row_idx)
+            let content_height = content_heights.get(row_idx)
                 .and_then(|r| r.get(col_idx))
-                .map(|l| l.len())
-                .unwrap_or(1);
-            let required_height = lines_count as f64 * line_height + cell_padding;
+                .copied()
+                .unwrap_or(line_height);
+            let required_height = content_height + table.padding_of(cell).vertical();
 
             // Calculate current total height of spanned rows
             let spanned_rows_end = (row_idx + cell.row_span).min(table.rows.len());
@@ -785,16 +1547,40 @@ fn compute_table_layout(
             if required_height > current_total {
                 let extra = required_height - current_total;
                 let extra_per_row = extra / cell.row_span as f64;
-                for r in row_idx..spanned_rows_end {
-                    row_heights[r] += extra_per_row;
+                for height in &mut row_heights[row_idx..spanned_rows_end] {
+                    *height += extra_per_row;
                 }
             }
         }
     }
 
-    // 3. Calculate totals - table should span full available width
+    // 3. Move the content of cells aligned to the middle or bottom down
+    // into the room their rows leave them
+    for (row_idx, row) in table.rows.iter().enumerate() {
+        for (col_idx, cell) in row.cells.iter().enumerate() {
+            let share = match cell.vertical_align {
+                CellVerticalAlign::Top => continue,
+                CellVerticalAlign::Middle => 0.5,
+                CellVerticalAlign::Bottom => 1.0,
+            };
+            if cell.covered {
+                continue;
+            }
+            let spanned_rows_end = (row_idx + cell.row_span).min(table.rows.len());
+            let cell_height = row_heights[row_idx..spanned_rows_end].iter().sum::<f64>()
+                + spanned_rows_end.saturating_sub(row_idx + 1) as f64 * border;
+            let room = cell_height - table.padding_of(cell).vertical() - content_heights[row_idx][col_idx];
+            if room > 0.0 {
+                for dl in &mut cell_lines[row_idx][col_idx] {
+                    dl.y_position += room * share;
+                }
+            }
+        }
+    }
+
+    // 4. Calculate totals
     let total_height = row_heights.iter().sum::<f64>() + (table.rows.len() + 1) as f64 * border;
-    let total_width = available_width; // Full column width
+    let total_width = column_widths.iter().sum::<f64>() + (num_cols + 1) as f64 * border;
 
     TableLayout {
         table_id: table.id.clone(),
@@ -806,214 +1592,500 @@ fn compute_table_layout(
     }
 }
 
-/// Wrap text for a table cell, returning lines
-/// Handles explicit newlines and word wrapping
-fn wrap_text_for_cell(
-    text: &str,
-    max_width: f64,
-    font_size: f64,
-    measure_fn: MeasureFn,
-    config: &LayoutConfig,
-) -> Vec<String> {
-    if text.is_empty() {
-        return vec![String::new()];
+/// Layout configuration for the content of a table cell: a single column
+/// `width` pixels wide with its top-left corner at (`x`, `y`), on a page
+/// that never ends
+///
+/// Paragraphs in a cell are not spaced apart unless their style or
+/// metadata says so, so the lines of a cell stay together.
+pub fn cell_config(config: &LayoutConfig, x: f64, y: f64, width: f64) -> LayoutConfig {
+    LayoutConfig {
+        page_width: x + width.max(0.0),
+        page_height: f64::INFINITY,
+        margin_top: y,
+        margin_right: 0.0,
+        margin_bottom: 0.0,
+        margin_left: x,
+        columns: 1,
+        column_gap: 0.0,
+        paragraph_spacing: 0.0,
+        ..config.clone()
     }
+}
 
-    let mut all_lines: Vec<String> = Vec::new();
-
-    // First, split by explicit newlines
-    for paragraph in text.split('\n') {
-        if paragraph.is_empty() {
-            all_lines.push(String::new());
+/// Lay out the paragraphs of a cell in a column `width` pixels wide
+///
+/// Returns the display lines, placed from the top-left corner of the column,
+/// and the height of the content including images floating in it.
+fn layout_cell<M: TextMeasurer + ?Sized>(
+    ctx: &LayoutContext<M>,
+    cell: &TableCell,
+    width: f64,
+) -> (Vec<DisplayLine>, f64) {
+    let config = cell_config(ctx.config, 0.0, 0.0, width);
+    let cell_ctx = LayoutContext {
+        document: ctx.document,
+        config: &config,
+        measurer: ctx.measurer,
+        depth: ctx.depth + 1,
+    };
+    let mut state = FlowState {
+        active_floats: Vec::new(),
+        list_counters: ListCounters::default(),
+    };
+    let mut cursor = PageCursor::default();
+    let mut lines = Vec::new();
+    for (para_idx, para) in cell.paragraphs.iter().enumerate() {
+        // Page breaks have no meaning inside a cell
+        if para.is_page_break() {
             continue;
         }
+        let format = paragraph_format(ctx.document, &para.meta, &config);
+        cursor.space_before(format.space_before);
+        let mut para_lines = layout_paragraph(&cell_ctx, para_idx, para, &format, &mut state, cursor);
+        for dl in &mut para_lines {
+            cursor.place(dl, &config);
+        }
+        // Space between paragraphs, but not below the last one
+        if para_idx + 1 < cell.paragraphs.len() && para_lines.last().is_some_and(|dl| dl.height > 0.0) {
+            cursor.y += format.space_after;
+        }
+        lines.extend(para_lines);
+    }
+    let height = state.active_floats.iter().map(|f| f.y_end).fold(cursor.y, f64::max);
+    (lines, height)
+}
 
-        // Then wrap each paragraph
-        let mut current_start = 0;
-
-        while current_start < paragraph.len() {
-            let remaining = &paragraph[current_start..];
-            let remaining_width = measure_text(measure_fn, remaining, font_size, config.letter_spacing);
+/// Width of each column of a table in pixels, not counting borders
+///
+/// Fixed widths are pixels and percentage widths are shares of the page
+/// column. Auto tables size their columns from the content, like the CSS
+/// automatic table layout: each column gets at least its widest word and at
+/// most its longest line, and the room in between is shared in proportion.
+/// Widths that need more room than the page column has are scaled down to
+/// fit, and cell text then wraps inside words.
+pub fn table_column_widths<M: TextMeasurer + ?Sized>(
+    document: &Document,
+    table: &DocumentTable,
+    config: &LayoutConfig,
+    measurer: &M,
+) -> Vec<f64> {
+    let num_cols = table.num_cols();
+    let content_width = (config.column_width() - (num_cols + 1) as f64 * table.border_width).max(0.0);
+    let widths: Vec<f64> = match table.width_mode {
+        TableWidthMode::Fixed => table.column_widths.iter().map(|w| w.max(0.0)).collect(),
+        TableWidthMode::Percentage => table
+            .column_widths
+            .iter()
+            .map(|w| content_width * w.max(0.0) / 100.0)
+            .collect(),
+        TableWidthMode::Auto => auto_column_widths(document, table, content_width, config, measurer),
+    };
+    let total: f64 = widths.iter().sum();
+    if total > content_width {
+        widths.iter().map(|w| w * content_width / total).collect()
+    } else {
+        widths
+    }
+}
 
-            if remaining_width <= max_width {
-                all_lines.push(remaining.to_string());
-                break;
+/// Column widths of an auto-sized table with `content_width` pixels of room
+/// between its borders
+fn auto_column_widths<M: TextMeasurer + ?Sized>(
+    document: &Document,
+    table: &DocumentTable,
+    content_width: f64,
+    config: &LayoutConfig,
+    measurer: &M,
+) -> Vec<f64> {
+    let num_cols = table.num_cols();
+    let mut min = vec![table.cell_padding.horizontal(); num_cols];
+    let mut max = min.clone();
+
+    // Cells in one column size it directly; merged cells then widen the
+    // columns they span evenly where these are too narrow for them
+    let mut merged = Vec::new();
+    for row in &table.rows {
+        for (col, cell) in row.cells.iter().enumerate().take(num_cols) {
+            if cell.covered {
+                continue;
             }
-
-            // Find break point
-            let mut line_end = current_start;
-            let mut last_word_boundary = current_start;
-
-            for (i, c) in paragraph[current_start..].char_indices() {
-                let pos = current_start + i;
-                let test_text = &paragraph[current_start..=pos];
-                let width = measure_text(measure_fn, test_text, font_size, config.letter_spacing);
-
-                if c == ' ' {
-                    last_word_boundary = pos + 1;
-                }
-
-                if width > max_width {
-                    line_end = if last_word_boundary > current_start {
-                        last_word_boundary
-                    } else {
-                        pos.max(current_start + 1)
-                    };
-                    break;
-                }
-
-                line_end = pos + c.len_utf8();
+            // Widest word and longest paragraph; images need their width and
+            // nested tables fit whatever room they get
+            let padding = table.padding_of(cell).horizontal();
+            let (mut cell_min, mut cell_max) = (padding, padding);
+            for para in &cell.paragraphs {
+                let image = para.image_id().and_then(|id| document.images.iter().find(|img| img.id == id));
+                let (para_min, para_max) = match image {
+                    Some(image) => (image.width, image.width),
+                    None if para.is_marker() => (0.0, 0.0),
+                    None => paragraph_content_widths(document, para, config, measurer),
+                };
+                cell_min = cell_min.max(padding + para_min);
+                cell_max = cell_max.max(padding + para_max);
             }
-
-            if line_end <= current_start {
-                line_end = current_start + 1;
+            if cell.col_span <= 1 {
+                min[col] = min[col].max(cell_min);
+                max[col] = max[col].max(cell_max);
+            } else {
+                merged.push((col..(col + cell.col_span).min(num_cols), cell_min, cell_max));
+            }
+        }
+    }
+    for (cols, cell_min, cell_max) in merged {
+        let inner_borders = (cols.len() - 1) as f64 * table.border_width;
+        for (widths, needed) in [(&mut min, cell_min), (&mut max, cell_max)] {
+            let lacking = needed - inner_borders - widths[cols.clone()].iter().sum::<f64>();
+            if lacking > 0.0 {
+                for width in &mut widths[cols.clone()] {
+                    *width += lacking / cols.len() as f64;
+                }
             }
-
-            all_lines.push(paragraph[current_start..line_end].to_string());
-            current_start = line_end;
         }
     }
 
-    if all_lines.is_empty() {
-        all_lines.push(String::new());
+    for (max, min) in max.iter_mut().zip(&min) {
+        *max = max.max(*min);
+    }
+    let min_total: f64 = min.iter().sum();
+    let max_total: f64 = max.iter().sum();
+    if max_total <= content_width {
+        max
+    } else if min_total >= content_width {
+        min
+    } else {
+        let share = (content_width - min_total) / (max_total - min_total);
+        min.iter().zip(&max).map(|(min, max)| min + (max - min) * share).collect()
     }
+}
 
-    all_lines
+/// Min-content (widest word) and max-content (whole paragraph on one line)
+/// widths of a text paragraph, in its resolved fonts and including its list
+/// and paragraph indents
+fn paragraph_content_widths<M: TextMeasurer + ?Sized>(
+    document: &Document,
+    para: &Paragraph,
+    config: &LayoutConfig,
+    measurer: &M,
+) -> (f64, f64) {
+    let meta = &para.meta;
+    let format = paragraph_format(document, meta, config);
+    let runs = font_runs(document, para, &format.font);
+    let measure = RunMeasure::new(measurer, &para.text, &runs, config.letter_spacing);
+
+    // Words are measured between whitespace, by character index
+    let mut widest_word: f64 = 0.0;
+    let mut word_start = None;
+    for (index, c) in para.text.chars().chain(std::iter::once(' ')).enumerate() {
+        match (c.is_whitespace(), word_start) {
+            (true, Some(start)) => {
+                widest_word = widest_word.max(measure.width(start, index));
+                word_start = None;
+            }
+            (false, None) => word_start = Some(index),
+            _ => {}
+        }
+    }
+
+    let gutter = list_indent(meta.list_type, meta.list_level, format.font.size) + meta.indent.right;
+    let any_line_start = meta.indent.line_start(true).max(meta.indent.line_start(false));
+    (
+        gutter + any_line_start + widest_word,
+        gutter + meta.indent.line_start(true) + measure.width(0, measure.char_count()),
+    )
 }
 
-/// Get float reduction for a given line
+/// Get float reduction for a line of `line_height` placed at `position`
 ///
-/// For move-with-text floats: uses line_index to check overlap
-/// For fixed-position floats: uses estimated_y to check Y-based overlap
+/// A float affects the line if both are on the same page and the line's box
+/// overlaps the float vertically.
 ///
 /// Returns the float reduction including the X position of the float
 fn get_float_reduction(
     floats: &[ActiveFloat],
-    line_index: usize,
-    estimated_y: f64,
+    position: &PageCursor,
     line_height: f64,
     column_width: f64,
 ) -> Option<FloatReduction> {
+    let line_top = position.y;
+    let line_bottom = position.y + line_height;
     for float in floats {
-        // Check Y-based overlap for fixed-position floats
-        if let (Some(y_start), Some(y_end)) = (float.y_start, float.y_end) {
-            // Line occupies Y range [estimated_y, estimated_y + line_height)
-            // Float occupies Y range [y_start, y_end)
-            // They overlap if: estimated_y < y_end AND estimated_y + line_height > y_start
-            if estimated_y < y_end && estimated_y + line_height > y_start {
-                // For fixed-position floats, use stored X position
-                let float_x = float.x_position.unwrap_or(0.0);
-                return Some(FloatReduction {
-                    side: float.side,
-                    width: float.width,
-                    float_x,
-                });
-            }
-        } else {
-            // Line-index based overlap for move-with-text floats
-            if line_index >= float.start_line && line_index < float.end_line {
-                // For move-with-text floats, calculate X based on side
-                let float_x = match float.side {
-                    FloatSide::Left => 0.0,
-                    FloatSide::Right => column_width - float.width,
-                };
-                return Some(FloatReduction {
-                    side: float.side,
-                    width: float.width,
-                    float_x,
-                });
-            }
+        if float.page_index != Some(position.page) {
+            continue;
+        }
+        // Line occupies Y range [line_top, line_bottom)
+        // Float occupies Y range [y_start, y_end)
+        if line_top < float.y_end && line_bottom > float.y_start {
+            // Fixed-position floats use their stored X position,
+            // move-with-text floats are aligned to their side
+            let float_x = float.x_position.unwrap_or(match float.side {
+                FloatSide::Left => 0.0,
+                FloatSide::Right => column_width - float.width,
+            });
+            return Some(FloatReduction {
+                side: float.side,
+                width: float.width,
+                float_x,
+            });
         }
     }
     None
 }
 
-/// Assign page and column positions to all display lines
-fn assign_page_positions(display_lines: &mut [DisplayLine], config: &LayoutConfig) {
-    let mut current_y = 0.0;
-    let mut current_page = 0;
-    let mut current_column = 0;
-    let max_column_height = config.content_height();
-    let line_height = config.line_height_px();
+/// Characters of a paragraph that share a font
+#[derive(Debug, Clone, PartialEq)]
+struct FontRun {
+    start: usize,
+    end: usize,
+    font: FontSpec,
+    rise: f64,
+    uppercase: bool,
+}
 
-    for dl in display_lines.iter_mut() {
-        // Handle page breaks
-        if dl.is_page_break {
-            dl.page_index = current_page;
-            dl.y_position = current_y;
-            dl.column_index = current_column;
-            // Move to next page
-            current_page += 1;
-            current_column = 0;
-            current_y = 0.0;
-            continue;
+/// Split a paragraph into runs of characters with the same font
+///
+/// `base` is the paragraph's font; style ranges, with their character styles
+/// resolved, override its family, size and weight. Superscript and subscript
+/// shrink the font and shift the baseline; small caps split a range into
+/// capitals and smaller uppercased lowercase letters. A paragraph without text
+/// gets a single empty run with the base font.
+fn font_runs(document: &Document, para: &Paragraph, base: &FontSpec) -> Vec<FontRun> {
+    let styles: Vec<TextStyle> = para.styles.iter().map(|s| document.resolve_text_style(s, base.size)).collect();
+    let chars: Vec<char> = para.text.chars().collect();
+    let len = chars.len();
+    let mut boundaries = vec![0, len];
+    for style in &styles {
+        boundaries.extend([style.start.min(len), style.end.min(len)]);
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut runs: Vec<FontRun> = Vec::new();
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let covering = || styles.iter().filter(|s| s.start <= start && s.end >= end);
+        let mut font = style_font(base, covering());
+        let vertical_align = covering()
+            .map(|s| s.vertical_align)
+            .find(|v| *v != VerticalAlign::Baseline)
+            .unwrap_or_default();
+        let caps = covering().map(|s| s.caps).find(|c| *c != CapsStyle::Normal).unwrap_or_default();
+
+        let rise = vertical_align.rise(font.size);
+        if vertical_align != VerticalAlign::Baseline {
+            font.size *= SCRIPT_SIZE_RATIO;
+        }
+        let mut small = font.clone();
+        small.size *= SMALL_CAPS_SIZE_RATIO;
+
+        for (index, c) in chars.iter().enumerate().take(end).skip(start) {
+            let (font, uppercase) = match caps {
+                CapsStyle::Normal => (&font, false),
+                CapsStyle::AllCaps => (&font, true),
+                CapsStyle::SmallCaps if c.is_lowercase() => (&small, true),
+                CapsStyle::SmallCaps => (&font, false),
+            };
+            match runs.last_mut() {
+                Some(last) if last.font == *font && last.rise == rise && last.uppercase == uppercase => {
+                    last.end = index + 1
+                }
+                _ => runs.push(FontRun { start: index, end: index + 1, font: font.clone(), rise, uppercase }),
+            }
         }
+    }
+    if runs.is_empty() {
+        runs.push(FontRun { start: 0, end: 0, font: base.clone(), rise: 0.0, uppercase: false });
+    }
+    runs
+}
 
-        // Calculate line height for this line
-        // Tables and images use image_height (in line units) for their height
-        let this_line_height = if dl.is_image || dl.is_table {
-            dl.image_height.unwrap_or(1.0) * line_height
-        } else {
-            line_height
-        };
+/// Font of text covered by `styles` in a paragraph with font `base`
+fn style_font<'a>(base: &FontSpec, styles: impl Iterator<Item = &'a TextStyle>) -> FontSpec {
+    let mut font = base.clone();
+    let mut bold = false;
+    let mut weight = None;
+    let mut family = None;
+    let mut size = None;
+    for style in styles {
+        bold |= style.bold;
+        font.italic |= style.italic;
+        weight = weight.or(style.font_weight);
+        family = family.or(style.font_family.as_ref());
+        size = size.or(style.font_size.filter(|s| *s > 0.0));
+    }
+    if let Some(family) = family {
+        font.family = Some(family.clone());
+    }
+    font.size = size.unwrap_or(font.size);
+    font.weight = match weight {
+        Some(weight) => weight,
+        None if bold => base.weight.max(FONT_WEIGHT_BOLD),
+        None => base.weight,
+    };
+    font
+}
 
-        // Add paragraph spacing if last line, but not for zero-height image markers
-        let spacing_after = if dl.is_last_line && this_line_height > 0.0 {
-            config.paragraph_spacing
-        } else {
-            0.0
-        };
+/// Measures character ranges of a paragraph made of font runs
+///
+/// Each run is measured once up front; widths of character ranges are then
+/// read from the runs' prefix widths instead of asking the measurer again.
+struct RunMeasure<'a, M: TextMeasurer + ?Sized> {
+    measurer: &'a M,
+    text: &'a str,
+    /// Byte index of every character boundary (one more than characters)
+    boundaries: Vec<usize>,
+    runs: &'a [FontRun],
+    /// Width of every character prefix of each run, parallel to `runs`
+    run_widths: Vec<Vec<f64>>,
+    letter_spacing: f64,
+}
 
-        // Check for overflow
-        if current_y + this_line_height > max_column_height {
-            // Move to next column or page
-            if config.columns > 1 && current_column < (config.columns - 1) as usize {
-                current_column += 1;
-                current_y = 0.0;
-            } else {
-                current_page += 1;
-                current_column = 0;
-                current_y = 0.0;
+impl<'a, M: TextMeasurer + ?Sized> RunMeasure<'a, M> {
+    fn new(measurer: &'a M, text: &'a str, runs: &'a [FontRun], letter_spacing: f64) -> Self {
+        let mut boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        boundaries.push(text.len());
+        let run_widths = runs
+            .iter()
+            .map(|run| {
+                let piece = &text[boundaries[run.start]..boundaries[run.end]];
+                if run.uppercase {
+                    uppercase_prefix_widths(measurer, piece, &run.font)
+                } else {
+                    measurer.prefix_widths(piece, &run.font)
+                }
+            })
+            .collect();
+        RunMeasure {
+            measurer,
+            text,
+            boundaries,
+            runs,
+            run_widths,
+            letter_spacing,
+        }
+    }
+
+    fn char_count(&self) -> usize {
+        self.boundaries.len() - 1
+    }
+
+    /// Text of the characters in `[start, end)`
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.text[self.boundaries[start]..self.boundaries[end]]
+    }
+
+    /// Width of the characters in `[start, end)`, each run in its own font
+    fn width(&self, start: usize, end: usize) -> f64 {
+        if end <= start {
+            return 0.0;
+        }
+        let mut width = 0.0;
+        for (run, widths) in self.runs.iter().zip(&self.run_widths) {
+            if run.start < end && run.end > start {
+                width += widths[end.min(run.end) - run.start] - widths[start.max(run.start) - run.start];
             }
         }
+        // Letter spacing separates every pair of neighbouring characters
+        width + (end - start - 1) as f64 * self.letter_spacing
+    }
 
-        // Assign position
-        dl.page_index = current_page;
-        dl.column_index = current_column;
-        dl.y_position = current_y;
+    /// Position of every character boundary in `[start, end]`, relative to `start`
+    fn positions(&self, start: usize, end: usize) -> Vec<f64> {
+        (start..=end).map(|index| self.width(start, index)).collect()
+    }
 
-        // Calculate X position
-        let column_offset = current_column as f64 * (config.column_width() + config.column_gap);
-        dl.x_position = config.margin_left + column_offset;
+    /// Find where a line starting at character `start` ends, breaking after
+    /// the last space that fits in `available_width` (or inside a word that is
+    /// too long). Returns the end and the line's character positions.
+    fn break_line(&self, start: usize, available_width: f64) -> (usize, Vec<f64>) {
+        let char_count = self.char_count();
+        // Entire remaining text fits
+        if self.width(start, char_count) <= available_width {
+            return (char_count, self.positions(start, char_count));
+        }
 
-        // Advance Y
-        current_y += this_line_height + spacing_after;
+        let mut last_word_boundary = start;
+        let mut end = start;
+        for index in start..char_count {
+            if self.slice(index, index + 1) == " " {
+                last_word_boundary = index + 1;
+            }
+            if self.width(start, index + 1) > available_width {
+                break;
+            }
+            end = index + 1;
+        }
+
+        // Break at the last word boundary; a line holds at least one character
+        let line_end = if last_word_boundary > start { last_word_boundary } else { end };
+        let line_end = line_end.max(start + 1);
+        (line_end, self.positions(start, line_end))
+    }
+
+    /// Font runs of the characters in `[start, end)`, relative to `start`
+    ///
+    /// An empty range gets the run of the character before it, or the first run.
+    fn line_runs(&self, start: usize, end: usize) -> Vec<LineRun> {
+        let overlapping: Vec<&FontRun> = if start == end {
+            let before = start.saturating_sub(1);
+            self.runs
+                .iter()
+                .find(|r| before < r.end)
+                .or(self.runs.last())
+                .into_iter()
+                .collect()
+        } else {
+            self.runs.iter().filter(|r| r.start < end && r.end > start).collect()
+        };
+        overlapping
+            .into_iter()
+            .map(|run| {
+                let metrics = self.measurer.metrics(&run.font);
+                LineRun {
+                    start: run.start.max(start) - start,
+                    end: run.end.min(end).max(start) - start,
+                    font: run.font.clone(),
+                    ascent: (metrics.ascent + run.rise).max(0.0),
+                    descent: (metrics.descent - run.rise).max(0.0),
+                    rise: run.rise,
+                    uppercase: run.uppercase,
+                }
+            })
+            .collect()
     }
 }
 
-/// Measure text width using the provided JS function
-fn measure_text(
-    measure_fn: &js_sys::Function,
-    text: &str,
-    font_size: f64,
-    letter_spacing: f64,
-) -> f64 {
-    let this = JsValue::NULL;
-    let text_js = JsValue::from_str(text);
-    let size_js = JsValue::from_f64(font_size);
-
-    match measure_fn.call2(&this, &text_js, &size_js) {
-        Ok(result) => {
-            let width = result.as_f64().unwrap_or(text.len() as f64 * font_size * 0.5);
-            // Add letter spacing
-            let spacing = if text.len() > 1 {
-                (text.chars().count() - 1) as f64 * letter_spacing
-            } else {
-                0.0
-            };
-            width + spacing
-        }
-        Err(_) => text.len() as f64 * font_size * 0.5,
+/// Prefix widths of `text` drawn in upper case, one entry per character of
+/// the original text (upper-casing can turn one character into several)
+fn uppercase_prefix_widths<M: TextMeasurer + ?Sized>(measurer: &M, text: &str, font: &FontSpec) -> Vec<f64> {
+    let mut upper = String::with_capacity(text.len());
+    let mut upper_ends = vec![0];
+    for c in text.chars() {
+        let before = upper.len();
+        upper.extend(c.to_uppercase());
+        let added = upper[before..].chars().count();
+        upper_ends.push(upper_ends.last().copied().unwrap_or(0) + added);
+    }
+    let widths = measurer.prefix_widths(&upper, font);
+    upper_ends.into_iter().map(|i| widths[i]).collect()
+}
+
+/// Largest value of `f` over the runs of a line
+fn max_of<F: Fn(&LineRun) -> f64>(runs: &[LineRun], f: F) -> f64 {
+    runs.iter().map(f).fold(0.0, f64::max)
+}
+
+/// Height of a line box holding `runs`
+///
+/// Multiple and at-least spacing never go below what the glyphs need; exact
+/// spacing is used as is, even if tall glyphs overflow the line.
+fn line_box_height(runs: &[LineRun], spacing: LineSpacing, config: &LayoutConfig) -> f64 {
+    let size = max_of(runs, |r| r.font.size);
+    let glyphs = max_of(runs, |r| r.ascent) + max_of(runs, |r| r.descent);
+    match spacing {
+        LineSpacing::Multiple(multiple) => (size * multiple).max(glyphs),
+        LineSpacing::Exact(height) => height,
+        LineSpacing::AtLeast(height) => (size * config.line_height).max(glyphs).max(height),
     }
 }
 
@@ -1027,7 +2099,7 @@ pub struct DisplayPosition {
 }
 
 /// Result of mapping a display position to a paragraph position
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ParagraphPosition {
     /// Paragraph index
     pub para: usize,
@@ -1055,7 +2127,7 @@ pub fn para_to_display_pos(
     let last_line = display_lines.len().saturating_sub(1);
     let last_col = display_lines
         .last()
-        .map(|dl| dl.text.len())
+        .map(|dl| dl.text.chars().count())
         .unwrap_or(0);
 
     DisplayPosition {
@@ -1083,7 +2155,7 @@ pub fn display_to_para(
     }
 
     let dl = &display_lines[line];
-    let clamped_col = col.min(dl.text.len());
+    let clamped_col = col.min(dl.end_offset - dl.start_offset);
 
     ParagraphPosition {
         para: dl.para_index,
@@ -44,6 +44,10 @@
 //! - [`layout`]: Layout computation (line wrapping, pagination)
 //! - [`render`]: Render command generation for Canvas drawing
 //! - [`text`]: Unicode-aware text manipulation utilities
+//! - [`measure`]: Text measurement abstraction (JS callback or native)
+//! - [`font`]: Font registry and rustybuzz-based shaping
+//! - [`history`]: Undo/redo transactions of invertible document operations
+//! - [`position`]: Hit testing between page coordinates and document positions
 //!
 //! ## Quick Start
 //!
@@ -59,22 +63,30 @@
 //! // Add content
 //! engine.set_paragraph(0, "Hello, World!".to_string());
 //!
-//! // Compute layout (requires JS measurement function)
-//! engine.recompute_layout(&measure_fn);
+//! // Compute layout (JS measurement function, or any `TextMeasurer` natively)
+//! engine.recompute_layout_with(&FixedWidthMeasurer::default());
 //!
 //! // Get render commands for page 0
 //! let commands_json = engine.get_render_commands(0);
 //! ```
 
 mod document;
+mod font;
+mod history;
 mod layout;
+mod measure;
+mod position;
 mod render;
 mod text;
 
 use wasm_bindgen::prelude::*;
 
 pub use document::*;
+pub use font::*;
+pub use history::*;
 pub use layout::*;
+pub use measure::*;
+pub use position::*;
 pub use render::*;
 pub use text::*;
 
@@ -92,7 +104,16 @@ pub struct Engine {
     document: Document,
     layout_config: LayoutConfig,
     display_lines: Vec<DisplayLine>,
-    dirty: bool,
+    layout_cache: LayoutCache,
+    /// Pages changed by the last layout update
+    changed_pages: Vec<usize>,
+    fonts: FontRegistry,
+    history: History,
+    /// Current selection as (anchor, focus), used to draw the caret and
+    /// selection in render commands
+    selection: Option<(ParagraphPosition, ParagraphPosition)>,
+    /// Whether `get_render_commands` includes the caret and selection
+    render_selection: bool,
 }
 
 #[wasm_bindgen]
@@ -104,12 +125,18 @@ impl Engine {
             document: Document::new(),
             layout_config: LayoutConfig::default(),
             display_lines: Vec::new(),
-            dirty: true,
+            layout_cache: LayoutCache::new(),
+            changed_pages: Vec::new(),
+            fonts: FontRegistry::new(),
+            history: History::new(),
+            selection: None,
+            render_selection: false,
         }
     }
 
     /// Set page configuration
     #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
     pub fn set_page_config(
         &mut self,
         page_width: f64,
@@ -131,12 +158,14 @@ impl Engine {
             columns,
             column_gap,
             // Preserve existing font settings
+            font_family: self.layout_config.font_family.clone(),
             font_size: self.layout_config.font_size,
             line_height: self.layout_config.line_height,
             letter_spacing: self.layout_config.letter_spacing,
             paragraph_spacing: self.layout_config.paragraph_spacing,
+            strike_checked_items: self.layout_config.strike_checked_items,
         };
-        self.dirty = true;
+        self.layout_cache.invalidate();
     }
 
     /// Set font configuration
@@ -152,7 +181,26 @@ impl Engine {
         self.layout_config.line_height = line_height;
         self.layout_config.letter_spacing = letter_spacing;
         self.layout_config.paragraph_spacing = paragraph_spacing;
-        self.dirty = true;
+        self.layout_cache.invalidate();
+    }
+
+    /// Set the default font family, used for text without a family of its own
+    #[wasm_bindgen]
+    pub fn set_default_font_family(&mut self, family: &str) {
+        let family = family.trim();
+        if family.is_empty() || family == self.layout_config.font_family {
+            return;
+        }
+        self.layout_config.font_family = family.to_string();
+        self.layout_cache.invalidate();
+    }
+
+    /// Choose whether ticked checklist items are drawn struck through
+    ///
+    /// Only affects rendering; layout is unchanged.
+    #[wasm_bindgen]
+    pub fn set_strike_checked_items(&mut self, strike: bool) {
+        self.layout_config.strike_checked_items = strike;
     }
 
     /// Get the number of paragraphs
@@ -170,47 +218,296 @@ impl Engine {
     /// Set paragraph text
     #[wasm_bindgen]
     pub fn set_paragraph(&mut self, index: usize, text: String) {
-        if let Some(para) = self.document.paragraphs.get_mut(index) {
-            para.text = text;
-            self.dirty = true;
+        self.edit_paragraph(index, |para| para.set_text(text));
+    }
+
+    /// Insert text at a character offset, shifting inline styles
+    #[wasm_bindgen]
+    pub fn insert_text(&mut self, para_index: usize, offset: usize, text: &str) {
+        if self.is_marker_paragraph(para_index) {
+            return;
+        }
+        self.edit_paragraph(para_index, |para| para.insert_text(offset, text));
+    }
+
+    /// Delete the characters in `[start, end)` of a paragraph, shifting inline styles
+    #[wasm_bindgen]
+    pub fn delete_text(&mut self, para_index: usize, start: usize, end: usize) {
+        if self.is_marker_paragraph(para_index) {
+            return;
+        }
+        self.edit_paragraph(para_index, |para| para.delete_text(start, end));
+    }
+
+    /// Split a paragraph at a character offset (Enter key)
+    ///
+    /// The text after the offset moves to a new paragraph at `para_index + 1`
+    /// with the same styles and metadata. Splitting an image, table or page
+    /// break marker inserts an empty paragraph before it (offset 0) or after it
+    /// instead. Returns false if the paragraph does not exist.
+    #[wasm_bindgen]
+    pub fn split_paragraph(&mut self, para_index: usize, offset: usize) -> bool {
+        if para_index >= self.document.paragraphs.len() {
+            return false;
+        }
+        if self.is_marker_paragraph(para_index) {
+            let index = if offset == 0 { para_index } else { para_index + 1 };
+            self.insert_paragraph_at(index, Paragraph::new(String::new()));
+            return true;
+        }
+
+        self.history.begin_group();
+        let mut tail = None;
+        self.edit_paragraph(para_index, |para| tail = Some(para.split_off(offset)));
+        if let Some(tail) = tail {
+            self.insert_paragraph_at(para_index + 1, tail);
+        }
+        self.history.end_group();
+        true
+    }
+
+    /// Join a paragraph with the one after it (Backspace at a paragraph
+    /// start, Delete at a paragraph end)
+    ///
+    /// The joined paragraph keeps the metadata of `para_index`. Image, table and
+    /// page break markers are never merged into text: an empty text paragraph
+    /// next to a marker is removed instead, otherwise nothing happens.
+    /// Returns true if the document changed.
+    #[wasm_bindgen]
+    pub fn join_with_next(&mut self, para_index: usize) -> bool {
+        let paragraphs = &self.document.paragraphs;
+        let (Some(first), Some(second)) = (paragraphs.get(para_index), paragraphs.get(para_index + 1))
+        else {
+            return false;
+        };
+
+        match (first.is_marker(), second.is_marker()) {
+            (false, false) => {
+                self.history.begin_group();
+                if let Some(next) = self.remove_paragraph_at(para_index + 1) {
+                    self.edit_paragraph(para_index, |para| para.append(next));
+                }
+                self.history.end_group();
+                true
+            }
+            (false, true) if first.text.is_empty() => {
+                self.remove_paragraph_at(para_index);
+                true
+            }
+            (true, false) if second.text.is_empty() => {
+                self.remove_paragraph_at(para_index + 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Delete the text between two document positions, which may lie in
+    /// different paragraphs
+    ///
+    /// The start paragraph keeps its text before `start_offset`, the end
+    /// paragraph keeps its text from `end_offset` on, and the two are joined.
+    /// Paragraphs in between are removed, including image, table and page
+    /// break markers. A marker at either end is removed only if the range
+    /// covers it (start offset 0, end offset past the marker). Returns true if
+    /// the document changed.
+    #[wasm_bindgen]
+    pub fn delete_range(
+        &mut self,
+        start_para: usize,
+        start_offset: usize,
+        end_para: usize,
+        end_offset: usize,
+    ) -> bool {
+        let count = self.document.paragraphs.len();
+        if start_para >= count || (start_para, start_offset) >= (end_para, end_offset) {
+            return false;
+        }
+        let end_para = end_para.min(count - 1);
+        if start_para == end_para {
+            if self.is_marker_paragraph(start_para) {
+                return false;
+            }
+            return self.edit_paragraph(start_para, |para| para.delete_text(start_offset, end_offset));
+        }
+
+        self.history.begin_group();
+
+        // Trim the end paragraph first so that earlier indices stay valid
+        let end_is_marker = self.is_marker_paragraph(end_para);
+        if end_is_marker && end_offset > 0 {
+            self.remove_paragraph_at(end_para);
+        } else if !end_is_marker {
+            self.edit_paragraph(end_para, |para| para.delete_text(0, end_offset));
+        }
+        for index in (start_para + 1..end_para).rev() {
+            self.remove_paragraph_at(index);
+        }
+        let start_is_marker = self.is_marker_paragraph(start_para);
+        if start_is_marker && start_offset == 0 {
+            self.remove_paragraph_at(start_para);
+        } else if !start_is_marker {
+            self.edit_paragraph(start_para, |para| {
+                let len = para.char_len();
+                para.delete_text(start_offset, len);
+            });
+        }
+
+        // Join what is left of the two ends if both are text
+        let kept_end = !end_is_marker || end_offset == 0;
+        if !start_is_marker && kept_end && !self.is_marker_paragraph(start_para + 1) {
+            if let Some(next) = self.remove_paragraph_at(start_para + 1) {
+                self.edit_paragraph(start_para, |para| para.append(next));
+            }
         }
+        if self.document.paragraphs.is_empty() {
+            self.insert_paragraph_at(0, Paragraph::new(String::new()));
+        }
+
+        self.history.end_group();
+        true
     }
 
     /// Insert a new paragraph at index
     #[wasm_bindgen]
     pub fn insert_paragraph(&mut self, index: usize, text: String) {
         let para = Paragraph::new(text);
-        if index >= self.document.paragraphs.len() {
-            self.document.paragraphs.push(para);
-        } else {
-            self.document.paragraphs.insert(index, para);
-        }
-        self.dirty = true;
+        self.insert_paragraph_at(index, para);
     }
 
     /// Delete paragraph at index
     #[wasm_bindgen]
     pub fn delete_paragraph(&mut self, index: usize) {
-        if index < self.document.paragraphs.len() {
-            self.document.paragraphs.remove(index);
-            self.dirty = true;
-        }
+        self.remove_paragraph_at(index);
     }
 
     /// Recompute layout if dirty, returns true if layout was recomputed
+    ///
+    /// `measure_fn` is called as `(text, fontSize, fontWeight, italic, fontFamily) => width`.
+    /// The optional `measure_prefixes_fn` takes the same arguments and returns
+    /// the widths of every character prefix of the text at once (see `JsMeasurer`).
+    #[wasm_bindgen]
+    pub fn recompute_layout(
+        &mut self,
+        measure_fn: &js_sys::Function,
+        measure_prefixes_fn: Option<js_sys::Function>,
+    ) -> bool {
+        let measurer = JsMeasurer::new(measure_fn);
+        match &measure_prefixes_fn {
+            Some(prefixes_fn) => self.recompute_layout_with(&measurer.with_prefixes(prefixes_fn)),
+            None => self.recompute_layout_with(&measurer),
+        }
+    }
+
+    /// Register a TTF/OTF font under a family name
+    ///
+    /// Weight and style are read from the font itself, so register each face
+    /// (regular, bold, italic, ...) under the same family name.
+    #[wasm_bindgen]
+    pub fn register_font(&mut self, name: &str, bytes: &[u8]) -> Result<(), JsValue> {
+        self.fonts
+            .register(name, bytes.to_vec())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.layout_cache.invalidate();
+        Ok(())
+    }
+
+    /// Get the registered font family names as a JSON array
     #[wasm_bindgen]
-    pub fn recompute_layout(&mut self, measure_fn: &js_sys::Function) -> bool {
-        if !self.dirty {
+    pub fn get_font_families(&self) -> String {
+        serde_json::to_string(&self.fonts.families()).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Recompute layout if dirty, measuring text with the registered fonts
+    ///
+    /// Text is shaped with rustybuzz, so line breaks do not depend on the
+    /// browser's `measureText`. Falls back to a fixed-width estimate if no
+    /// font has been registered.
+    #[wasm_bindgen]
+    pub fn recompute_layout_with_fonts(&mut self) -> bool {
+        if !self.layout_cache.needs_layout() {
             return false;
         }
+        // Move the registry out so the measurer can borrow it while layout mutates self
+        let fonts = std::mem::take(&mut self.fonts);
+        // Text without a family is drawn in the configured family, so shape it with that too
+        let measurer = ShapingMeasurer::new(&fonts).with_family(&self.layout_config.font_family);
+        let recomputed = self.recompute_layout_with(&measurer);
+        self.fonts = fonts;
+        recomputed
+    }
+
+    /// Get the pages changed by the last layout recomputation as a JSON array
+    ///
+    /// Only these pages need to be repainted. Pages beyond the current
+    /// `page_count()` were removed.
+    #[wasm_bindgen]
+    pub fn get_changed_pages(&self) -> String {
+        serde_json::to_string(&self.changed_pages).unwrap_or_else(|_| "[]".to_string())
+    }
 
-        self.display_lines = layout::compute_layout(
-            &self.document,
-            &self.layout_config,
-            measure_fn,
-        );
-        self.dirty = false;
-        true
+    // =========================================================================
+    // History API
+    // =========================================================================
+
+    /// Undo the last transaction, returns true if anything was undone
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(transaction) => {
+                for operation in &transaction.invert().operations {
+                    self.apply_operation(operation);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone transaction, returns true if anything was redone
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(transaction) => {
+                for operation in &transaction.operations {
+                    self.apply_operation(operation);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if there is anything to undo
+    #[wasm_bindgen]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    /// Check if there is anything to redo
+    #[wasm_bindgen]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Start recording changes into a single undo step
+    ///
+    /// Every call must be balanced by `end_group()`. Groups may be nested.
+    #[wasm_bindgen]
+    pub fn begin_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    /// Finish the undo step started by `begin_group()`
+    #[wasm_bindgen]
+    pub fn end_group(&mut self) {
+        self.history.end_group();
+    }
+
+    /// Drop all undo/redo history
+    #[wasm_bindgen]
+    pub fn clear_history(&mut self) {
+        self.history.clear();
     }
 
     /// Get the total number of pages after layout
@@ -225,17 +522,91 @@ impl Engine {
     }
 
     /// Get render commands for a specific page as JSON
+    ///
+    /// When enabled with `set_render_selection`, the commands include the
+    /// selection set with `set_selection` (or the caret if it is collapsed).
     #[wasm_bindgen]
     pub fn get_render_commands(&self, page_index: usize) -> String {
-        let commands = render::generate_render_commands(
+        let overlay = self.selection_overlay();
+        let commands = render::generate_render_commands_with_overlay(
             &self.display_lines,
             &self.document,
             &self.layout_config,
             page_index,
+            &overlay,
         );
         serde_json::to_string(&commands).unwrap_or_else(|_| "[]".to_string())
     }
 
+    /// Get the caret rectangle at a paragraph position
+    ///
+    /// Returns JSON `{ pageIndex, x, y, width, height }` in page coordinates,
+    /// or "null" if the position is not laid out. At the boundary between two
+    /// wrapped lines the caret is placed at the start of the second line.
+    #[wasm_bindgen]
+    pub fn caret_rect(&self, para: usize, offset: usize) -> String {
+        let rect = position::caret_rect(
+            &self.display_lines,
+            &self.document,
+            &self.layout_config,
+            para,
+            offset,
+            Affinity::Downstream,
+        );
+        serde_json::to_string(&rect).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Get the rectangles covering the text between two paragraph positions
+    ///
+    /// Returns a JSON array of `{ pageIndex, x, y, width, height }`, one per
+    /// display line, in document order.
+    #[wasm_bindgen]
+    pub fn selection_rects(
+        &self,
+        start_para: usize,
+        start_offset: usize,
+        end_para: usize,
+        end_offset: usize,
+    ) -> String {
+        let rects = position::selection_rects(
+            &self.display_lines,
+            &self.document,
+            &self.layout_config,
+            ParagraphPosition { para: start_para, offset: start_offset },
+            ParagraphPosition { para: end_para, offset: end_offset },
+        );
+        serde_json::to_string(&rects).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Set the selection drawn by `get_render_commands`
+    ///
+    /// A collapsed selection (anchor == focus) is drawn as a caret.
+    #[wasm_bindgen]
+    pub fn set_selection(
+        &mut self,
+        anchor_para: usize,
+        anchor_offset: usize,
+        focus_para: usize,
+        focus_offset: usize,
+    ) {
+        self.selection = Some((
+            ParagraphPosition { para: anchor_para, offset: anchor_offset },
+            ParagraphPosition { para: focus_para, offset: focus_offset },
+        ));
+    }
+
+    /// Clear the selection drawn by `get_render_commands`
+    #[wasm_bindgen]
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Include the caret and selection in `get_render_commands`
+    #[wasm_bindgen]
+    pub fn set_render_selection(&mut self, enabled: bool) {
+        self.render_selection = enabled;
+    }
+
     /// Get all display lines as JSON (for debugging)
     #[wasm_bindgen]
     pub fn get_display_lines_json(&self) -> String {
@@ -280,6 +651,26 @@ impl Engine {
         layout::get_page_for_position(&self.display_lines, para_index, char_offset)
     }
 
+    /// Find the document position under a point on a page
+    ///
+    /// `x` and `y` are page coordinates. Returns JSON
+    /// `{ position: { para, offset }, affinity, imageId, table, checkbox, link }`,
+    /// where `table` is `{ tableId, row, col, para, offset }` for clicks inside a
+    /// table and `link` is `{ url, title }` for clicks on a link, or "null" if
+    /// the page has no content.
+    #[wasm_bindgen]
+    pub fn hit_test(&self, page_index: usize, x: f64, y: f64) -> String {
+        let result = position::hit_test(
+            &self.display_lines,
+            &self.document,
+            &self.layout_config,
+            page_index,
+            x,
+            y,
+        );
+        serde_json::to_string(&result).unwrap_or_else(|_| "null".to_string())
+    }
+
     /// Get total number of display lines
     #[wasm_bindgen]
     pub fn display_line_count(&self) -> usize {
@@ -292,7 +683,8 @@ impl Engine {
         match serde_json::from_str::<Document>(json) {
             Ok(doc) => {
                 self.document = doc;
-                self.dirty = true;
+                self.layout_cache.invalidate();
+                self.history.clear();
                 Ok(())
             }
             Err(e) => Err(JsValue::from_str(&e.to_string())),
@@ -306,14 +698,35 @@ impl Engine {
     }
 
     /// Get paragraph metadata as JSON
-    /// Returns: { align, blockType, listType }
+    /// Returns: { style, align, blockType, listType, listLevel, numberFormat, numberTemplate,
+    /// listStart, listContinue, checked, indent, spaceBefore, spaceAfter, lineSpacing,
+    /// keepWithNext, keepTogether, pageBreakBefore, widows, orphans }, where style
+    /// is the paragraph style in effect and align, spacing and pagination are
+    /// resolved through it
     #[wasm_bindgen]
     pub fn get_paragraph_meta(&self, index: usize) -> JsValue {
         if let Some(para) = self.document.paragraphs.get(index) {
+            let format = paragraph_format(&self.document, &para.meta, &self.layout_config);
             let result = serde_json::json!({
-                "align": para.meta.align,
+                "style": para.meta.effective_style_id(),
+                "align": format.align,
                 "blockType": para.meta.block_type,
                 "listType": para.meta.list_type,
+                "listLevel": para.meta.list_level,
+                "numberFormat": para.meta.number_format,
+                "numberTemplate": para.meta.number_template,
+                "listStart": para.meta.list_start,
+                "listContinue": para.meta.list_continue,
+                "checked": para.meta.checked,
+                "indent": para.meta.indent,
+                "spaceBefore": format.space_before,
+                "spaceAfter": format.space_after,
+                "lineSpacing": format.line_spacing,
+                "keepWithNext": format.keep_with_next,
+                "keepTogether": format.keep_together,
+                "pageBreakBefore": format.page_break_before,
+                "widows": format.widows,
+                "orphans": format.orphans,
             });
             JsValue::from_str(&result.to_string())
         } else {
@@ -322,7 +735,7 @@ impl Engine {
     }
 
     /// Get the list type of a paragraph as a string
-    /// Returns: "none", "bullet", or "numbered"
+    /// Returns: "none", "bullet", "numbered" or "checklist"
     #[wasm_bindgen]
     pub fn get_list_type(&self, index: usize) -> String {
         if let Some(para) = self.document.paragraphs.get(index) {
@@ -330,178 +743,461 @@ impl Engine {
                 ListType::None => "none".to_string(),
                 ListType::Bullet => "bullet".to_string(),
                 ListType::Numbered => "numbered".to_string(),
+                ListType::Checklist => "checklist".to_string(),
             }
         } else {
             "none".to_string()
         }
     }
 
-    /// Insert a new paragraph that inherits list type from source paragraph
+    /// Insert a new paragraph that inherits list type, level and numbering
+    /// format from source paragraph
     #[wasm_bindgen]
     pub fn insert_paragraph_with_list(&mut self, index: usize, text: String, source_para: usize) {
-        let list_type = if let Some(para) = self.document.paragraphs.get(source_para) {
-            para.meta.list_type
-        } else {
-            ListType::None
-        };
-
         let mut para = Paragraph::new(text);
-        para.meta.list_type = list_type;
-
-        if index >= self.document.paragraphs.len() {
-            self.document.paragraphs.push(para);
-        } else {
-            self.document.paragraphs.insert(index, para);
+        if let Some(source) = self.document.paragraphs.get(source_para) {
+            para.meta.list_type = source.meta.list_type;
+            para.meta.list_level = source.meta.list_level;
+            para.meta.number_format = source.meta.number_format;
+            para.meta.number_template = source.meta.number_template.clone();
         }
-        self.dirty = true;
+
+        self.insert_paragraph_at(index, para);
     }
 
     /// Set paragraph block type (p, h1, h2, h3, h4, blockquote)
+    ///
+    /// The paragraph takes the block type's built-in style, replacing any
+    /// paragraph style set with `set_paragraph_style`.
     #[wasm_bindgen]
     pub fn set_block_type(&mut self, index: usize, block_type: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(index) {
-            para.meta.block_type = match block_type {
-                "h1" => BlockType::Heading1,
-                "h2" => BlockType::Heading2,
-                "h3" => BlockType::Heading3,
-                "h4" => BlockType::Heading4,
-                "blockquote" => BlockType::Blockquote,
-                _ => BlockType::Paragraph,
-            };
-            self.dirty = true;
-        }
+        let block_type = match block_type {
+            "h1" => BlockType::Heading1,
+            "h2" => BlockType::Heading2,
+            "h3" => BlockType::Heading3,
+            "h4" => BlockType::Heading4,
+            "blockquote" => BlockType::Blockquote,
+            _ => BlockType::Paragraph,
+        };
+        self.edit_paragraph(index, |para| {
+            para.meta.block_type = block_type;
+            para.meta.style_id = None;
+        });
     }
 
-    /// Set paragraph alignment (left, center, right, justify)
+    /// Set paragraph alignment (left, center, right, justify); "" falls back
+    /// to the paragraph style's alignment
     #[wasm_bindgen]
     pub fn set_alignment(&mut self, index: usize, align: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(index) {
-            para.meta.align = match align {
-                "center" => TextAlign::Center,
-                "right" => TextAlign::Right,
-                "justify" => TextAlign::Justify,
-                _ => TextAlign::Left,
-            };
-            self.dirty = true;
-        }
+        let align = match align {
+            "" => None,
+            "center" => Some(TextAlign::Center),
+            "right" => Some(TextAlign::Right),
+            "justify" => Some(TextAlign::Justify),
+            _ => Some(TextAlign::Left),
+        };
+        self.edit_paragraph(index, |para| para.meta.align = align);
     }
 
-    /// Set paragraph list type (none, bullet, numbered)
+    /// Set paragraph list type (none, bullet, numbered, checklist)
     #[wasm_bindgen]
     pub fn set_list_type(&mut self, index: usize, list_type: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(index) {
-            para.meta.list_type = match list_type {
-                "bullet" => ListType::Bullet,
-                "numbered" => ListType::Numbered,
-                _ => ListType::None,
-            };
-            self.dirty = true;
-        }
+        let list_type = match list_type {
+            "bullet" => ListType::Bullet,
+            "numbered" => ListType::Numbered,
+            "checklist" => ListType::Checklist,
+            _ => ListType::None,
+        };
+        self.edit_paragraph(index, |para| {
+            para.meta.list_type = list_type;
+            if list_type == ListType::None {
+                para.meta.list_level = 0;
+            }
+        });
     }
 
     /// Toggle list type for a paragraph
     #[wasm_bindgen]
     pub fn toggle_list(&mut self, index: usize, list_type: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(index) {
-            let target = match list_type {
-                "bullet" => ListType::Bullet,
-                "numbered" => ListType::Numbered,
-                _ => ListType::None,
-            };
+        let target = match list_type {
+            "bullet" => ListType::Bullet,
+            "numbered" => ListType::Numbered,
+            "checklist" => ListType::Checklist,
+            _ => ListType::None,
+        };
 
+        self.edit_paragraph(index, |para| {
             // Toggle: if already this type, remove it; otherwise set it
             para.meta.list_type = if para.meta.list_type == target {
                 ListType::None
             } else {
                 target
             };
-            self.dirty = true;
+            if para.meta.list_type == ListType::None {
+                para.meta.list_level = 0;
+            }
+        });
+    }
+
+    /// Nest a list item one level deeper (Tab)
+    ///
+    /// Returns false if the paragraph is not a list item or is already at the
+    /// deepest level.
+    #[wasm_bindgen]
+    pub fn indent_list_item(&mut self, index: usize) -> bool {
+        match self.document.paragraphs.get(index) {
+            Some(para) if para.meta.list_type != ListType::None && para.meta.list_level < MAX_LIST_LEVEL => {
+                self.edit_paragraph(index, |para| para.meta.list_level += 1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Move a list item one level up (Shift+Tab)
+    ///
+    /// Outdenting a top-level item turns it back into a normal paragraph.
+    /// Returns false if the paragraph is not a list item.
+    #[wasm_bindgen]
+    pub fn outdent_list_item(&mut self, index: usize) -> bool {
+        match self.document.paragraphs.get(index) {
+            Some(para) if para.meta.list_type != ListType::None => self.edit_paragraph(index, |para| {
+                if para.meta.list_level > 0 {
+                    para.meta.list_level -= 1;
+                } else {
+                    para.meta.list_type = ListType::None;
+                }
+            }),
+            _ => false,
+        }
+    }
+
+    /// Set a paragraph's indents in pixels: `left` and `right` apply to every
+    /// line, `first_line` adds to the first line and `hanging` to the others.
+    /// Negative or non-finite values count as 0.
+    #[wasm_bindgen]
+    pub fn set_paragraph_indent(&mut self, index: usize, left: f64, right: f64, first_line: f64, hanging: f64) {
+        let clean = |value: f64| if value.is_finite() { value.max(0.0) } else { 0.0 };
+        let indent = ParagraphIndent {
+            left: clean(left),
+            right: clean(right),
+            first_line: clean(first_line),
+            hanging: clean(hanging),
+        };
+        self.edit_paragraph(index, |para| para.meta.indent = indent);
+    }
+
+    /// Set the space above and below a paragraph in pixels; `undefined` falls
+    /// back to the paragraph style (and then to no space before and the
+    /// layout's paragraph spacing after). Negative values count as 0.
+    #[wasm_bindgen]
+    pub fn set_paragraph_spacing(&mut self, index: usize, before: Option<f64>, after: Option<f64>) {
+        let clean = |value: Option<f64>| value.filter(|v| v.is_finite()).map(|v| v.max(0.0));
+        self.edit_paragraph(index, |para| {
+            para.meta.space_before = clean(before);
+            para.meta.space_after = clean(after);
+        });
+    }
+
+    /// Set a paragraph's line spacing: "multiple" (of the font size),
+    /// "exact" or "at-least" (pixels); "" falls back to the paragraph style.
+    /// Returns false for an unknown mode or a value that is not positive.
+    #[wasm_bindgen]
+    pub fn set_line_spacing(&mut self, index: usize, mode: &str, value: f64) -> bool {
+        let spacing = match mode {
+            "" => None,
+            _ if !(value.is_finite() && value > 0.0) => return false,
+            "multiple" => Some(LineSpacing::Multiple(value)),
+            "exact" => Some(LineSpacing::Exact(value)),
+            "at-least" => Some(LineSpacing::AtLeast(value)),
+            _ => return false,
+        };
+        if index >= self.document.paragraphs.len() {
+            return false;
+        }
+        self.edit_paragraph(index, |para| para.meta.line_spacing = spacing);
+        true
+    }
+
+    /// Set a paragraph's pagination rules; `undefined` falls back to the
+    /// paragraph style (headings are kept with the next paragraph)
+    #[wasm_bindgen]
+    pub fn set_pagination(
+        &mut self,
+        index: usize,
+        keep_with_next: Option<bool>,
+        keep_together: Option<bool>,
+        page_break_before: Option<bool>,
+    ) {
+        self.edit_paragraph(index, |para| {
+            para.meta.keep_with_next = keep_with_next;
+            para.meta.keep_together = keep_together;
+            para.meta.page_break_before = page_break_before;
+        });
+    }
+
+    /// Set the minimum number of lines a split paragraph keeps at the top
+    /// (widows) and bottom (orphans) of a page or column; `undefined` falls
+    /// back to the paragraph style, or 2. 1 turns the control off.
+    #[wasm_bindgen]
+    pub fn set_widow_orphan_lines(&mut self, index: usize, widows: Option<u8>, orphans: Option<u8>) {
+        self.edit_paragraph(index, |para| {
+            para.meta.widows = widows.map(|n| n.max(1));
+            para.meta.orphans = orphans.map(|n| n.max(1));
+        });
+    }
+
+    /// Move a paragraph's left indent to the next multiple of `INDENT_STEP`,
+    /// leaving at least one step of the column for text; list items are
+    /// nested one level deeper instead. Returns false if nothing changed.
+    #[wasm_bindgen]
+    pub fn increase_indent(&mut self, index: usize) -> bool {
+        let Some(para) = self.document.paragraphs.get(index) else {
+            return false;
+        };
+        if para.meta.list_type != ListType::None {
+            return self.indent_list_item(index);
+        }
+        let max_left = (self.layout_config.column_width() - INDENT_STEP).max(0.0);
+        let left = ((para.meta.indent.left / INDENT_STEP).floor() + 1.0) * INDENT_STEP;
+        self.edit_paragraph(index, |para| para.meta.indent.left = left.min(max_left).max(para.meta.indent.left))
+    }
+
+    /// Move a paragraph's left indent back to the previous multiple of
+    /// `INDENT_STEP`; list items are outdented instead. Returns false if
+    /// nothing changed.
+    #[wasm_bindgen]
+    pub fn decrease_indent(&mut self, index: usize) -> bool {
+        let Some(para) = self.document.paragraphs.get(index) else {
+            return false;
+        };
+        if para.meta.list_type != ListType::None {
+            return self.outdent_list_item(index);
+        }
+        let left = ((para.meta.indent.left / INDENT_STEP).ceil() - 1.0).max(0.0) * INDENT_STEP;
+        self.edit_paragraph(index, |para| para.meta.indent.left = left)
+    }
+
+    /// Tick or untick a checklist item
+    ///
+    /// Returns false if the paragraph is not a checklist item.
+    #[wasm_bindgen]
+    pub fn toggle_checklist_item(&mut self, index: usize) -> bool {
+        match self.document.paragraphs.get(index) {
+            Some(para) if para.meta.list_type == ListType::Checklist => {
+                self.edit_paragraph(index, |para| para.meta.checked = !para.meta.checked)
+            }
+            _ => false,
         }
     }
 
+    /// Set the number format of a numbered list item
+    ///
+    /// Formats: decimal, decimal-zero, lower-alpha, upper-alpha, lower-roman,
+    /// upper-roman. Anything else restores the per-level default (1., a., i.).
+    #[wasm_bindgen]
+    pub fn set_number_format(&mut self, index: usize, format: &str) {
+        let format = match format {
+            "decimal" => Some(NumberFormat::Decimal),
+            "decimal-zero" => Some(NumberFormat::DecimalZero),
+            "lower-alpha" => Some(NumberFormat::LowerAlpha),
+            "upper-alpha" => Some(NumberFormat::UpperAlpha),
+            "lower-roman" => Some(NumberFormat::LowerRoman),
+            "upper-roman" => Some(NumberFormat::UpperRoman),
+            _ => None,
+        };
+        self.edit_paragraph(index, |para| para.meta.number_format = format);
+    }
+
+    /// Set the marker template of a numbered list item
+    ///
+    /// `%1` to `%9` stand for the numbers of the nesting levels, e.g. "(%1)",
+    /// "Article %1." or "%1.%2.%3" for outline numbering. An empty template
+    /// restores the default "%N." for the item's level.
+    #[wasm_bindgen]
+    pub fn set_number_template(&mut self, index: usize, template: &str) {
+        let template = (!template.is_empty()).then(|| template.to_string());
+        self.edit_paragraph(index, |para| para.meta.number_template = template);
+    }
+
+    /// Restart the numbering of a list item at `start`, or clear the restart
+    #[wasm_bindgen]
+    pub fn set_list_start(&mut self, index: usize, start: Option<usize>) {
+        self.edit_paragraph(index, |para| para.meta.list_start = start);
+    }
+
+    /// Let a list item continue the numbering of the previous list when it
+    /// follows non-list paragraphs
+    #[wasm_bindgen]
+    pub fn set_list_continue(&mut self, index: usize, continue_previous: bool) {
+        self.edit_paragraph(index, |para| para.meta.list_continue = continue_previous);
+    }
+
     /// Toggle bold for a range of text in a paragraph
     #[wasm_bindgen]
     pub fn toggle_bold(&mut self, para_index: usize, start: usize, end: usize) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
+        self.edit_paragraph(para_index, |para| {
             // Check if the range is already bold
-            let is_bold = para.styles_in_range(start, end)
-                .iter()
-                .all(|s| s.bold);
+            let is_bold = para.range_has_style(start, end, |s| s.bold);
 
             para.apply_style(start, end, |style| {
                 style.bold = !is_bold;
             });
-            self.dirty = true;
-        }
+        });
     }
 
     /// Toggle italic for a range of text in a paragraph
     #[wasm_bindgen]
     pub fn toggle_italic(&mut self, para_index: usize, start: usize, end: usize) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
-            let is_italic = para.styles_in_range(start, end)
-                .iter()
-                .all(|s| s.italic);
+        self.edit_paragraph(para_index, |para| {
+            let is_italic = para.range_has_style(start, end, |s| s.italic);
 
             para.apply_style(start, end, |style| {
                 style.italic = !is_italic;
             });
-            self.dirty = true;
-        }
+        });
     }
 
     /// Toggle underline for a range of text in a paragraph
     #[wasm_bindgen]
     pub fn toggle_underline(&mut self, para_index: usize, start: usize, end: usize) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
-            let is_underline = para.styles_in_range(start, end)
-                .iter()
-                .all(|s| s.underline);
+        self.edit_paragraph(para_index, |para| {
+            let is_underline = para.range_has_style(start, end, |s| s.underline);
 
             para.apply_style(start, end, |style| {
                 style.underline = !is_underline;
             });
-            self.dirty = true;
-        }
+        });
     }
 
     /// Toggle strikethrough for a range of text in a paragraph
     #[wasm_bindgen]
     pub fn toggle_strikethrough(&mut self, para_index: usize, start: usize, end: usize) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
-            let is_strike = para.styles_in_range(start, end)
-                .iter()
-                .all(|s| s.strikethrough);
+        self.edit_paragraph(para_index, |para| {
+            let is_strike = para.range_has_style(start, end, |s| s.strikethrough);
 
             para.apply_style(start, end, |style| {
                 style.strikethrough = !is_strike;
             });
-            self.dirty = true;
-        }
+        });
     }
 
-    /// Set text color for a range
+    /// Toggle superscript for a range (clears subscript)
     #[wasm_bindgen]
-    pub fn set_text_color(&mut self, para_index: usize, start: usize, end: usize, color: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
-            let color_opt = if color.is_empty() { None } else { Some(color.to_string()) };
-            para.apply_style(start, end, |style| {
-                style.color = color_opt.clone();
-            });
-            self.dirty = true;
-        }
+    pub fn toggle_superscript(&mut self, para_index: usize, start: usize, end: usize) {
+        self.toggle_vertical_align(para_index, start, end, VerticalAlign::Superscript);
     }
 
-    /// Set background/highlight color for a range
+    /// Toggle subscript for a range (clears superscript)
     #[wasm_bindgen]
-    pub fn set_highlight_color(&mut self, para_index: usize, start: usize, end: usize, color: &str) {
-        if let Some(para) = self.document.paragraphs.get_mut(para_index) {
-            let color_opt = if color.is_empty() { None } else { Some(color.to_string()) };
-            para.apply_style(start, end, |style| {
-                style.background = color_opt.clone();
-            });
-            self.dirty = true;
-        }
+    pub fn toggle_subscript(&mut self, para_index: usize, start: usize, end: usize) {
+        self.toggle_vertical_align(para_index, start, end, VerticalAlign::Subscript);
+    }
+
+    /// Toggle small caps for a range (clears all caps)
+    #[wasm_bindgen]
+    pub fn toggle_small_caps(&mut self, para_index: usize, start: usize, end: usize) {
+        self.toggle_caps(para_index, start, end, CapsStyle::SmallCaps);
+    }
+
+    /// Toggle all caps for a range (clears small caps)
+    #[wasm_bindgen]
+    pub fn toggle_all_caps(&mut self, para_index: usize, start: usize, end: usize) {
+        self.toggle_caps(para_index, start, end, CapsStyle::AllCaps);
+    }
+
+    /// Set text color for a range
+    #[wasm_bindgen]
+    pub fn set_text_color(&mut self, para_index: usize, start: usize, end: usize, color: &str) {
+        let color_opt = if color.is_empty() { None } else { Some(color.to_string()) };
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.color = color_opt.clone();
+            });
+        });
+    }
+
+    /// Set background/highlight color for a range
+    #[wasm_bindgen]
+    pub fn set_highlight_color(&mut self, para_index: usize, start: usize, end: usize, color: &str) {
+        let color_opt = if color.is_empty() { None } else { Some(color.to_string()) };
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.background = color_opt.clone();
+            });
+        });
+    }
+
+    /// Set the font family for a range (empty string restores the default family)
+    #[wasm_bindgen]
+    pub fn set_font_family(&mut self, para_index: usize, start: usize, end: usize, family: &str) {
+        let family = family.trim();
+        let family = (!family.is_empty()).then(|| family.to_string());
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.font_family = family.clone();
+            });
+        });
+    }
+
+    /// Set the font size in pixels for a range (0 restores the paragraph's size)
+    #[wasm_bindgen]
+    pub fn set_font_size(&mut self, para_index: usize, start: usize, end: usize, size: f64) {
+        let size = (size > 0.0).then_some(size);
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.font_size = size;
+            });
+        });
+    }
+
+    /// Set the numeric font weight (100-900) for a range (0 restores the
+    /// bold flag / paragraph weight)
+    #[wasm_bindgen]
+    pub fn set_font_weight(&mut self, para_index: usize, start: usize, end: usize, weight: u16) {
+        let weight = (weight > 0).then(|| weight.clamp(100, 900));
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.font_weight = weight;
+            });
+        });
+    }
+
+    /// Turn a range of text into a link
+    ///
+    /// Only http(s), mailto and "#anchor" URLs are accepted; returns false
+    /// (and changes nothing) for any other URL or an empty range.
+    #[wasm_bindgen]
+    pub fn set_link(&mut self, para_index: usize, start: usize, end: usize, url: &str, title: Option<String>) -> bool {
+        let Some(link) = Link::new(url, title) else {
+            return false;
+        };
+        if start >= end {
+            return false;
+        }
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.link = Some(link.clone());
+            });
+        })
+    }
+
+    /// Remove links from a range of text
+    #[wasm_bindgen]
+    pub fn remove_link(&mut self, para_index: usize, start: usize, end: usize) {
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| {
+                style.link = None;
+            });
+        });
+    }
+
+    /// Get the link covering the character at `offset`
+    ///
+    /// Returns JSON `{ url, title }`, or "null" if there is no link.
+    #[wasm_bindgen]
+    pub fn get_link_at(&self, para_index: usize, offset: usize) -> String {
+        let link = self.document.paragraphs.get(para_index).and_then(|p| p.link_at(offset));
+        serde_json::to_string(&link).unwrap_or_else(|_| "null".to_string())
     }
 
     /// Get styles for a paragraph as JSON array
@@ -515,6 +1211,78 @@ impl Engine {
         }
     }
 
+    /// Get the document's named paragraph and character styles as a JSON array
+    #[wasm_bindgen]
+    pub fn get_styles(&self) -> String {
+        serde_json::to_string(&self.document.styles).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Add a named style, or replace the style with the same ID, from JSON
+    /// (`{ id, name, kind, based_on?, ...properties }`)
+    ///
+    /// Every paragraph using the style, directly or through inheritance, is
+    /// restyled. Returns false if the JSON is invalid, `based_on` names an
+    /// unknown style or would make the style inherit from itself, or the kind
+    /// of an existing style would change.
+    #[wasm_bindgen]
+    pub fn set_style(&mut self, json: &str) -> bool {
+        let Ok(style) = serde_json::from_str::<NamedStyle>(json) else {
+            return false;
+        };
+        if style.id.is_empty() {
+            return false;
+        }
+        if let Some(parent) = &style.based_on {
+            if self.document.style(parent).is_none() || self.document.would_cycle(&style.id, parent) {
+                return false;
+            }
+        }
+        if self.document.style(&style.id).is_some_and(|existing| existing.kind != style.kind) {
+            return false;
+        }
+        self.edit_styles(|styles| match styles.iter_mut().find(|s| s.id == style.id) {
+            Some(existing) => *existing = style,
+            None => styles.push(style),
+        });
+        true
+    }
+
+    /// Apply a named paragraph style to a paragraph; "" returns it to the
+    /// built-in style of its block type. Direct formatting stays in effect.
+    /// Returns false if the ID is not a paragraph style.
+    #[wasm_bindgen]
+    pub fn set_paragraph_style(&mut self, index: usize, style_id: &str) -> bool {
+        let style_id = match style_id {
+            "" => None,
+            id if self.document.style(id).is_some_and(|s| s.kind == StyleKind::Paragraph) => Some(id.to_string()),
+            _ => return false,
+        };
+        if index >= self.document.paragraphs.len() {
+            return false;
+        }
+        self.edit_paragraph(index, |para| para.meta.style_id = style_id);
+        true
+    }
+
+    /// Apply a named character style to a range; "" removes it. Direct
+    /// formatting of the range stays in effect. Returns false if the ID is not
+    /// a character style.
+    #[wasm_bindgen]
+    pub fn set_character_style(&mut self, para_index: usize, start: usize, end: usize, style_id: &str) -> bool {
+        let style_id = match style_id {
+            "" => None,
+            id if self.document.style(id).is_some_and(|s| s.kind == StyleKind::Character) => Some(id.to_string()),
+            _ => return false,
+        };
+        if para_index >= self.document.paragraphs.len() {
+            return false;
+        }
+        self.edit_paragraph(para_index, |para| {
+            para.apply_style(start, end, |style| style.style_id = style_id.clone());
+        });
+        true
+    }
+
     /// Add an image to the document
     #[wasm_bindgen]
     pub fn add_image(
@@ -529,7 +1297,9 @@ impl Engine {
         let mut image = DocumentImage::new(id.to_string(), src.to_string(), width, height);
         image.natural_width = natural_width;
         image.natural_height = natural_height;
-        self.document.images.push(image);
+        let index = self.document.images.len();
+        self.document.images.push(image.clone());
+        self.history.record(Operation::InsertImage { index, image });
     }
 
     /// Insert an image paragraph at the given index
@@ -538,12 +1308,7 @@ impl Engine {
         // Create the image marker paragraph (U+FFFC + image ID)
         let text = format!("\u{FFFC}{}", image_id);
         let para = Paragraph::new(text);
-        if index >= self.document.paragraphs.len() {
-            self.document.paragraphs.push(para);
-        } else {
-            self.document.paragraphs.insert(index, para);
-        }
-        self.dirty = true;
+        self.insert_paragraph_at(index, para);
     }
 
     /// Get image info by ID
@@ -560,26 +1325,30 @@ impl Engine {
     /// Update image dimensions
     #[wasm_bindgen]
     pub fn update_image_size(&mut self, id: &str, width: f64, height: f64) {
-        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
+        self.edit_image(id, |image| {
             image.width = width;
             image.height = height;
-            self.dirty = true;
-        }
+        });
     }
 
     /// Delete an image from the document
     #[wasm_bindgen]
     pub fn delete_image(&mut self, id: &str) {
-        self.document.images.retain(|img| img.id != id);
+        self.history.begin_group();
         // Also remove any image paragraphs referencing this image
-        self.document.paragraphs.retain(|p| {
-            if let Some(img_id) = p.image_id() {
-                img_id != id
-            } else {
-                true
+        for index in (0..self.document.paragraphs.len()).rev() {
+            if self.document.paragraphs[index].image_id() == Some(id) {
+                self.remove_paragraph_at(index);
             }
-        });
-        self.dirty = true;
+        }
+        if let Some(index) = self.document.images.iter().position(|img| img.id == id) {
+            let image = self.document.images.remove(index);
+            if image.position_mode == ImagePositionMode::FixedPosition {
+                self.layout_cache.invalidate();
+            }
+            self.history.record(Operation::RemoveImage { index, image });
+        }
+        self.history.end_group();
     }
 
     /// Insert a page break at the given paragraph index
@@ -587,68 +1356,57 @@ impl Engine {
     pub fn insert_page_break(&mut self, index: usize) {
         // Create the page break marker paragraph (U+FFFD)
         let para = Paragraph::new("\u{FFFD}".to_string());
-        if index >= self.document.paragraphs.len() {
-            self.document.paragraphs.push(para);
-        } else {
-            self.document.paragraphs.insert(index, para);
-        }
-        self.dirty = true;
+        self.insert_paragraph_at(index, para);
     }
 
     /// Set image wrap style
     #[wasm_bindgen]
     pub fn set_image_wrap_style(&mut self, id: &str, wrap_style: &str) {
-        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
-            image.wrap_style = match wrap_style {
-                "inline" => ImageWrapStyle::Inline,
-                "top-bottom" => ImageWrapStyle::TopBottom,
-                "square" => ImageWrapStyle::Square,
-                "tight" => ImageWrapStyle::Tight,
-                "through" => ImageWrapStyle::Through,
-                "behind" => ImageWrapStyle::Behind,
-                "in-front" => ImageWrapStyle::InFront,
-                _ => ImageWrapStyle::Inline,
-            };
-            self.dirty = true;
-        }
+        let wrap_style = match wrap_style {
+            "inline" => ImageWrapStyle::Inline,
+            "top-bottom" => ImageWrapStyle::TopBottom,
+            "square" => ImageWrapStyle::Square,
+            "tight" => ImageWrapStyle::Tight,
+            "through" => ImageWrapStyle::Through,
+            "behind" => ImageWrapStyle::Behind,
+            "in-front" => ImageWrapStyle::InFront,
+            _ => ImageWrapStyle::Inline,
+        };
+        self.edit_image(id, |image| image.wrap_style = wrap_style);
     }
 
     /// Set image to fixed position (for dragging)
     #[wasm_bindgen]
     pub fn set_image_position(&mut self, id: &str, x: f64, y: f64, page_index: usize) {
-        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
+        self.edit_image(id, |image| {
             image.x = Some(x);
             image.y = Some(y);
             image.page_index = Some(page_index);
             image.position_mode = ImagePositionMode::FixedPosition;
-            self.dirty = true;
-        }
+        });
     }
 
     /// Clear image position (reset to move-with-text mode)
     #[wasm_bindgen]
     pub fn clear_image_position(&mut self, id: &str) {
-        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
+        self.edit_image(id, |image| {
             image.x = None;
             image.y = None;
             image.page_index = None;
             image.position_mode = ImagePositionMode::MoveWithText;
-            self.dirty = true;
-        }
+        });
     }
 
     /// Set image horizontal alignment
     #[wasm_bindgen]
     pub fn set_image_horizontal_align(&mut self, id: &str, align: &str) {
-        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
-            image.horizontal_align = match align {
-                "left" => HorizontalAlign::Left,
-                "center" => HorizontalAlign::Center,
-                "right" => HorizontalAlign::Right,
-                _ => HorizontalAlign::Left,
-            };
-            self.dirty = true;
-        }
+        let align = match align {
+            "left" => HorizontalAlign::Left,
+            "center" => HorizontalAlign::Center,
+            "right" => HorizontalAlign::Right,
+            _ => HorizontalAlign::Left,
+        };
+        self.edit_image(id, |image| image.horizontal_align = align);
     }
 
     // =========================================================================
@@ -658,11 +1416,8 @@ impl Engine {
     /// Create a new table with the specified dimensions and return its ID
     #[wasm_bindgen]
     pub fn create_table(&mut self, rows: usize, cols: usize) -> String {
-        let id = format!("table_{}", self.document.tables.len());
-        let table = DocumentTable::new(id.clone(), rows, cols, self.layout_config.column_width());
-        self.document.tables.push(table);
-        self.dirty = true;
-        id
+        let table = DocumentTable::new(self.next_table_id(), rows, cols, self.layout_config.column_width());
+        self.add_table(table)
     }
 
     /// Insert a table paragraph at the given index
@@ -670,9 +1425,7 @@ impl Engine {
     pub fn insert_table_paragraph(&mut self, index: usize, table_id: &str) {
         let text = format!("\u{FFFB}{}", table_id);
         let para = Paragraph::new(text);
-        let idx = index.min(self.document.paragraphs.len());
-        self.document.paragraphs.insert(idx, para);
-        self.dirty = true;
+        self.insert_paragraph_at(index, para);
     }
 
     /// Get table info by ID as JSON
@@ -686,136 +1439,453 @@ impl Engine {
         }
     }
 
-    /// Get cell text
+    /// Get cell text, with the cell's paragraphs separated by newlines
     #[wasm_bindgen]
     pub fn get_cell_text(&self, table_id: &str, row: usize, col: usize) -> Option<String> {
         self.document.tables
             .iter()
             .find(|t| t.id == table_id)
             .and_then(|t| t.get_cell(row, col))
-            .map(|c| c.text.clone())
+            .map(|c| c.text())
     }
 
-    /// Set cell text
+    /// Set cell text, making a paragraph of each line
     #[wasm_bindgen]
     pub fn set_cell_text(&mut self, table_id: &str, row: usize, col: usize, text: &str) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            if let Some(cell) = table.get_cell_mut(row, col) {
-                cell.text = text.to_string();
-                self.dirty = true;
+        self.edit_cell(table_id, row, col, |cell| cell.set_text(text));
+    }
+
+    /// Get the paragraphs of a cell as JSON, in the document's paragraph format
+    #[wasm_bindgen]
+    pub fn get_cell_paragraphs(&self, table_id: &str, row: usize, col: usize) -> Option<String> {
+        let cell = self.document.tables
+            .iter()
+            .find(|t| t.id == table_id)
+            .and_then(|t| t.get_cell(row, col))?;
+        serde_json::to_string(&cell.paragraphs).ok()
+    }
+
+    /// Insert text at a character offset of a cell paragraph; each newline in
+    /// `text` starts a new paragraph
+    /// Returns false if the cell or paragraph does not exist
+    #[wasm_bindgen]
+    pub fn insert_cell_text(
+        &mut self,
+        table_id: &str,
+        row: usize,
+        col: usize,
+        para: usize,
+        offset: usize,
+        text: &str,
+    ) -> bool {
+        self.edit_cell(table_id, row, col, |cell| {
+            let Some(target) = cell.paragraphs.get(para) else {
+                return false;
+            };
+            let (mut index, mut offset) = (para, offset.min(target.char_len()));
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    let rest = cell.paragraphs[index].split_off(offset);
+                    index += 1;
+                    cell.paragraphs.insert(index, rest);
+                    offset = 0;
+                }
+                cell.paragraphs[index].insert_text(offset, line);
+                offset += line.chars().count();
             }
+            true
+        })
+        .unwrap_or(false)
+    }
+
+    /// Delete the content of a cell between two positions, given as
+    /// paragraph index and character offset; the paragraphs at either end
+    /// are joined
+    /// Returns false if the cell does not exist or the range is empty
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn delete_cell_text(
+        &mut self,
+        table_id: &str,
+        row: usize,
+        col: usize,
+        start_para: usize,
+        start_offset: usize,
+        end_para: usize,
+        end_offset: usize,
+    ) -> bool {
+        self.edit_cell(table_id, row, col, |cell| {
+            if end_para >= cell.paragraphs.len() || (start_para, start_offset) >= (end_para, end_offset) {
+                return false;
+            }
+            if start_para == end_para {
+                cell.paragraphs[start_para].delete_text(start_offset, end_offset);
+                return true;
+            }
+            let mut last = cell.paragraphs.drain(start_para + 1..=end_para).next_back().expect("range is not empty");
+            last.delete_text(0, end_offset);
+            let first = &mut cell.paragraphs[start_para];
+            first.delete_text(start_offset, first.char_len());
+            first.append(last);
+            true
+        })
+        .unwrap_or(false)
+    }
+
+    /// Set the list type (none, bullet, numbered, checklist) of a cell paragraph
+    #[wasm_bindgen]
+    pub fn set_cell_list_type(&mut self, table_id: &str, row: usize, col: usize, para: usize, list_type: &str) {
+        let list_type = match list_type {
+            "bullet" => ListType::Bullet,
+            "numbered" => ListType::Numbered,
+            "checklist" => ListType::Checklist,
+            _ => ListType::None,
+        };
+        self.edit_cell(table_id, row, col, |cell| {
+            if let Some(para) = cell.paragraphs.get_mut(para) {
+                para.meta.list_type = list_type;
+                if list_type == ListType::None {
+                    para.meta.list_level = 0;
+                }
+            }
+        });
+    }
+
+    /// Insert an image paragraph at the given index of a cell's paragraphs
+    #[wasm_bindgen]
+    pub fn insert_cell_image_paragraph(&mut self, table_id: &str, row: usize, col: usize, index: usize, image_id: &str) {
+        let para = Paragraph::new(format!("\u{FFFC}{}", image_id));
+        self.edit_cell(table_id, row, col, |cell| {
+            cell.paragraphs.insert(index.min(cell.paragraphs.len()), para);
+        });
+    }
+
+    /// Nest a table in a cell, inserting its table paragraph at the given
+    /// index of the cell's paragraphs
+    /// Returns false if the cell does not exist or the nested table would
+    /// end up inside itself
+    #[wasm_bindgen]
+    pub fn insert_cell_table_paragraph(
+        &mut self,
+        table_id: &str,
+        row: usize,
+        col: usize,
+        index: usize,
+        nested_table_id: &str,
+    ) -> bool {
+        if nested_table_id == table_id || self.document.enclosing_tables(table_id).contains(&nested_table_id) {
+            return false;
         }
+        let para = Paragraph::new(format!("\u{FFFB}{}", nested_table_id));
+        self.edit_cell(table_id, row, col, |cell| {
+            cell.paragraphs.insert(index.min(cell.paragraphs.len()), para);
+        })
+        .is_some()
     }
 
     /// Set cell background color
     #[wasm_bindgen]
     pub fn set_cell_background(&mut self, table_id: &str, row: usize, col: usize, color: &str) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
+        self.edit_table(table_id, |table| {
             if let Some(cell) = table.get_cell_mut(row, col) {
                 cell.background = if color.is_empty() { None } else { Some(color.to_string()) };
-                self.dirty = true;
             }
-        }
+        });
     }
 
-    /// Set cell alignment
+    /// Set the alignment of every paragraph of a cell
     #[wasm_bindgen]
     pub fn set_cell_align(&mut self, table_id: &str, row: usize, col: usize, align: &str) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            if let Some(cell) = table.get_cell_mut(row, col) {
-                cell.align = match align {
-                    "center" => TextAlign::Center,
-                    "right" => TextAlign::Right,
-                    "justify" => TextAlign::Justify,
-                    _ => TextAlign::Left,
-                };
-                self.dirty = true;
+        let align = match align {
+            "center" => TextAlign::Center,
+            "right" => TextAlign::Right,
+            "justify" => TextAlign::Justify,
+            _ => TextAlign::Left,
+        };
+        self.edit_cell(table_id, row, col, |cell| cell.set_align(align));
+    }
+
+    /// Set where a cell's content sits when its row is taller: "top",
+    /// "middle" or "bottom". Returns false for an unknown alignment or cell.
+    #[wasm_bindgen]
+    pub fn set_cell_vertical_align(&mut self, table_id: &str, row: usize, col: usize, align: &str) -> bool {
+        let align = match align {
+            "top" => CellVerticalAlign::Top,
+            "middle" => CellVerticalAlign::Middle,
+            "bottom" => CellVerticalAlign::Bottom,
+            _ => return false,
+        };
+        self.edit_cell(table_id, row, col, |cell| cell.vertical_align = align)
+            .is_some()
+    }
+
+    /// Set the padding of a cell in pixels
+    #[allow(clippy::too_many_arguments)]
+    #[wasm_bindgen]
+    pub fn set_cell_padding(
+        &mut self,
+        table_id: &str,
+        row: usize,
+        col: usize,
+        top: f64,
+        right: f64,
+        bottom: f64,
+        left: f64,
+    ) {
+        let padding = CellPadding {
+            top: top.max(0.0),
+            right: right.max(0.0),
+            bottom: bottom.max(0.0),
+            left: left.max(0.0),
+        };
+        self.edit_cell(table_id, row, col, |cell| cell.padding = Some(padding));
+    }
+
+    /// Give a cell the table's cell padding again
+    #[wasm_bindgen]
+    pub fn clear_cell_padding(&mut self, table_id: &str, row: usize, col: usize) {
+        self.edit_cell(table_id, row, col, |cell| cell.padding = None);
+    }
+
+    /// Set the padding of the cells of a table that do not set their own
+    #[wasm_bindgen]
+    pub fn set_table_cell_padding(&mut self, table_id: &str, top: f64, right: f64, bottom: f64, left: f64) {
+        self.edit_table(table_id, |table| {
+            table.cell_padding = CellPadding {
+                top: top.max(0.0),
+                right: right.max(0.0),
+                bottom: bottom.max(0.0),
+                left: left.max(0.0),
+            };
+        });
+    }
+
+    /// Set the border of one side of a cell ("top", "right", "bottom",
+    /// "left") or of all of them ("all"). `style` is "solid", "dashed",
+    /// "dotted", "double" or "none"; an empty style clears the side, so it
+    /// takes the table's border again. Where two cells meet, the stronger
+    /// border is drawn (see the render module), no wider than the table's
+    /// border width. Returns false for an unknown side, style or cell.
+    #[allow(clippy::too_many_arguments)]
+    #[wasm_bindgen]
+    pub fn set_cell_border(
+        &mut self,
+        table_id: &str,
+        row: usize,
+        col: usize,
+        side: &str,
+        width: f64,
+        color: &str,
+        style: &str,
+    ) -> bool {
+        let style = match style {
+            "" => None,
+            "solid" => Some(BorderStyle::Solid),
+            "dashed" => Some(BorderStyle::Dashed),
+            "dotted" => Some(BorderStyle::Dotted),
+            "double" => Some(BorderStyle::Double),
+            "none" => Some(BorderStyle::None),
+            _ => return false,
+        };
+        let border = style.map(|style| CellBorder {
+            width: width.max(0.0),
+            color: color.to_string(),
+            style,
+        });
+        if !matches!(side, "top" | "right" | "bottom" | "left" | "all") {
+            return false;
+        }
+        self.edit_cell(table_id, row, col, |cell| {
+            let borders = &mut cell.borders;
+            for (name, slot) in [
+                ("top", &mut borders.top),
+                ("right", &mut borders.right),
+                ("bottom", &mut borders.bottom),
+                ("left", &mut borders.left),
+            ] {
+                if side == name || side == "all" {
+                    *slot = border.clone();
+                }
             }
+        })
+        .is_some()
+    }
+
+    /// Sort the rows of a table below its header rows by the text in a
+    /// column, as numbers if `numeric`. Returns false for an unknown table or
+    /// column, or if cells merged across rows would have to move apart.
+    #[wasm_bindgen]
+    pub fn sort_table(&mut self, table_id: &str, col: usize, ascending: bool, numeric: bool) -> bool {
+        self.edit_table(table_id, |table| table.sort_rows(col, ascending, numeric))
+            .unwrap_or(false)
+    }
+
+    /// Move a row of a table to another position. Returns false if a
+    /// position is out of range or a cell merged across rows is in the way.
+    #[wasm_bindgen]
+    pub fn move_table_row(&mut self, table_id: &str, from: usize, to: usize) -> bool {
+        self.edit_table(table_id, |table| table.move_row(from, to))
+            .unwrap_or(false)
+    }
+
+    /// Move a column of a table to another position. Returns false if a
+    /// position is out of range or a cell merged across columns is in the way.
+    #[wasm_bindgen]
+    pub fn move_table_column(&mut self, table_id: &str, from: usize, to: usize) -> bool {
+        self.edit_table(table_id, |table| table.move_column(from, to))
+            .unwrap_or(false)
+    }
+
+    /// Turn paragraphs `start` to `end` (inclusive) into a table with a row
+    /// per paragraph, splitting each at `delimiter` ("\t" for tab-separated
+    /// text) into cells. Returns the new table's ID, or None if the range is
+    /// not in the document.
+    #[wasm_bindgen]
+    pub fn convert_paragraphs_to_table(&mut self, start: usize, end: usize, delimiter: &str) -> Option<String> {
+        if start > end || end >= self.document.paragraphs.len() {
+            return None;
+        }
+        let table = DocumentTable::from_paragraphs(
+            self.next_table_id(),
+            &self.document.paragraphs[start..=end],
+            delimiter,
+            self.layout_config.column_width(),
+        );
+
+        self.history.begin_group();
+        let id = self.add_table(table);
+        for index in (start..=end).rev() {
+            self.remove_paragraph_at(index);
+        }
+        self.insert_table_paragraph(start, &id);
+        self.history.end_group();
+        Some(id)
+    }
+
+    /// Replace a table in the document body with a paragraph per row, the
+    /// cells of which are joined with `delimiter`. Returns false if the
+    /// table is not in the body.
+    #[wasm_bindgen]
+    pub fn convert_table_to_paragraphs(&mut self, table_id: &str, delimiter: &str) -> bool {
+        let Some(index) = self.document.paragraphs.iter().position(|p| p.table_id() == Some(table_id)) else {
+            return false;
+        };
+        let Some(table_index) = self.document.tables.iter().position(|t| t.id == table_id) else {
+            return false;
+        };
+        let paragraphs = self.document.tables[table_index].to_paragraphs(delimiter);
+
+        self.history.begin_group();
+        self.remove_paragraph_at(index);
+        for (offset, para) in paragraphs.into_iter().enumerate() {
+            self.insert_paragraph_at(index + offset, para);
         }
+        let table = self.document.tables.remove(table_index);
+        self.history.record(Operation::RemoveTable { index: table_index, table });
+        self.history.end_group();
+        true
     }
 
     /// Add a row at the specified index
     #[wasm_bindgen]
     pub fn add_table_row(&mut self, table_id: &str, at_index: usize) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            table.add_row(at_index);
-            self.dirty = true;
-        }
+        self.edit_table(table_id, |table| table.add_row(at_index));
     }
 
     /// Add a column at the specified index
     #[wasm_bindgen]
     pub fn add_table_column(&mut self, table_id: &str, at_index: usize) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            table.add_column(at_index);
-            self.dirty = true;
-        }
+        self.edit_table(table_id, |table| table.add_column(at_index));
     }
 
     /// Delete a row at the specified index
     #[wasm_bindgen]
     pub fn delete_table_row(&mut self, table_id: &str, row: usize) -> bool {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            let result = table.delete_row(row);
-            if result {
-                self.dirty = true;
-            }
-            result
-        } else {
-            false
-        }
+        self.edit_table(table_id, |table| table.delete_row(row))
+            .unwrap_or(false)
     }
 
     /// Delete a column at the specified index
     #[wasm_bindgen]
     pub fn delete_table_column(&mut self, table_id: &str, col: usize) -> bool {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            let result = table.delete_column(col);
-            if result {
-                self.dirty = true;
-            }
-            result
-        } else {
-            false
-        }
+        self.edit_table(table_id, |table| table.delete_column(col))
+            .unwrap_or(false)
     }
 
     /// Delete entire table
     #[wasm_bindgen]
     pub fn delete_table(&mut self, id: &str) {
-        // Remove table from tables list
-        self.document.tables.retain(|t| t.id != id);
+        self.history.begin_group();
 
         // Remove any table paragraphs referencing this table
-        self.document.paragraphs.retain(|p| {
-            if let Some(table_id) = p.table_id() {
-                table_id != id
-            } else {
-                true
+        for index in (0..self.document.paragraphs.len()).rev() {
+            if self.document.paragraphs[index].table_id() == Some(id) {
+                self.remove_paragraph_at(index);
             }
-        });
-        self.dirty = true;
+        }
+
+        // Remove table from tables list
+        if let Some(index) = self.document.tables.iter().position(|t| t.id == id) {
+            let table = self.document.tables.remove(index);
+            self.history.record(Operation::RemoveTable { index, table });
+        }
+        self.history.end_group();
     }
 
     /// Set column width
     #[wasm_bindgen]
     pub fn set_column_width(&mut self, table_id: &str, col: usize, width: f64) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
+        self.edit_table(table_id, |table| {
             if col < table.column_widths.len() {
                 table.column_widths[col] = width;
-                self.dirty = true;
             }
-        }
+        });
     }
 
     /// Set table border style
     #[wasm_bindgen]
     pub fn set_table_border(&mut self, table_id: &str, width: f64, color: &str) {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
+        self.edit_table(table_id, |table| {
             table.border_width = width;
             table.border_color = color.to_string();
-            self.dirty = true;
-        }
+        });
+    }
+
+    /// Set how many rows at the top of a table are repeated on every page or
+    /// column the table continues on (0 = none)
+    #[wasm_bindgen]
+    pub fn set_table_header_rows(&mut self, table_id: &str, rows: usize) {
+        self.edit_table(table_id, |table| table.header_rows = rows.min(table.num_rows()));
+    }
+
+    /// Set how a table's columns are sized: "fixed" (pixels), "percentage"
+    /// (of the page column) or "auto" (from the cell content). Fixed and
+    /// percentage widths start from the table's current layout, so it keeps
+    /// its look. Returns false for an unknown mode or table.
+    #[wasm_bindgen]
+    pub fn set_table_width_mode(&mut self, table_id: &str, mode: &str) -> bool {
+        let mode = match mode {
+            "fixed" => TableWidthMode::Fixed,
+            "percentage" => TableWidthMode::Percentage,
+            "auto" => TableWidthMode::Auto,
+            _ => return false,
+        };
+        let laid_out = self.cached_table_layout(table_id).map(|layout| layout.column_widths.clone());
+        let column_width = self.layout_config.column_width();
+        self.edit_table(table_id, |table| {
+            if let Some(widths) = laid_out.filter(|widths| widths.len() == table.num_cols()) {
+                let content_width = column_width - (table.num_cols() + 1) as f64 * table.border_width;
+                match mode {
+                    TableWidthMode::Fixed => table.column_widths = widths,
+                    TableWidthMode::Percentage if content_width > 0.0 => {
+                        table.column_widths = widths.iter().map(|w| w / content_width * 100.0).collect();
+                    }
+                    _ => {}
+                }
+            }
+            table.width_mode = mode;
+        })
+        .is_some()
     }
 
     /// Get table dimensions as JSON { rows, cols }
@@ -832,69 +1902,25 @@ impl Engine {
         }
     }
 
-    /// Get cell at click position within a table
-    /// Returns JSON { row, col } or null if not found
+    /// Get the cell at a point of a table, in pixels from the table's
+    /// top-left corner as laid out by the last layout pass
+    /// Returns JSON { tableId, row, col, para, offset } or null if the point
+    /// is outside the table; a covered cell reports the merged cell covering
+    /// it, para and offset are the position in the cell nearest to the point,
+    /// and a point on a nested table reports the nested table's cell
     #[wasm_bindgen]
     pub fn get_cell_at_position(&self, table_id: &str, rel_x: f64, rel_y: f64) -> JsValue {
-        if let Some(table) = self.document.tables.iter().find(|t| t.id == table_id) {
-            let border = table.border_width;
-            let available_width = self.layout_config.column_width();
-            let line_height = self.layout_config.line_height_px();
-            let cell_padding = 8.0;
-
-            // Compute column widths (percentage mode)
-            let column_widths: Vec<f64> = table.column_widths
-                .iter()
-                .map(|w| available_width * w / 100.0)
-                .collect();
-
-            // Compute row heights based on cell content
-            let mut row_heights: Vec<f64> = Vec::new();
-            for row in &table.rows {
-                let mut max_lines = 1usize;
-                for (col_idx, cell) in row.cells.iter().enumerate() {
-                    let cell_width = column_widths.get(col_idx).copied().unwrap_or(100.0) - cell_padding;
-                    // Estimate lines needed for this cell's text
-                    let text_width = self.measure_text_width(&cell.text);
-                    let lines = if cell_width > 0.0 && text_width > 0.0 {
-                        ((text_width / cell_width).ceil() as usize).max(1)
-                    } else {
-                        // Count explicit newlines too
-                        cell.text.matches('\n').count() + 1
-                    };
-                    max_lines = max_lines.max(lines);
-                }
-                let row_height = (max_lines as f64 * line_height + cell_padding)
-                    .max(row.min_height.unwrap_or(line_height + cell_padding));
-                row_heights.push(row_height);
-            }
-
-            // Find row by Y position
-            let mut y = border;
-            let mut found_row = None;
-            for (row_idx, &row_height) in row_heights.iter().enumerate() {
-                if rel_y >= y && rel_y < y + row_height {
-                    found_row = Some(row_idx);
-                    break;
-                }
-                y += row_height + border;
-            }
-
-            // Find column by X position
-            let mut x = border;
-            let mut found_col = None;
-            for (col_idx, &col_width) in column_widths.iter().enumerate() {
-                if rel_x >= x && rel_x < x + col_width {
-                    found_col = Some(col_idx);
-                    break;
-                }
-                x += col_width + border;
-            }
-
-            if let (Some(row), Some(col)) = (found_row, found_col) {
+        let table = self.document.tables.iter().find(|t| t.id == table_id);
+        if let (Some(table), Some(layout)) = (table, self.cached_table_layout(table_id)) {
+            let inside = (0.0..=layout.total_width).contains(&rel_x) && (0.0..=layout.total_height).contains(&rel_y);
+            if inside {
+                let hit = table_cell_at(&self.document, table, layout, &self.layout_config, rel_x, rel_y);
                 let result = serde_json::json!({
-                    "row": row,
-                    "col": col,
+                    "tableId": hit.table_id,
+                    "row": hit.row,
+                    "col": hit.col,
+                    "para": hit.para,
+                    "offset": hit.offset,
                 });
                 return JsValue::from_str(&result.to_string());
             }
@@ -902,14 +1928,6 @@ impl Engine {
         JsValue::NULL
     }
 
-    /// Measure text width using layout config
-    fn measure_text_width(&self, text: &str) -> f64 {
-        // Simple estimation: character count * average character width
-        // This is a rough approximation; actual width depends on font
-        let avg_char_width = self.layout_config.font_size * 0.6;
-        text.chars().count() as f64 * avg_char_width
-    }
-
     /// Merge cells in a rectangular selection
     /// Returns true if merge was successful
     #[wasm_bindgen]
@@ -921,30 +1939,18 @@ impl Engine {
         end_row: usize,
         end_col: usize,
     ) -> bool {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            let result = table.merge_cells(start_row, start_col, end_row, end_col);
-            if result {
-                self.dirty = true;
-            }
-            result
-        } else {
-            false
-        }
+        self.edit_table(table_id, |table| {
+            table.merge_cells(start_row, start_col, end_row, end_col)
+        })
+        .unwrap_or(false)
     }
 
     /// Split a merged cell back into individual cells
     /// Returns true if split was successful
     #[wasm_bindgen]
     pub fn split_cell(&mut self, table_id: &str, row: usize, col: usize) -> bool {
-        if let Some(table) = self.document.tables.iter_mut().find(|t| t.id == table_id) {
-            let result = table.split_cell(row, col);
-            if result {
-                self.dirty = true;
-            }
-            result
-        } else {
-            false
-        }
+        self.edit_table(table_id, |table| table.split_cell(row, col))
+            .unwrap_or(false)
     }
 
     /// Check if a cell is a merge origin (can be split)
@@ -989,6 +1995,298 @@ impl Engine {
     }
 }
 
+/// Native (non-wasm) API
+impl Engine {
+    /// Recompute layout if dirty using any text measurer
+    ///
+    /// This is the entry point for running layout outside a JS host. Only the
+    /// paragraphs changed since the last layout are laid out again.
+    pub fn recompute_layout_with<M: TextMeasurer + ?Sized>(&mut self, measurer: &M) -> bool {
+        if !self.layout_cache.needs_layout() {
+            return false;
+        }
+
+        let update = layout::update_layout(
+            &self.document,
+            &self.layout_config,
+            measurer,
+            &mut self.layout_cache,
+            &mut self.display_lines,
+        );
+        self.changed_pages = update.changed_pages;
+        true
+    }
+
+    /// Get the pages changed by the last layout recomputation
+    pub fn changed_pages(&self) -> &[usize] {
+        &self.changed_pages
+    }
+
+    /// Caret and selection to draw, if rendering them is enabled
+    fn selection_overlay(&self) -> SelectionOverlay {
+        let Some((anchor, focus)) = self.selection.filter(|_| self.render_selection) else {
+            return SelectionOverlay::default();
+        };
+        if anchor == focus {
+            let caret = position::caret_rect(
+                &self.display_lines,
+                &self.document,
+                &self.layout_config,
+                focus.para,
+                focus.offset,
+                Affinity::Downstream,
+            );
+            SelectionOverlay { caret, selection: Vec::new() }
+        } else {
+            let selection = position::selection_rects(
+                &self.display_lines,
+                &self.document,
+                &self.layout_config,
+                anchor,
+                focus,
+            );
+            SelectionOverlay { caret: None, selection }
+        }
+    }
+
+    /// Insert a paragraph, clamping the index to the end of the document.
+    /// Returns the index the paragraph was inserted at.
+    fn insert_paragraph_at(&mut self, index: usize, para: Paragraph) -> usize {
+        let index = index.min(self.document.paragraphs.len());
+        self.document.paragraphs.insert(index, para.clone());
+        self.layout_cache.insert_paragraph(index);
+        self.history.record(Operation::InsertParagraph { index, paragraph: para });
+        index
+    }
+
+    /// An ID no table of the document has
+    fn next_table_id(&self) -> String {
+        (self.document.tables.len()..)
+            .map(|n| format!("table_{}", n))
+            .find(|id| self.document.tables.iter().all(|t| &t.id != id))
+            .expect("IDs are unlimited")
+    }
+
+    /// Add a table to the document and record it; returns its ID
+    fn add_table(&mut self, table: DocumentTable) -> String {
+        let id = table.id.clone();
+        let index = self.document.tables.len();
+        self.document.tables.push(table.clone());
+        self.history.record(Operation::InsertTable { index, table });
+        id
+    }
+
+    /// Remove a paragraph and record the removal
+    fn remove_paragraph_at(&mut self, index: usize) -> Option<Paragraph> {
+        if index >= self.document.paragraphs.len() {
+            return None;
+        }
+        let paragraph = self.document.paragraphs.remove(index);
+        self.layout_cache.remove_paragraph(index);
+        self.history.record(Operation::RemoveParagraph { index, paragraph: paragraph.clone() });
+        Some(paragraph)
+    }
+
+    /// Modify a paragraph in place, recording the change if there was one.
+    /// Returns true if the paragraph changed.
+    fn edit_paragraph<F: FnOnce(&mut Paragraph)>(&mut self, index: usize, edit: F) -> bool {
+        if let Some(para) = self.document.paragraphs.get_mut(index) {
+            let before = para.clone();
+            edit(para);
+            if *para != before {
+                let after = para.clone();
+                self.layout_cache.mark_dirty(index);
+                self.history.record(Operation::SetParagraph { index, before, after });
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Set a range to `align`, or back to the baseline if all of it already is
+    fn toggle_vertical_align(&mut self, para_index: usize, start: usize, end: usize, align: VerticalAlign) {
+        self.edit_paragraph(para_index, |para| {
+            let is_set = para.range_has_style(start, end, |s| s.vertical_align == align);
+            para.apply_style(start, end, |style| {
+                style.vertical_align = if is_set { VerticalAlign::Baseline } else { align };
+            });
+        });
+    }
+
+    /// Set a range to `caps`, or back to normal case if all of it already is
+    fn toggle_caps(&mut self, para_index: usize, start: usize, end: usize, caps: CapsStyle) {
+        self.edit_paragraph(para_index, |para| {
+            let is_set = para.range_has_style(start, end, |s| s.caps == caps);
+            para.apply_style(start, end, |style| {
+                style.caps = if is_set { CapsStyle::Normal } else { caps };
+            });
+        });
+    }
+
+    /// Modify the style sheet, recording the change and restyling every
+    /// paragraph if there was one. Returns true if the style sheet changed.
+    fn edit_styles<F: FnOnce(&mut Vec<NamedStyle>)>(&mut self, edit: F) -> bool {
+        let before = self.document.styles.clone();
+        edit(&mut self.document.styles);
+        if self.document.styles == before {
+            return false;
+        }
+        let after = self.document.styles.clone();
+        self.layout_cache.invalidate();
+        self.history.record(Operation::SetStyles { before, after });
+        true
+    }
+
+    /// Modify an image in place, recording the change if there was one.
+    /// Returns true if the image changed.
+    fn edit_image<F: FnOnce(&mut DocumentImage)>(&mut self, id: &str, edit: F) -> bool {
+        if let Some(image) = self.document.images.iter_mut().find(|img| img.id == id) {
+            let before = image.clone();
+            edit(image);
+            if *image != before {
+                let after = image.clone();
+                if before.position_mode == ImagePositionMode::FixedPosition {
+                    self.layout_cache.invalidate();
+                }
+                self.mark_image_dirty(id);
+                self.history.record(Operation::SetImage { before, after });
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Layout of a table from the last layout pass
+    fn cached_table_layout(&self, table_id: &str) -> Option<&TableLayout> {
+        self.display_lines
+            .iter()
+            .find(|dl| dl.table_id.as_deref() == Some(table_id))
+            .and_then(|dl| dl.table_layout.as_ref())
+    }
+
+    /// Modify a table cell in place, recording the change if there was one.
+    /// Returns the closure's result, or `None` if the cell does not exist.
+    fn edit_cell<R, F>(&mut self, table_id: &str, row: usize, col: usize, edit: F) -> Option<R>
+    where
+        F: FnOnce(&mut TableCell) -> R,
+    {
+        self.edit_table(table_id, |table| table.get_cell_mut(row, col).map(edit)).flatten()
+    }
+
+    /// Modify a table in place, recording the change if there was one.
+    /// Returns the closure's result, or `None` if the table does not exist.
+    fn edit_table<R, F>(&mut self, id: &str, edit: F) -> Option<R>
+    where
+        F: FnOnce(&mut DocumentTable) -> R,
+    {
+        let table = self.document.tables.iter_mut().find(|t| t.id == id)?;
+        let before = table.clone();
+        let result = edit(table);
+        if *table != before {
+            let after = table.clone();
+            self.mark_table_dirty(id);
+            self.history.record(Operation::SetTable { before, after });
+        }
+        Some(result)
+    }
+
+    /// Apply an operation from the history and update the layout cache
+    fn apply_operation(&mut self, operation: &Operation) {
+        // A positioned image affects its whole page, before and after the change
+        if let Operation::SetImage { before: image, .. } | Operation::RemoveImage { image, .. } =
+            operation
+        {
+            if image.position_mode == ImagePositionMode::FixedPosition {
+                self.layout_cache.invalidate();
+            }
+        }
+
+        if !operation.apply(&mut self.document) {
+            self.layout_cache.invalidate();
+            return;
+        }
+
+        match operation {
+            Operation::SetParagraph { index, .. } => self.layout_cache.mark_dirty(*index),
+            Operation::InsertParagraph { index, .. } => self.layout_cache.insert_paragraph(*index),
+            Operation::RemoveParagraph { index, .. } => self.layout_cache.remove_paragraph(*index),
+            Operation::SetImage { after: image, .. } | Operation::InsertImage { image, .. } => {
+                self.mark_image_dirty(&image.id)
+            }
+            Operation::SetTable { after: table, .. } | Operation::InsertTable { table, .. } => {
+                self.mark_table_dirty(&table.id)
+            }
+            // Marker paragraphs are removed by their own operations
+            Operation::RemoveImage { .. } | Operation::RemoveTable { .. } => {}
+            // A style can be used by any paragraph
+            Operation::SetStyles { .. } => self.layout_cache.invalidate(),
+        }
+    }
+
+    /// Get the undo/redo history
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Check if a paragraph is a page break, image or table marker
+    fn is_marker_paragraph(&self, index: usize) -> bool {
+        self.document.paragraphs.get(index).is_some_and(|p| p.is_marker())
+    }
+
+    /// Mark the paragraphs displaying an image for relayout
+    fn mark_image_dirty(&mut self, image_id: &str) {
+        let positioned = self.document.images.iter().any(|img| {
+            img.id == image_id && img.position_mode == ImagePositionMode::FixedPosition
+        });
+        if positioned {
+            // Positioned floats affect text on their page regardless of paragraph
+            self.layout_cache.invalidate();
+            return;
+        }
+        for (index, para) in self.document.paragraphs.iter().enumerate() {
+            if para.image_id() == Some(image_id) {
+                self.layout_cache.mark_dirty(index);
+            }
+        }
+        // Images in cells are laid out with their table
+        let tables: Vec<String> = self.document.tables
+            .iter()
+            .filter(|t| t.cell_paragraphs().any(|p| p.image_id() == Some(image_id)))
+            .map(|t| t.id.clone())
+            .collect();
+        for table_id in tables {
+            self.mark_table_dirty(&table_id);
+        }
+    }
+
+    /// Mark the paragraphs displaying a table for relayout, including those
+    /// of the tables it is nested in
+    fn mark_table_dirty(&mut self, table_id: &str) {
+        let mut tables = self.document.enclosing_tables(table_id);
+        tables.push(table_id);
+        for (index, para) in self.document.paragraphs.iter().enumerate() {
+            if para.table_id().is_some_and(|id| tables.contains(&id)) {
+                self.layout_cache.mark_dirty(index);
+            }
+        }
+    }
+
+    /// Get the registered fonts
+    pub fn fonts(&self) -> &FontRegistry {
+        &self.fonts
+    }
+
+    /// Get the current document
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Get the display lines from the last layout
+    pub fn display_lines(&self) -> &[DisplayLine] {
+        &self.display_lines
+    }
+}
+
 impl Default for Engine {
     fn default() -> Self {
         Self::new()
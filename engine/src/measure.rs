@@ -0,0 +1,275 @@
+//! Text Measurement
+//!
+//! This module defines how the layout engine asks for text widths. Layout never
+//! talks to a specific text backend directly; instead it is generic over the
+//! `TextMeasurer` trait, so the same pipeline can run inside a browser (measuring
+//! through a JavaScript callback) or natively (in `cargo test`, server-side
+//! pagination jobs, command line tools, ...).
+//!
+//! # Implementations
+//!
+//! - `JsMeasurer`: Adapter around a JavaScript
+//!   `(text, fontSize, fontWeight, italic, fontFamily) => width` callback, typically
+//!   backed by `CanvasRenderingContext2D.measureText`, and an optional callback
+//!   returning the widths of all character prefixes at once
+//! - `CallbackMeasurer`: The same adapter around Rust closures
+//! - `FixedWidthMeasurer`: Approximation where every character has the same advance.
+//!   Useful for tests and as a fallback when no real font data is available
+//!
+//! # Example
+//!
+//! ```ignore
+//! use editor_engine::{compute_layout, FixedWidthMeasurer, LayoutConfig};
+//!
+//! let measurer = FixedWidthMeasurer::default();
+//! let lines = compute_layout(&document, &LayoutConfig::default(), &measurer);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+/// Font weight used for regular text
+pub const FONT_WEIGHT_NORMAL: u16 = 400;
+
+/// Font weight used for bold text
+pub const FONT_WEIGHT_BOLD: u16 = 700;
+
+/// Font parameters that affect the width of a piece of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontSpec {
+    /// Font family (None = the measurer's default family)
+    pub family: Option<String>,
+    /// Font size in pixels
+    pub size: f64,
+    /// Numeric font weight (100-900, 400 = normal, 700 = bold)
+    pub weight: u16,
+    /// Italic style
+    pub italic: bool,
+}
+
+impl FontSpec {
+    /// Create a regular (non-bold, non-italic) font spec
+    pub fn new(size: f64) -> Self {
+        FontSpec {
+            family: None,
+            size,
+            weight: FONT_WEIGHT_NORMAL,
+            italic: false,
+        }
+    }
+
+    /// Create a font spec from bold/italic flags
+    pub fn with_style(size: f64, bold: bool, italic: bool) -> Self {
+        FontSpec {
+            family: None,
+            size,
+            weight: if bold { FONT_WEIGHT_BOLD } else { FONT_WEIGHT_NORMAL },
+            italic,
+        }
+    }
+
+    /// Use a specific font family
+    pub fn with_family(mut self, family: &str) -> Self {
+        self.family = Some(family.to_string());
+        self
+    }
+
+    /// Check if this font is rendered bold
+    pub fn is_bold(&self) -> bool {
+        self.weight >= 600
+    }
+}
+
+/// Vertical metrics of a font at a given size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the tallest glyphs, in pixels
+    pub ascent: f64,
+    /// Distance from the baseline to the bottom of the lowest glyphs, in pixels
+    pub descent: f64,
+}
+
+impl FontMetrics {
+    /// Typical metrics for Latin fonts: 80% of the size above the baseline,
+    /// 20% below
+    pub fn approximate(size: f64) -> Self {
+        FontMetrics {
+            ascent: size * 0.8,
+            descent: size * 0.2,
+        }
+    }
+}
+
+/// Measures the advance width of text
+///
+/// Implementations must be deterministic: the same text and font must always
+/// produce the same width, otherwise line breaking becomes unstable.
+pub trait TextMeasurer {
+    /// Measure the width in pixels of `text` rendered with `font`
+    fn measure(&self, text: &str, font: &FontSpec) -> f64;
+
+    /// Width of every character prefix of `text`, from the empty prefix to the
+    /// whole text (one more entry than characters)
+    ///
+    /// Layout calls this once per font run to place characters and find line
+    /// breaks. The default measures each prefix; backends that can produce
+    /// all advances in one pass should override it.
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        measure_each_prefix(self, text, font)
+    }
+
+    /// Get the ascent and descent of `font`
+    ///
+    /// Defaults to `FontMetrics::approximate`, which is enough when the
+    /// backend has no access to real font data.
+    fn metrics(&self, font: &FontSpec) -> FontMetrics {
+        FontMetrics::approximate(font.size)
+    }
+}
+
+/// Measurer that assigns every character the same advance width
+///
+/// The advance is `font.size * char_width` regardless of weight and style.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedWidthMeasurer {
+    /// Character advance as a fraction of the font size
+    pub char_width: f64,
+}
+
+impl Default for FixedWidthMeasurer {
+    fn default() -> Self {
+        FixedWidthMeasurer { char_width: 0.5 }
+    }
+}
+
+impl FixedWidthMeasurer {
+    pub fn new(char_width: f64) -> Self {
+        FixedWidthMeasurer { char_width }
+    }
+}
+
+impl TextMeasurer for FixedWidthMeasurer {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        text.chars().count() as f64 * font.size * self.char_width
+    }
+
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        let advance = font.size * self.char_width;
+        (0..=text.chars().count()).map(|i| i as f64 * advance).collect()
+    }
+}
+
+/// Width of every character prefix of `text`, measuring each prefix on its own
+fn measure_each_prefix<M: TextMeasurer + ?Sized>(measurer: &M, text: &str, font: &FontSpec) -> Vec<f64> {
+    let mut widths = vec![0.0];
+    widths.extend(
+        text.char_indices()
+            .map(|(i, c)| measurer.measure(&text[..i + c.len_utf8()], font)),
+    );
+    widths
+}
+
+/// Callback measuring the width of a piece of text, or None if it failed
+type MeasureCallback<'a> = Box<dyn Fn(&str, &FontSpec) -> Option<f64> + 'a>;
+
+/// Callback measuring every character prefix of a piece of text at once
+type PrefixesCallback<'a> = Box<dyn Fn(&str, &FontSpec) -> Option<Vec<f64>> + 'a>;
+
+/// Measurer driven by callbacks, shaped like the JavaScript measuring API
+///
+/// `measure` returns the width of a piece of text. The optional `prefixes`
+/// callback returns the widths of all its character prefixes in one call; a
+/// missing callback, or a result that is not one width per character
+/// boundary, falls back to calling `measure` for each prefix. Failed
+/// measurements fall back to `FixedWidthMeasurer`.
+pub struct CallbackMeasurer<'a> {
+    measure: MeasureCallback<'a>,
+    prefixes: Option<PrefixesCallback<'a>>,
+    fallback: FixedWidthMeasurer,
+}
+
+impl<'a> CallbackMeasurer<'a> {
+    pub fn new(measure: impl Fn(&str, &FontSpec) -> Option<f64> + 'a) -> Self {
+        CallbackMeasurer {
+            measure: Box::new(measure),
+            prefixes: None,
+            fallback: FixedWidthMeasurer::default(),
+        }
+    }
+
+    /// Measure character prefixes with one call per piece of text
+    pub fn with_prefixes(mut self, prefixes: impl Fn(&str, &FontSpec) -> Option<Vec<f64>> + 'a) -> Self {
+        self.prefixes = Some(Box::new(prefixes));
+        self
+    }
+}
+
+impl TextMeasurer for CallbackMeasurer<'_> {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        (self.measure)(text, font).unwrap_or_else(|| self.fallback.measure(text, font))
+    }
+
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        let widths = self.prefixes.as_ref().and_then(|prefixes| prefixes(text, font));
+        match widths {
+            Some(widths) if widths.len() == text.chars().count() + 1 => widths,
+            _ => measure_each_prefix(self, text, font),
+        }
+    }
+}
+
+/// Adapter that measures text through JavaScript callbacks
+///
+/// The callback is invoked as `measure(text, fontSize, fontWeight, italic, fontFamily)`
+/// and must return the width in pixels. `fontFamily` is `undefined` for text in
+/// the default family. Callbacks that only accept `(text, fontSize)` keep
+/// working, they simply ignore the extra arguments.
+///
+/// An optional `measurePrefixes` callback, taking the same arguments, returns
+/// the widths of every character prefix of the text (an array or
+/// `Float64Array` starting with 0, one more entry than code points), so layout
+/// crosses into JavaScript once per font run instead of once per character.
+pub struct JsMeasurer<'a>(CallbackMeasurer<'a>);
+
+impl<'a> JsMeasurer<'a> {
+    pub fn new(measure_fn: &'a js_sys::Function) -> Self {
+        JsMeasurer(CallbackMeasurer::new(move |text, font| {
+            call_js(measure_fn, text, font).and_then(|result| result.as_f64())
+        }))
+    }
+
+    /// Measure character prefixes through a `measurePrefixes` callback
+    pub fn with_prefixes(self, prefixes_fn: &'a js_sys::Function) -> Self {
+        JsMeasurer(self.0.with_prefixes(move |text, font| {
+            call_js(prefixes_fn, text, font)
+                .filter(|result| result.is_object())
+                .map(|result| js_sys::Float64Array::new(&result).to_vec())
+        }))
+    }
+}
+
+impl TextMeasurer for JsMeasurer<'_> {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        self.0.measure(text, font)
+    }
+
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        self.0.prefix_widths(text, font)
+    }
+}
+
+/// Call a JavaScript measuring callback as `(text, fontSize, fontWeight, italic, fontFamily)`
+fn call_js(callback: &js_sys::Function, text: &str, font: &FontSpec) -> Option<JsValue> {
+    let this = JsValue::NULL;
+    let text_js = JsValue::from_str(text);
+    let size_js = JsValue::from_f64(font.size);
+    let weight_js = JsValue::from_f64(font.weight as f64);
+    let italic_js = JsValue::from_bool(font.italic);
+    let family_js = match &font.family {
+        Some(family) => JsValue::from_str(family),
+        None => JsValue::UNDEFINED,
+    };
+
+    callback
+        .call5(&this, &text_js, &size_js, &weight_js, &italic_js, &family_js)
+        .ok()
+}
@@ -0,0 +1,702 @@
+//! Pixel Positions
+//!
+//! This module maps between page coordinates (the pixels the user clicks on)
+//! and document positions (paragraph index and character offset). It uses the
+//! same line placement as the render module, so a hit test always agrees with
+//! what is drawn on the canvas.
+//!
+//! # Line Placement
+//!
+//! Layout records the X offset of every character boundary in a display line
+//! (`DisplayLine::char_positions`). `line_placement()` adds everything that
+//! shifts the text horizontally on the page:
+//! - Page margin and column offset
+//! - List indent
+//! - Floating images on the left of the line
+//! - Alignment (center/right offset, justified word spacing)
+//!
+//! # Hit Testing
+//!
+//! `hit_test()` finds what lies under a point on a page, in this order:
+//! 1. Images drawn over text (in front, floating, inline)
+//! 2. The text or table line whose box contains the point (including the
+//!    checkbox of a checklist item and the link on the character under it)
+//! 3. Images drawn behind text
+//! 4. The nearest line in the column under the point
+//!
+//! Text positions carry an `Affinity`: the end of a wrapped line and the start
+//! of the next line are the same paragraph offset, and the affinity tells which
+//! of the two lines the caret belongs on.
+//!
+//! # Caret and Selection
+//!
+//! `caret_rect()` and `selection_rects()` go the other way, from document
+//! positions to rectangles on a page, using the same placement. Selection
+//! rectangles cover whole line boxes vertically so that the rectangles of
+//! the lines of a paragraph touch.
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::{
+    Document, DocumentImage, DocumentTable, ImagePositionMode, ImageWrapStyle, Link, ListType,
+    ParagraphMeta, TextAlign,
+};
+use crate::layout::{
+    cell_config, list_indent, paragraph_format, DisplayLine, LayoutConfig, ParagraphFormat, ParagraphPosition,
+    TableLayout,
+};
+use crate::render::calculate_image_x;
+
+/// Gap kept between a floating image and the text next to it
+const FLOAT_GAP: f64 = 10.0;
+
+/// Width of the caret in pixels
+pub const CARET_WIDTH: f64 = 2.0;
+
+/// A rectangle on a page
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageRect {
+    pub page_index: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PageRect {
+    /// Whether a point lies inside the rectangle
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Which side of a line break a position belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Affinity {
+    /// The position belongs to the line ending at it (caret at the end of a
+    /// wrapped line)
+    Upstream,
+    /// The position belongs to the line starting at it
+    #[default]
+    Downstream,
+}
+
+/// A position inside a table cell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableHit {
+    pub table_id: String,
+    pub row: usize,
+    pub col: usize,
+    /// Index of the paragraph within the cell
+    pub para: usize,
+    /// Character offset within the paragraph
+    pub offset: usize,
+}
+
+/// Result of hit-testing a point on a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitTestResult {
+    /// Document position under the point. For images and tables this is the
+    /// start of their marker paragraph.
+    pub position: ParagraphPosition,
+    pub affinity: Affinity,
+    /// Image under the point, if any
+    pub image_id: Option<String>,
+    /// Table cell under the point, if any
+    pub table: Option<TableHit>,
+    /// Whether the point is on a checklist item's checkbox
+    pub checkbox: bool,
+    /// Link under the point, if any
+    pub link: Option<Link>,
+}
+
+impl HitTestResult {
+    fn text(para: usize, offset: usize, affinity: Affinity) -> Self {
+        HitTestResult {
+            position: ParagraphPosition { para, offset },
+            affinity,
+            image_id: None,
+            table: None,
+            checkbox: false,
+            link: None,
+        }
+    }
+}
+
+/// Horizontal placement of a display line's text on the page
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinePlacement {
+    /// Page X coordinate where the line's text starts
+    pub x: f64,
+    /// Extra space added to every space character (justified lines)
+    pub word_spacing: f64,
+}
+
+impl LinePlacement {
+    /// Page X coordinate of the boundary before character `index` of the line
+    pub fn x_at(&self, line: &DisplayLine, index: usize) -> f64 {
+        let spaces = line.text.chars().take(index).filter(|&c| c == ' ').count();
+        self.x + line.char_x(index) + spaces as f64 * self.word_spacing
+    }
+}
+
+/// Compute where a text line is drawn on its page
+pub fn line_placement(line: &DisplayLine, format: &ParagraphFormat, config: &LayoutConfig) -> LinePlacement {
+    let column_width = config.column_width();
+
+    let font_size = format.font.size;
+    let list_indent = list_indent(line.list_type, line.list_level, font_size);
+
+    // Floats on the left push the text to the right; floats on the right only
+    // reduce the available width
+    let (float_offset, float_width) = match &line.float_reduction {
+        Some(fr) => {
+            let offset = if fr.float_x < column_width / 2.0 {
+                fr.float_x + fr.width + FLOAT_GAP
+            } else {
+                0.0
+            };
+            (offset, fr.width + FLOAT_GAP)
+        }
+        None => (0.0, 0.0),
+    };
+
+    // Paragraph indents; the first line has its own
+    let indent = format.indent.line_start(line.start_offset == 0);
+    let available = column_width - list_indent - indent - format.indent.right - float_width;
+    let free = (available - line.content_width()).max(0.0);
+    let (align_offset, word_spacing) = match format.align {
+        TextAlign::Left => (0.0, 0.0),
+        TextAlign::Center => (free / 2.0, 0.0),
+        TextAlign::Right => (free, 0.0),
+        TextAlign::Justify => {
+            let spaces = line.text.trim_end_matches(' ').chars().filter(|&c| c == ' ').count();
+            if line.is_last_line || spaces == 0 {
+                (0.0, 0.0)
+            } else {
+                (0.0, free / spaces as f64)
+            }
+        }
+    };
+
+    LinePlacement {
+        x: column_x(line, config) + list_indent + indent + float_offset + align_offset,
+        word_spacing,
+    }
+}
+
+/// Checkbox of a checklist item, drawn in the list gutter of its first line
+pub fn checkbox_rect(line: &DisplayLine, format: &ParagraphFormat, config: &LayoutConfig) -> Option<PageRect> {
+    if line.list_type != ListType::Checklist || line.list_marker.is_none() {
+        return None;
+    }
+    let font_size = format.font.size;
+    let gutter_x = column_x(line, config) + format.indent.line_start(true)
+        + list_indent(line.list_type, line.list_level, font_size)
+        - list_indent(line.list_type, 0, font_size);
+    let size = font_size * 0.75;
+    Some(PageRect {
+        page_index: line.page_index,
+        x: gutter_x + font_size * 0.25,
+        y: config.margin_top + line.y_position + line.baseline() - size,
+        width: size,
+        height: size,
+    })
+}
+
+/// Find the document position under a point on a page
+///
+/// `x` and `y` are page coordinates, the same ones render commands use.
+/// Returns None if the page has no content.
+pub fn hit_test(
+    display_lines: &[DisplayLine],
+    document: &Document,
+    config: &LayoutConfig,
+    page_index: usize,
+    x: f64,
+    y: f64,
+) -> Option<HitTestResult> {
+    let page_lines: Vec<&DisplayLine> = display_lines
+        .iter()
+        .filter(|dl| dl.page_index == page_index && !dl.is_page_break)
+        .collect();
+    if page_lines.is_empty() {
+        return None;
+    }
+
+    // 1. Images drawn over text
+    let over_text = [
+        |s: ImageWrapStyle| s == ImageWrapStyle::InFront,
+        |s: ImageWrapStyle| s.is_float(),
+        |s: ImageWrapStyle| matches!(s, ImageWrapStyle::Inline | ImageWrapStyle::TopBottom),
+    ];
+    for matches_style in over_text {
+        if let Some(hit) = hit_image(&page_lines, document, config, page_index, x, y, matches_style) {
+            return Some(hit);
+        }
+    }
+
+    // 2. Line under the point
+    let column = column_at(config, x);
+    let column_lines: Vec<&DisplayLine> = page_lines
+        .iter()
+        .copied()
+        .filter(|dl| dl.column_index == column && !dl.is_image)
+        .collect();
+    let containing = column_lines.iter().find(|dl| {
+        let top = config.margin_top + dl.y_position;
+        y >= top && y < top + dl.height
+    });
+    if let Some(dl) = containing {
+        let format = line_format(document, dl, config);
+        if checkbox_rect(dl, &format, config).is_some_and(|rect| rect.contains(x, y)) {
+            let mut hit = HitTestResult::text(dl.para_index, 0, Affinity::Downstream);
+            hit.checkbox = true;
+            return Some(hit);
+        }
+        let mut hit = hit_line(dl, document, config, x, y);
+        hit.link = link_under(dl, document, config, x);
+        return Some(hit);
+    }
+
+    // 3. Images drawn behind text
+    let behind = |s: ImageWrapStyle| s == ImageWrapStyle::Behind;
+    if let Some(hit) = hit_image(&page_lines, document, config, page_index, x, y, behind) {
+        return Some(hit);
+    }
+
+    // 4. Nearest line in the column, or on the page if the column is empty
+    let candidates = if column_lines.is_empty() { &page_lines } else { &column_lines };
+    let nearest = candidates.iter().min_by(|a, b| {
+        vertical_distance(a, config, y).total_cmp(&vertical_distance(b, config, y))
+    })?;
+    if nearest.is_image {
+        return Some(HitTestResult::text(nearest.para_index, 0, Affinity::Downstream));
+    }
+    Some(hit_line(nearest, document, config, x, y))
+}
+
+/// Column whose horizontal extent is closest to `x`
+fn column_at(config: &LayoutConfig, x: f64) -> usize {
+    let column_width = config.column_width();
+    let relative = x - config.margin_left;
+    let mut column = 0;
+    for i in 0..config.columns.max(1) as usize {
+        if relative >= i as f64 * (column_width + config.column_gap) - config.column_gap / 2.0 {
+            column = i;
+        }
+    }
+    column
+}
+
+/// Distance from `y` to a line's box (0 if inside)
+fn vertical_distance(line: &DisplayLine, config: &LayoutConfig, y: f64) -> f64 {
+    let top = config.margin_top + line.y_position;
+    if y < top {
+        top - y
+    } else {
+        (y - (top + line.height)).max(0.0)
+    }
+}
+
+/// Hit-test the images on the page whose wrap style matches
+fn hit_image<F: Fn(ImageWrapStyle) -> bool>(
+    page_lines: &[&DisplayLine],
+    document: &Document,
+    config: &LayoutConfig,
+    page_index: usize,
+    x: f64,
+    y: f64,
+    matches_style: F,
+) -> Option<HitTestResult> {
+    // Later images are drawn on top, so test them first
+    for dl in page_lines.iter().rev().filter(|dl| dl.is_image) {
+        let Some(image) = dl.image_id.as_ref().and_then(|id| document.images.iter().find(|img| &img.id == id))
+        else {
+            continue;
+        };
+        if !matches_style(image.wrap_style) {
+            continue;
+        }
+        let Some((left, top, width, height)) = image_rect(image, dl, config, page_index) else {
+            continue;
+        };
+        if x >= left && x < left + width && y >= top && y < top + height {
+            return Some(HitTestResult {
+                position: ParagraphPosition { para: dl.para_index, offset: 0 },
+                affinity: Affinity::Downstream,
+                image_id: Some(image.id.clone()),
+                table: None,
+                checkbox: false,
+                link: None,
+            });
+        }
+    }
+    None
+}
+
+/// Page rectangle (x, y, width, height) an image is drawn in, if it is drawn
+/// on this page
+fn image_rect(
+    image: &DocumentImage,
+    line: &DisplayLine,
+    config: &LayoutConfig,
+    page_index: usize,
+) -> Option<(f64, f64, f64, f64)> {
+    let fixed = image.position_mode == ImagePositionMode::FixedPosition
+        && !matches!(image.wrap_style, ImageWrapStyle::Inline | ImageWrapStyle::TopBottom);
+    let y = if fixed {
+        if image.page_index != Some(page_index) {
+            return None;
+        }
+        config.margin_top + image.y.unwrap_or(0.0)
+    } else {
+        config.margin_top + line.y_position
+    };
+    let x = calculate_image_x(image, line.column_index, config);
+    Some((x, y, image.width.min(config.column_width()), image.cropped_height()))
+}
+
+/// Hit-test a text or table line
+fn hit_line(line: &DisplayLine, document: &Document, config: &LayoutConfig, x: f64, y: f64) -> HitTestResult {
+    if line.is_table {
+        let table = line
+            .table_id
+            .as_ref()
+            .and_then(|id| document.tables.iter().find(|t| &t.id == id));
+        if let (Some(table), Some(layout)) = (table, &line.table_layout) {
+            let hit = hit_table(document, table, layout, line, config, x, y);
+            return HitTestResult {
+                position: ParagraphPosition { para: line.para_index, offset: 0 },
+                affinity: Affinity::Downstream,
+                image_id: None,
+                table: Some(hit),
+                checkbox: false,
+                link: None,
+            };
+        }
+        return HitTestResult::text(line.para_index, 0, Affinity::Downstream);
+    }
+
+    let placement = line_placement(line, &line_format(document, line, config), config);
+
+    let char_count = line.end_offset - line.start_offset;
+    let index = nearest_boundary(char_count, x, |i| placement.x_at(line, i));
+    let affinity = if index == char_count && !line.is_last_line {
+        Affinity::Upstream
+    } else {
+        Affinity::Downstream
+    };
+    HitTestResult::text(line.para_index, line.start_offset + index, affinity)
+}
+
+/// Link on the character of a text line under `x`
+fn link_under(line: &DisplayLine, document: &Document, config: &LayoutConfig, x: f64) -> Option<Link> {
+    if line.is_table {
+        return None;
+    }
+    let para = document.paragraphs.get(line.para_index)?;
+    let placement = line_placement(line, &paragraph_format(document, &para.meta, config), config);
+    let index = (0..line.end_offset - line.start_offset)
+        .find(|&i| x >= placement.x_at(line, i) && x < placement.x_at(line, i + 1))?;
+    para.link_at(line.start_offset + index).cloned()
+}
+
+/// Index of the boundary in `0..=count` whose X coordinate is closest to `x`
+fn nearest_boundary<F: Fn(usize) -> f64>(count: usize, x: f64, x_at: F) -> usize {
+    let mut best = 0;
+    let mut best_distance = f64::INFINITY;
+    for i in 0..=count {
+        let distance = (x_at(i) - x).abs();
+        if distance < best_distance {
+            best = i;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
+/// Index of the track (row or column) containing `pos`, given where the
+/// tracks start; positions outside are clamped to the first or last track
+fn track_at(starts: &[f64], pos: f64) -> usize {
+    starts.iter().rposition(|&start| pos >= start).unwrap_or(0)
+}
+
+/// Find the cell and character offset under a point inside a table line
+fn hit_table(
+    document: &Document,
+    table: &DocumentTable,
+    layout: &TableLayout,
+    line: &DisplayLine,
+    config: &LayoutConfig,
+    x: f64,
+    y: f64,
+) -> TableHit {
+    let table_y = config.margin_top + line.y_position;
+    // On a part of a split table, find where in the whole table the point is
+    let y = match &line.table_fragment {
+        Some(fragment) => fragment.table_y(y - table_y),
+        None => y - table_y,
+    };
+    table_cell_at(document, table, layout, config, x - column_x(line, config), y)
+}
+
+/// Find the cell and the position in its content at a point of a laid out
+/// table, given in pixels from the table's top-left corner
+///
+/// Points outside the table are clamped to its nearest row and column, and
+/// cells covered by a merged cell map to the merged cell's origin. A point on
+/// a table nested in the cell hits a cell of the nested table.
+pub fn table_cell_at(
+    document: &Document,
+    table: &DocumentTable,
+    layout: &TableLayout,
+    config: &LayoutConfig,
+    x: f64,
+    y: f64,
+) -> TableHit {
+    let border = table.border_width;
+
+    let row_starts = track_starts(0.0, &layout.row_heights, border);
+    let col_starts = track_starts(0.0, &layout.column_widths, border);
+    let (mut row, mut col) = (track_at(&row_starts, y), track_at(&col_starts, x));
+
+    // Covered cells belong to the merged cell that covers them
+    if let Some((origin_row, origin_col)) = merge_origin(table, row, col) {
+        row = origin_row;
+        col = origin_col;
+    }
+
+    let mut hit = TableHit {
+        table_id: table.id.clone(),
+        row,
+        col,
+        para: 0,
+        offset: 0,
+    };
+    let Some(cell) = table.get_cell(row, col) else {
+        return hit;
+    };
+    let lines = layout.cell_lines.get(row).and_then(|r| r.get(col)).map_or(&[][..], Vec::as_slice);
+
+    // The cell content is placed like body text on a page the size of its text area
+    let mut cell_width: f64 = layout.column_widths.iter().skip(col).take(cell.col_span).sum();
+    cell_width += cell.col_span.saturating_sub(1) as f64 * border;
+    let padding = table.padding_of(cell);
+    let cell_config = cell_config(
+        config,
+        col_starts.get(col).copied().unwrap_or(0.0) + border + padding.left,
+        row_starts.get(row).copied().unwrap_or(0.0) + border + padding.top,
+        cell_width - padding.horizontal(),
+    );
+    let line = lines
+        .iter()
+        .filter(|dl| dl.height > 0.0)
+        .find(|dl| y < cell_config.margin_top + dl.y_position + dl.height)
+        .or_else(|| lines.iter().rfind(|dl| dl.height > 0.0));
+    let Some(line) = line else {
+        return hit;
+    };
+
+    hit.para = line.para_index;
+    if line.is_table {
+        let nested = line
+            .table_id
+            .as_ref()
+            .and_then(|id| document.tables.iter().find(|t| &t.id == id));
+        if let (Some(nested), Some(nested_layout)) = (nested, &line.table_layout) {
+            let nested_x = x - column_x(line, &cell_config);
+            let nested_y = y - cell_config.margin_top - line.y_position;
+            return table_cell_at(document, nested, nested_layout, &cell_config, nested_x, nested_y);
+        }
+    } else if !line.is_image {
+        let meta = cell.paragraphs.get(line.para_index).map(|p| &p.meta);
+        let format = paragraph_format(document, meta.unwrap_or(&ParagraphMeta::default()), &cell_config);
+        let placement = line_placement(line, &format, &cell_config);
+        let char_count = line.end_offset - line.start_offset;
+        hit.offset = line.start_offset + nearest_boundary(char_count, x, |i| placement.x_at(line, i));
+    }
+    hit
+}
+
+/// Start coordinate of each row or column, including the border before it
+fn track_starts(origin: f64, sizes: &[f64], border: f64) -> Vec<f64> {
+    let mut starts = Vec::with_capacity(sizes.len());
+    let mut pos = origin;
+    for size in sizes {
+        starts.push(pos);
+        pos += size + border;
+    }
+    starts
+}
+
+/// Origin of the merged cell covering (row, col), if the cell is covered
+pub fn merge_origin(table: &DocumentTable, row: usize, col: usize) -> Option<(usize, usize)> {
+    if !table.get_cell(row, col)?.covered {
+        return None;
+    }
+    for r in (0..=row).rev() {
+        for c in (0..=col).rev() {
+            if let Some(cell) = table.get_cell(r, c) {
+                if !cell.covered && r + cell.row_span > row && c + cell.col_span > col {
+                    return Some((r, c));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Rectangle of the caret at a document position
+///
+/// At the boundary between two wrapped lines, `affinity` picks the line: the
+/// end of the first line (upstream) or the start of the next (downstream).
+/// The caret spans the ascent and descent of the font run before the offset,
+/// sitting on the line's baseline. Returns None if the position is not laid out.
+pub fn caret_rect(
+    display_lines: &[DisplayLine],
+    document: &Document,
+    config: &LayoutConfig,
+    para: usize,
+    offset: usize,
+    affinity: Affinity,
+) -> Option<PageRect> {
+    let mut candidates = display_lines.iter().filter(|dl| {
+        dl.para_index == para && !dl.is_page_break && offset >= dl.start_offset && offset <= dl.end_offset
+    });
+    let line = match affinity {
+        Affinity::Upstream => candidates.next()?,
+        Affinity::Downstream => {
+            let first = candidates.next()?;
+            if offset == first.end_offset && !first.is_last_line {
+                candidates.next().unwrap_or(first)
+            } else {
+                first
+            }
+        }
+    };
+
+    let top = config.margin_top + line.y_position;
+    if line.is_image || line.is_table {
+        return Some(PageRect {
+            page_index: line.page_index,
+            x: column_x(line, config),
+            y: top,
+            width: CARET_WIDTH,
+            height: line.height,
+        });
+    }
+
+    let placement = line_placement(line, &line_format(document, line, config), config);
+    let index = offset - line.start_offset;
+    // The caret is as tall as the font text typed at the offset would get
+    let (ascent, descent) = match line.run_at(index) {
+        Some(run) => (run.ascent, run.descent),
+        None => (line.ascent, line.descent),
+    };
+    Some(PageRect {
+        page_index: line.page_index,
+        x: placement.x_at(line, index),
+        y: top + line.baseline() - ascent,
+        width: CARET_WIDTH,
+        height: ascent + descent,
+    })
+}
+
+/// Rectangles covering the selected text between two document positions
+///
+/// The positions may be given in either order. One rectangle is returned per
+/// display line that contains selected text, in document order. A selected
+/// paragraph break is shown as a space-wide box after the paragraph's last
+/// line, and image and table lines are covered across the column when the
+/// selection spans their marker.
+pub fn selection_rects(
+    display_lines: &[DisplayLine],
+    document: &Document,
+    config: &LayoutConfig,
+    anchor: ParagraphPosition,
+    focus: ParagraphPosition,
+) -> Vec<PageRect> {
+    let (start, end) = if (anchor.para, anchor.offset) <= (focus.para, focus.offset) {
+        (anchor, focus)
+    } else {
+        (focus, anchor)
+    };
+    if start == end {
+        return Vec::new();
+    }
+
+    let mut rects = Vec::new();
+    for line in display_lines {
+        if line.para_index < start.para || line.para_index > end.para || line.is_page_break {
+            continue;
+        }
+        let top = config.margin_top + line.y_position;
+
+        if line.is_image || line.is_table {
+            let selected = (line.para_index > start.para || start.offset == 0)
+                && (line.para_index < end.para || end.offset > 0);
+            if selected {
+                rects.push(PageRect {
+                    page_index: line.page_index,
+                    x: column_x(line, config),
+                    y: top,
+                    width: config.column_width(),
+                    height: line.height,
+                });
+            }
+            continue;
+        }
+
+        let from = if line.para_index == start.para {
+            start.offset.max(line.start_offset)
+        } else {
+            line.start_offset
+        };
+        let to = if line.para_index == end.para {
+            end.offset.min(line.end_offset)
+        } else {
+            line.end_offset
+        };
+        let break_selected = line.is_last_line && line.para_index < end.para;
+        if from > to || (from == to && !break_selected) {
+            continue;
+        }
+
+        let placement = line_placement(line, &line_format(document, line, config), config);
+        let left = placement.x_at(line, from - line.start_offset);
+        let mut right = placement.x_at(line, to - line.start_offset);
+        if break_selected {
+            // Roughly the width of a space
+            right += (line.ascent + line.descent) / 4.0;
+        }
+        rects.push(PageRect {
+            page_index: line.page_index,
+            x: left,
+            y: top,
+            width: right - left,
+            height: line.height,
+        });
+    }
+    rects
+}
+
+/// Page X coordinate of the left edge of a line's column
+fn column_x(line: &DisplayLine, config: &LayoutConfig) -> f64 {
+    config.margin_left + line.column_index as f64 * (config.column_width() + config.column_gap)
+}
+
+/// Resolved formatting of a line's paragraph
+fn line_format(document: &Document, line: &DisplayLine, config: &LayoutConfig) -> ParagraphFormat {
+    let default_meta = ParagraphMeta::default();
+    let meta = document
+        .paragraphs
+        .get(line.para_index)
+        .map(|p| &p.meta)
+        .unwrap_or(&default_meta);
+    paragraph_format(document, meta, config)
+}
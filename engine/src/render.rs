@@ -32,6 +32,12 @@
 //! // Send json to JavaScript for execution
 //! ```
 //!
+//! # Caret and Selection
+//!
+//! `generate_render_commands_with_overlay()` also draws a `SelectionOverlay`:
+//! selection rectangles are drawn before the text so that they appear behind
+//! it, and the caret is drawn after everything else.
+//!
 //! # Styled Text Rendering
 //!
 //! Text rendering handles inline styles by splitting lines into styled segments.
@@ -40,8 +46,17 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::document::{BlockType, Document, DocumentTable, HorizontalAlign, ImagePositionMode, ImageWrapStyle, ListType, TextAlign, TextStyle};
-use crate::layout::{DisplayLine, LayoutConfig, TableLayout};
+use crate::document::{
+    BlockType, BorderStyle, CellBorder, Document, DocumentTable, HorizontalAlign, ImagePositionMode, ImageWrapStyle,
+    ListType, Paragraph, TextStyle,
+};
+use crate::layout::{cell_config, list_indent, paragraph_format, DisplayLine, LayoutConfig, LineRun, ParagraphFormat, TableLayout};
+use crate::measure::FontSpec;
+use crate::position::{checkbox_rect, line_placement, merge_origin, PageRect};
+use crate::text::char_to_byte_index;
+
+/// Text color of links without an explicit color
+pub const LINK_COLOR: &str = "#1a73e8";
 
 /// A render command that can be sent to JavaScript for drawing
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +67,9 @@ pub enum RenderCommand {
         font: String,
         size: f64,
         bold: bool,
+        /// Numeric weight (100-900); `bold` is kept for weight-unaware renderers
+        #[serde(default)]
+        weight: u16,
         italic: bool,
     },
     /// Set the fill color
@@ -134,6 +152,7 @@ pub enum RenderCommand {
         y2: f64,
         width: f64,
         color: String,
+        style: BorderStyle,
     },
     /// Fill a table cell background
     FillCellBackground {
@@ -145,12 +164,25 @@ pub enum RenderCommand {
     },
 }
 
+impl RenderCommand {
+    /// SetFont command for a font spec; fonts without a family use `default_family`
+    pub fn set_font(font: &FontSpec, default_family: &str) -> Self {
+        RenderCommand::SetFont {
+            font: font.family.clone().unwrap_or_else(|| default_family.to_string()),
+            size: font.size,
+            bold: font.is_bold(),
+            weight: font.weight,
+            italic: font.italic,
+        }
+    }
+}
+
 /// A styled text segment for rendering
+///
+/// Fonts (bold, italic, family, size) come from the display line's font runs.
 #[derive(Debug, Clone)]
 struct StyledSegment {
     text: String,
-    bold: bool,
-    italic: bool,
     underline: bool,
     strikethrough: bool,
     color: String,
@@ -158,21 +190,24 @@ struct StyledSegment {
 }
 
 /// Get styled segments for a display line
-/// Splits the line text based on overlapping styles
+/// Splits the line text based on overlapping styles and the line's font runs
 fn get_styled_segments(
     line_text: &str,
     line_start: usize,
     line_end: usize,
     styles: &[TextStyle],
-    default_color: &str,
+    runs: &[LineRun],
+    format: &ParagraphFormat,
     _block_type: BlockType,
 ) -> Vec<StyledSegment> {
+    let default_color = format.color.as_deref().unwrap_or("#202124");
     if line_text.is_empty() {
         return vec![];
     }
 
-    // Find all style boundaries within this line
+    // Find all style and font run boundaries within this line
     let mut boundaries: Vec<usize> = vec![line_start, line_end];
+    boundaries.extend(runs.iter().map(|run| line_start + run.start).filter(|b| *b < line_end));
     for style in styles {
         if style.start > line_start && style.start < line_end {
             boundaries.push(style.start);
@@ -190,34 +225,27 @@ fn get_styled_segments(
         let seg_start = boundaries[i];
         let seg_end = boundaries[i + 1];
 
-        // Get text for this segment
-        let text_start = seg_start - line_start;
-        let text_end = seg_end - line_start;
+        // Get text for this segment (offsets are in characters)
+        let text_start = char_to_byte_index(line_text, seg_start - line_start);
+        let text_end = char_to_byte_index(line_text, seg_end - line_start);
         if text_start >= line_text.len() {
             continue;
         }
-        let text = line_text[text_start..text_end.min(line_text.len())].to_string();
+        let text = line_text[text_start..text_end].to_string();
         if text.is_empty() {
             continue;
         }
 
-        // Merge styles that apply to this segment
-        let mut bold = false;
-        let mut italic = false;
-        let mut underline = false;
-        let mut strikethrough = false;
+        // Merge styles that apply to this segment over the paragraph's formatting
+        let mut underline = format.underline;
+        let mut strikethrough = format.strikethrough;
         let mut color: Option<String> = None;
         let mut background: Option<String> = None;
+        let mut link = false;
 
         for style in styles {
             if style.start <= seg_start && style.end >= seg_end {
                 // This style fully covers this segment
-                if style.bold {
-                    bold = true;
-                }
-                if style.italic {
-                    italic = true;
-                }
                 if style.underline {
                     underline = true;
                 }
@@ -230,17 +258,20 @@ fn get_styled_segments(
                 if style.background.is_some() && background.is_none() {
                     background = style.background.clone();
                 }
+                if style.link.as_ref().is_some_and(|l| l.is_safe()) {
+                    link = true;
+                }
             }
         }
 
+        // Links are underlined and blue unless colored explicitly
+        let default_color = if link { LINK_COLOR } else { default_color };
         segments.push(StyledSegment {
             text,
-            bold,
-            italic,
-            underline,
+            underline: underline || link,
             strikethrough,
             color: color.unwrap_or_else(|| default_color.to_string()),
-            background,
+            background: background.or_else(|| format.background.clone()),
         });
     }
 
@@ -248,12 +279,10 @@ fn get_styled_segments(
     if segments.is_empty() {
         segments.push(StyledSegment {
             text: line_text.to_string(),
-            bold: false,
-            italic: false,
-            underline: false,
-            strikethrough: false,
+            underline: format.underline,
+            strikethrough: format.strikethrough,
             color: default_color.to_string(),
-            background: None,
+            background: format.background.clone(),
         });
     }
 
@@ -261,7 +290,7 @@ fn get_styled_segments(
 }
 
 /// Calculate image X position based on position mode, alignment and column
-fn calculate_image_x(
+pub fn calculate_image_x(
     image: &crate::document::DocumentImage,
     column_index: usize,
     config: &LayoutConfig,
@@ -288,6 +317,15 @@ fn calculate_image_x(
     }
 }
 
+/// Caret and selection rectangles to draw on top of a page
+#[derive(Debug, Clone, Default)]
+pub struct SelectionOverlay {
+    /// Caret rectangle, drawn over the text
+    pub caret: Option<PageRect>,
+    /// Selection rectangles, drawn under the text
+    pub selection: Vec<PageRect>,
+}
+
 /// Generate render commands for a specific page
 /// Uses multi-pass rendering for proper layering:
 /// 1. Behind images (under text, with reduced opacity)
@@ -299,6 +337,19 @@ pub fn generate_render_commands(
     document: &Document,
     config: &LayoutConfig,
     page_index: usize,
+) -> Vec<RenderCommand> {
+    let overlay = SelectionOverlay::default();
+    generate_render_commands_with_overlay(display_lines, document, config, page_index, &overlay)
+}
+
+/// Generate render commands for a page, including the caret and selection
+/// rectangles of `overlay` that lie on it
+pub fn generate_render_commands_with_overlay(
+    display_lines: &[DisplayLine],
+    document: &Document,
+    config: &LayoutConfig,
+    page_index: usize,
+    overlay: &SelectionOverlay,
 ) -> Vec<RenderCommand> {
     let mut commands: Vec<RenderCommand> = Vec::new();
 
@@ -380,18 +431,36 @@ pub fn generate_render_commands(
     }
 
     // ===== PASS 3: Text and inline/top-bottom images =====
+    // Selection highlight goes under the text
+    for rect in overlay.selection.iter().filter(|r| r.page_index == page_index) {
+        commands.push(RenderCommand::DrawSelection {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        });
+    }
+
     for dl in &page_lines {
-        if dl.is_page_break {
-            continue;
-        }
+        render_flow_line(dl, &document.paragraphs, document, config, &mut commands);
+    }
 
+    // ===== PASS 4: In-front images (rendered last, over text) =====
+    for dl in &page_lines {
         if dl.is_image {
             if let Some(image_id) = &dl.image_id {
                 if let Some(image) = document.images.iter().find(|img| &img.id == image_id) {
-                    // Only render inline and top-bottom images in this pass
-                    if matches!(image.wrap_style, ImageWrapStyle::Inline | ImageWrapStyle::TopBottom) {
+                    if image.wrap_style == ImageWrapStyle::InFront {
                         let x = calculate_image_x(image, dl.column_index, config);
-                        let y = config.margin_top + dl.y_position;
+                        let y = if image.position_mode == ImagePositionMode::FixedPosition {
+                            if image.page_index == Some(page_index) {
+                                config.margin_top + image.y.unwrap_or(0.0)
+                            } else {
+                                continue;
+                            }
+                        } else {
+                            config.margin_top + dl.y_position
+                        };
 
                         commands.push(RenderCommand::DrawImage {
                             image_id: image_id.clone(),
@@ -407,299 +476,315 @@ pub fn generate_render_commands(
                     }
                 }
             }
-            continue;
         }
+    }
+
+    // Caret goes over everything else
+    if let Some(caret) = overlay.caret.filter(|c| c.page_index == page_index) {
+        commands.push(RenderCommand::DrawCursor {
+            x: caret.x,
+            y: caret.y,
+            height: caret.height,
+        });
+    }
+
+    // Draw page number
+    commands.push(RenderCommand::SetFillColor {
+        color: "#999".to_string(),
+    });
+    commands.push(RenderCommand::set_font(&FontSpec::new(10.0), &config.font_family));
+    commands.push(RenderCommand::DrawPageNumber {
+        number: page_index + 1,
+        x: config.page_width / 2.0,
+        y: config.page_height - 20.0,
+    });
+
+    commands
+}
+
+/// Draw a display line of flowing content: text, a table or an inline image
+///
+/// `paragraphs` are the paragraphs the line's `para_index` refers to: the
+/// document's, or those of the table cell the line is in.
+fn render_flow_line(
+    dl: &DisplayLine,
+    paragraphs: &[Paragraph],
+    document: &Document,
+    config: &LayoutConfig,
+    commands: &mut Vec<RenderCommand>,
+) {
+    if dl.is_page_break {
+        return;
+    }
+
+    if dl.is_image {
+        if let Some(image_id) = &dl.image_id {
+            if let Some(image) = document.images.iter().find(|img| &img.id == image_id) {
+                // Other images are drawn in passes of their own
+                if matches!(image.wrap_style, ImageWrapStyle::Inline | ImageWrapStyle::TopBottom) {
+                    let x = calculate_image_x(image, dl.column_index, config);
+                    let y = config.margin_top + dl.y_position;
+
+                    commands.push(RenderCommand::DrawImage {
+                        image_id: image_id.clone(),
+                        x,
+                        y,
+                        width: image.width.min(config.column_width()),
+                        height: image.cropped_height(),
+                        crop_top: image.crop_top,
+                        crop_right: image.crop_right,
+                        crop_bottom: image.crop_bottom,
+                        crop_left: image.crop_left,
+                    });
+                }
+            }
+        }
+        return;
+    }
 
-        // Handle tables
-        if dl.is_table {
-            if let Some(table_id) = &dl.table_id {
-                if let Some(table) = document.tables.iter().find(|t| &t.id == table_id) {
-                    if let Some(layout) = &dl.table_layout {
-                        let x = config.margin_left + dl.column_index as f64 * (config.column_width() + config.column_gap);
-                        let y = config.margin_top + dl.y_position;
-                        render_table(table, layout, x, y, &mut commands, config);
+    // Handle tables
+    if dl.is_table {
+        if let Some(table_id) = &dl.table_id {
+            if let Some(table) = document.tables.iter().find(|t| &t.id == table_id) {
+                if let Some(layout) = &dl.table_layout {
+                    let x = config.margin_left + dl.column_index as f64 * (config.column_width() + config.column_gap);
+                    // A split table draws its repeated header rows and its part on this page
+                    let bands = match &dl.table_fragment {
+                        Some(fragment) => fragment.bands(),
+                        None => vec![(0.0, layout.total_height)],
+                    };
+                    let mut top = config.margin_top + dl.y_position;
+                    for (start, end) in bands {
+                        render_table(document, table, layout, x, top - start, (top, top + end - start), commands, config);
+                        top += end - start;
                     }
                 }
             }
-            continue;
         }
+        return;
+    }
 
-        // Calculate text position
-        let y = config.margin_top + dl.y_position;
-        let col_offset = dl.column_index as f64 * (config.column_width() + config.column_gap);
-
-        // Get paragraph meta
-        let default_meta = crate::document::ParagraphMeta::default();
-        let para_meta = document
-            .paragraphs
-            .get(dl.para_index)
-            .map(|p| &p.meta)
-            .unwrap_or(&default_meta);
-
-        // Calculate font size
-        let base_font_size = para_meta.font_size.unwrap_or(config.font_size);
-        let font_size = base_font_size * dl.block_type.font_size_multiplier();
-
-        // Set font
-        commands.push(RenderCommand::SetFont {
-            font: "Arial".to_string(), // TODO: make configurable
-            size: font_size,
-            bold: dl.block_type.is_bold(),
-            italic: dl.block_type.is_italic(),
+    // Calculate text position
+    let y = config.margin_top + dl.y_position;
+    let col_offset = dl.column_index as f64 * (config.column_width() + config.column_gap);
+
+    // Get paragraph meta
+    let default_meta = crate::document::ParagraphMeta::default();
+    let para_meta = paragraphs
+        .get(dl.para_index)
+        .map(|p| &p.meta)
+        .unwrap_or(&default_meta);
+
+    // Resolve the paragraph's style and set its font
+    let format = paragraph_format(document, para_meta, config);
+    let line_font = format.font.clone();
+    let font_size = line_font.size;
+    commands.push(RenderCommand::set_font(&line_font, &config.font_family));
+
+    // List indent, floats and alignment decide where the text starts
+    let placement = line_placement(dl, &format, config);
+
+    // Text is drawn from the top of its em box, which sits `ascent` above the baseline
+    let text_y = y + dl.baseline() - dl.ascent;
+
+    // Draw list marker in the gutter before the item's indent level
+    if let Some(rect) = checkbox_rect(dl, &format, config) {
+        commands.push(RenderCommand::SetStrokeColor {
+            color: "#5f6368".to_string(),
+        });
+        commands.push(RenderCommand::StrokeRect {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        });
+        if para_meta.checked {
+            // Tick mark
+            commands.push(RenderCommand::SetStrokeColor {
+                color: "#1a73e8".to_string(),
+            });
+            let points = [(0.2, 0.5), (0.42, 0.75), (0.8, 0.25)];
+            for pair in points.windows(2) {
+                commands.push(RenderCommand::DrawLine {
+                    x1: rect.x + rect.width * pair[0].0,
+                    y1: rect.y + rect.height * pair[0].1,
+                    x2: rect.x + rect.width * pair[1].0,
+                    y2: rect.y + rect.height * pair[1].1,
+                    width: 2.0,
+                });
+            }
+        }
+    } else if let Some(marker) = &dl.list_marker {
+        commands.push(RenderCommand::SetFillColor {
+            color: "#202124".to_string(),
         });
 
-        // Calculate list indent and float offset
-        let list_indent = if dl.list_type != ListType::None {
-            font_size * 1.5
+        let gutter_x = config.margin_left + col_offset + format.indent.line_start(true)
+            + list_indent(dl.list_type, dl.list_level, font_size)
+            - list_indent(dl.list_type, 0, font_size);
+        if marker == "•" {
+            commands.push(RenderCommand::FillCircle {
+                x: gutter_x + font_size * 0.5,
+                y: y + dl.baseline() - font_size * 0.3,
+                radius: font_size * 0.15,
+            });
         } else {
-            0.0
-        };
+            commands.push(RenderCommand::DrawText {
+                text: marker.clone(),
+                x: gutter_x + font_size * 0.25,
+                y: text_y,
+            });
+        }
+    }
 
-        // For floats, text must avoid the float's X region
-        // float_x is relative to column start, we need to offset text accordingly
-        let float_offset = if let Some(ref fr) = dl.float_reduction {
-            // The float occupies X range [float_x, float_x + width]
-            // Text must be outside this range
-            // For left-side floats (or floats starting from x=0): text starts after float
-            // For right-side floats: text ends before float (handled by reduced width)
+    // Draw blockquote indicator
+    if dl.block_type == BlockType::Blockquote && dl.start_offset == 0 {
+        commands.push(RenderCommand::SetFillColor {
+            color: "#ccc".to_string(),
+        });
+        commands.push(RenderCommand::FillRect {
+            x: config.margin_left + col_offset + format.indent.left,
+            y,
+            width: 3.0,
+            height: dl.height,
+        });
+    }
 
-            // Calculate where the float ends (relative to column start)
-            let float_end_x = fr.float_x + fr.width + 10.0; // 10px gap
+    // Get paragraph styles for this line, with their character styles applied
+    let para_styles: Vec<TextStyle> = paragraphs
+        .get(dl.para_index)
+        .map(|p| p.styles.iter().map(|s| document.resolve_text_style(s, font_size)).collect())
+        .unwrap_or_default();
+
+    // Get styled segments for this line
+    let mut segments = get_styled_segments(
+        &dl.text,
+        dl.start_offset,
+        dl.end_offset,
+        &para_styles,
+        &dl.runs,
+        &format,
+        dl.block_type,
+    );
+
+    // Ticked checklist items are greyed out and struck through
+    if dl.list_type == ListType::Checklist && para_meta.checked && config.strike_checked_items {
+        for segment in &mut segments {
+            segment.color = "#80868b".to_string();
+            segment.strikethrough = true;
+        }
+    }
 
-            // If float starts near the left edge, offset text to the right
-            if fr.float_x < config.column_width() / 2.0 {
-                float_end_x
-            } else {
-                0.0 // Float is on right side, no left offset needed
-            }
-        } else {
-            0.0
+    // Render each styled segment at the position of its first character
+    let mut segment_start = 0;
+    for segment in &segments {
+        let current_x = placement.x_at(dl, segment_start);
+        // Segments never cross a font run, so the run of their first character applies
+        let run = dl.run_at(segment_start + 1);
+        segment_start += segment.text.chars().count();
+        let text = match run {
+            Some(run) => run.display_text(&segment.text),
+            None => segment.text.clone(),
         };
 
-        let text_start_x = config.margin_left + col_offset + list_indent + float_offset;
-
-        // Draw list marker
-        if dl.start_offset == 0 && dl.list_type != ListType::None {
-            commands.push(RenderCommand::SetFillColor {
-                color: "#202124".to_string(),
-            });
-
-            match dl.list_type {
-                ListType::Bullet => {
-                    let bullet_x = config.margin_left + col_offset + font_size * 0.5;
-                    let bullet_y = y + config.line_height_px() / 2.0;
-                    commands.push(RenderCommand::FillCircle {
-                        x: bullet_x,
-                        y: bullet_y,
-                        radius: font_size * 0.15,
-                    });
-                }
-                ListType::Numbered => {
-                    if let Some(num) = dl.list_number {
-                        let marker_x = config.margin_left + col_offset + font_size * 1.2;
-                        let marker_y = y + (config.line_height_px() - font_size) / 2.0;
-                        commands.push(RenderCommand::DrawText {
-                            text: format!("{}.", num),
-                            x: marker_x,
-                            y: marker_y,
-                        });
-                    }
-                }
-                ListType::None => {}
-            }
-        }
+        // Set font for this segment; runs of different sizes share the line's baseline
+        let segment_font = run.map(|r| r.font.clone()).unwrap_or_else(|| line_font.clone());
+        let font_size = segment_font.size;
+        let text_y = match run {
+            Some(run) => y + dl.baseline() - run.ascent,
+            None => text_y,
+        };
+        commands.push(RenderCommand::set_font(&segment_font, &config.font_family));
 
-        // Draw blockquote indicator
-        if dl.block_type == BlockType::Blockquote && dl.start_offset == 0 {
+        // Draw background/highlight if present
+        if let Some(ref bg_color) = segment.background {
             commands.push(RenderCommand::SetFillColor {
-                color: "#ccc".to_string(),
+                color: bg_color.clone(),
             });
+            // Note: width will need to be calculated by JS, using placeholder
             commands.push(RenderCommand::FillRect {
-                x: config.margin_left + col_offset,
+                x: current_x,
                 y,
-                width: 3.0,
-                height: config.line_height_px(),
+                width: 0.0, // JS will calculate based on text measurement
+                height: dl.height,
             });
         }
 
-        // Get paragraph styles for this line
-        let para_styles = document
-            .paragraphs
-            .get(dl.para_index)
-            .map(|p| &p.styles[..])
-            .unwrap_or(&[]);
-
-        // Default text color
-        let default_color = para_meta
-            .text_color
-            .clone()
-            .unwrap_or_else(|| "#202124".to_string());
-
-        // Draw text based on alignment
-        let text_y = y + (config.line_height_px() - font_size) / 2.0;
-
-        // Get styled segments for this line
-        let segments = get_styled_segments(
-            &dl.text,
-            dl.start_offset,
-            dl.end_offset,
-            para_styles,
-            &default_color,
-            dl.block_type,
-        );
-
-        // Render each styled segment
-        let current_x = text_start_x;
-        for segment in &segments {
-            // Set font for this segment
-            commands.push(RenderCommand::SetFont {
-                font: "Arial".to_string(),
-                size: font_size,
-                bold: segment.bold || dl.block_type.is_bold(),
-                italic: segment.italic || dl.block_type.is_italic(),
-            });
+        // Set text color
+        commands.push(RenderCommand::SetFillColor {
+            color: segment.color.clone(),
+        });
 
-            // Draw background/highlight if present
-            if let Some(ref bg_color) = segment.background {
-                commands.push(RenderCommand::SetFillColor {
-                    color: bg_color.clone(),
-                });
-                // Note: width will need to be calculated by JS, using placeholder
-                commands.push(RenderCommand::FillRect {
+        // Draw text
+        if placement.word_spacing > 0.0 {
+            let words: Vec<String> = text.split(' ').map(|s| s.to_string()).collect();
+            if words.len() > 1 {
+                commands.push(RenderCommand::DrawTextJustified {
+                    words,
                     x: current_x,
-                    y,
-                    width: 0.0, // JS will calculate based on text measurement
-                    height: config.line_height_px(),
+                    y: text_y,
+                    word_spacing: placement.word_spacing,
                 });
-            }
-
-            // Set text color
-            commands.push(RenderCommand::SetFillColor {
-                color: segment.color.clone(),
-            });
-
-            // Draw text
-            if para_meta.align == TextAlign::Justify && !dl.is_last_line && !dl.text.is_empty() && segments.len() == 1 {
-                // Only use justified rendering for unstyled single-segment lines
-                let words: Vec<String> = segment.text.split(' ').map(|s| s.to_string()).collect();
-                if words.len() > 1 {
-                    commands.push(RenderCommand::DrawTextJustified {
-                        words,
-                        x: current_x,
-                        y: text_y,
-                        word_spacing: 0.0,
-                    });
-                } else {
-                    commands.push(RenderCommand::DrawText {
-                        text: segment.text.clone(),
-                        x: current_x,
-                        y: text_y,
-                    });
-                }
             } else {
                 commands.push(RenderCommand::DrawText {
-                    text: segment.text.clone(),
+                    text: text.clone(),
                     x: current_x,
                     y: text_y,
                 });
             }
-
-            // Draw underline if needed (JS needs to measure text width)
-            if segment.underline {
-                commands.push(RenderCommand::SetStrokeColor {
-                    color: segment.color.clone(),
-                });
-                commands.push(RenderCommand::DrawUnderline {
-                    x: current_x,
-                    y: text_y + font_size + 2.0,
-                    width: 0.0, // JS will calculate
-                });
-            }
-
-            // Draw strikethrough if needed
-            if segment.strikethrough {
-                commands.push(RenderCommand::SetStrokeColor {
-                    color: segment.color.clone(),
-                });
-                commands.push(RenderCommand::DrawStrikethrough {
-                    x: current_x,
-                    y: text_y + font_size / 2.0,
-                    width: 0.0, // JS will calculate
-                });
-            }
-
-            // Note: current_x advancement will be handled by JS based on text measurement
-            // We're emitting relative positions here
+        } else {
+            commands.push(RenderCommand::DrawText {
+                text,
+                x: current_x,
+                y: text_y,
+            });
         }
-    }
 
-    // ===== PASS 4: In-front images (rendered last, over text) =====
-    for dl in &page_lines {
-        if dl.is_image {
-            if let Some(image_id) = &dl.image_id {
-                if let Some(image) = document.images.iter().find(|img| &img.id == image_id) {
-                    if image.wrap_style == ImageWrapStyle::InFront {
-                        let x = calculate_image_x(image, dl.column_index, config);
-                        let y = if image.position_mode == ImagePositionMode::FixedPosition {
-                            if image.page_index == Some(page_index) {
-                                config.margin_top + image.y.unwrap_or(0.0)
-                            } else {
-                                continue;
-                            }
-                        } else {
-                            config.margin_top + dl.y_position
-                        };
+        // Draw underline if needed (JS needs to measure text width)
+        if segment.underline {
+            commands.push(RenderCommand::SetStrokeColor {
+                color: segment.color.clone(),
+            });
+            commands.push(RenderCommand::DrawUnderline {
+                x: current_x,
+                y: text_y + font_size + 2.0,
+                width: 0.0, // JS will calculate
+            });
+        }
 
-                        commands.push(RenderCommand::DrawImage {
-                            image_id: image_id.clone(),
-                            x,
-                            y,
-                            width: image.width.min(config.column_width()),
-                            height: image.cropped_height(),
-                            crop_top: image.crop_top,
-                            crop_right: image.crop_right,
-                            crop_bottom: image.crop_bottom,
-                            crop_left: image.crop_left,
-                        });
-                    }
-                }
-            }
+        // Draw strikethrough if needed
+        if segment.strikethrough {
+            commands.push(RenderCommand::SetStrokeColor {
+                color: segment.color.clone(),
+            });
+            commands.push(RenderCommand::DrawStrikethrough {
+                x: current_x,
+                y: text_y + font_size / 2.0,
+                width: 0.0, // JS will calculate
+            });
         }
     }
-
-    // Draw page number
-    commands.push(RenderCommand::SetFillColor {
-        color: "#999".to_string(),
-    });
-    commands.push(RenderCommand::SetFont {
-        font: "Arial".to_string(),
-        size: 10.0,
-        bold: false,
-        italic: false,
-    });
-    commands.push(RenderCommand::DrawPageNumber {
-        number: page_index + 1,
-        x: config.page_width / 2.0,
-        y: config.page_height - 20.0,
-    });
-
-    commands
 }
 
 /// Render a table with borders and cell contents
+///
+/// The table's top is at `y`, but only the part between the `clip` Y
+/// coordinates is drawn, closed off by borders at its edges: the part of a
+/// split table on one page, or its repeated header rows. Cell content is
+/// drawn like the document body, in a column the size of the cell's text area.
+#[allow(clippy::too_many_arguments)]
 fn render_table(
+    document: &Document,
     table: &DocumentTable,
     layout: &TableLayout,
     x: f64,
     y: f64,
+    clip: (f64, f64),
     commands: &mut Vec<RenderCommand>,
     config: &LayoutConfig,
 ) {
+    let (clip_top, clip_bottom) = clip;
     let border = table.border_width;
-    let border_color = &table.border_color;
-    let line_height = config.line_height_px();
-    let font_size = config.font_size;
-    let cell_padding = 4.0;
 
     // Pre-calculate row Y positions
     let mut row_y_positions: Vec<f64> = Vec::new();
@@ -748,192 +833,90 @@ fn render_table(
                     cell_height += (cell.row_span - 1) as f64 * border;
                 }
 
-                commands.push(RenderCommand::FillCellBackground {
-                    x: cell_x,
-                    y: cell_y,
-                    width: cell_width,
-                    height: cell_height,
-                    color: bg.clone(),
-                });
-            }
-        }
-    }
-
-    // 2. Draw table outer border (always draw all 4 sides)
-    // Top border
-    commands.push(RenderCommand::DrawTableBorder {
-        x1: x,
-        y1: y,
-        x2: x + layout.total_width,
-        y2: y,
-        width: border,
-        color: border_color.clone(),
-    });
-    // Bottom border
-    commands.push(RenderCommand::DrawTableBorder {
-        x1: x,
-        y1: y + layout.total_height,
-        x2: x + layout.total_width,
-        y2: y + layout.total_height,
-        width: border,
-        color: border_color.clone(),
-    });
-    // Left border
-    commands.push(RenderCommand::DrawTableBorder {
-        x1: x,
-        y1: y,
-        x2: x,
-        y2: y + layout.total_height,
-        width: border,
-        color: border_color.clone(),
-    });
-    // Right border
-    commands.push(RenderCommand::DrawTableBorder {
-        x1: x + layout.total_width,
-        y1: y,
-        x2: x + layout.total_width,
-        y2: y + layout.total_height,
-        width: border,
-        color: border_color.clone(),
-    });
-
-    // 3. Draw internal horizontal lines (skip lines inside merged cells)
-    for row_idx in 1..layout.row_heights.len() {
-        let line_y = row_y_positions.get(row_idx).copied().unwrap_or(y);
-
-        // For each column, check if this horizontal line should be drawn
-        let mut segment_start_x = x;
-        let mut col_idx = 0;
-
-        while col_idx < layout.column_widths.len() {
-            // Check if there's a cell that spans across this line
-            let mut skip_segment = false;
-            let mut skip_cols = 1;
-
-            // Look for cells in rows above that might span down past this line
-            for check_row in 0..row_idx {
-                if let Some(cell) = table.get_cell(check_row, col_idx) {
-                    if !cell.covered && cell.row_span > 1 {
-                        let span_end_row = check_row + cell.row_span;
-                        if span_end_row > row_idx {
-                            // This cell spans past our line
-                            skip_segment = true;
-                            skip_cols = cell.col_span;
-                            break;
-                        }
-                    }
-                }
-            }
-
-            if skip_segment {
-                // Draw segment before the merged cell
-                if segment_start_x < col_x_positions.get(col_idx).copied().unwrap_or(x) + border {
-                    commands.push(RenderCommand::DrawTableBorder {
-                        x1: segment_start_x,
-                        y1: line_y,
-                        x2: col_x_positions.get(col_idx).copied().unwrap_or(x) + border,
-                        y2: line_y,
-                        width: border,
-                        color: border_color.clone(),
+                let top = cell_y.max(clip_top);
+                let bottom = (cell_y + cell_height).min(clip_bottom);
+                if bottom > top {
+                    commands.push(RenderCommand::FillCellBackground {
+                        x: cell_x,
+                        y: top,
+                        width: cell_width,
+                        height: bottom - top,
+                        color: bg.clone(),
                     });
                 }
-                // Skip columns covered by the merge
-                col_idx += skip_cols;
-                if col_idx < col_x_positions.len() {
-                    segment_start_x = col_x_positions.get(col_idx).copied().unwrap_or(x);
-                }
-            } else {
-                col_idx += 1;
             }
         }
+    }
 
-        // Draw remaining segment
-        if segment_start_x < x + layout.total_width {
-            commands.push(RenderCommand::DrawTableBorder {
-                x1: segment_start_x,
-                y1: line_y,
-                x2: x + layout.total_width,
-                y2: line_y,
-                width: border,
-                color: border_color.clone(),
-            });
+    // 2. Draw the borders. Each edge between two cells gets one border,
+    // resolved from the sides of the cells that meet there; the edges of the
+    // clip are closed off with the sides of the cells inside them.
+    let row_count = layout.row_heights.len();
+    let col_count = layout.column_widths.len();
+    let row_top = |row: usize| row_y_positions.get(row).copied().unwrap_or(y + layout.total_height);
+    let col_left = |col: usize| col_x_positions.get(col).copied().unwrap_or(x + layout.total_width);
+    let origin = |row: usize, col: usize| merge_origin(table, row, col).unwrap_or((row, col));
+    let sides = |(row, col): (usize, usize)| table.get_cell(row, col).map(|cell| &cell.borders);
+    let table_border = table.default_border();
+
+    // Horizontal edges: between rows inside the clip, and at its top and bottom
+    let inside_top = (0..row_count).rev().find(|&row| row_top(row) <= clip_top + 0.5);
+    let inside_bottom = (0..row_count).rev().find(|&row| row_top(row) < clip_bottom - 0.5);
+    let mut horizontal: Vec<(f64, Option<usize>, Option<usize>)> = Vec::new();
+    if let Some(row) = inside_top {
+        horizontal.push((clip_top, None, Some(row)));
+    }
+    for row in 1..row_count {
+        let line_y = row_top(row);
+        // Lines at the clip edges are drawn as outer borders
+        if line_y > clip_top && line_y < clip_bottom {
+            horizontal.push((line_y, Some(row - 1), Some(row)));
         }
     }
+    if let Some(row) = inside_bottom {
+        horizontal.push((clip_bottom, Some(row), None));
+    }
+    for (line_y, above, below) in horizontal {
+        let segments = (0..col_count).map(|col| {
+            let above = above.map(|row| origin(row, col));
+            let below = below.map(|row| origin(row, col));
+            // No line inside a merged cell
+            let border = if above.is_some() && above == below {
+                None
+            } else {
+                let above = above.and_then(sides).and_then(|b| b.bottom.as_ref());
+                let below = below.and_then(sides).and_then(|b| b.top.as_ref());
+                collapse_border(&[above, below], &table_border)
+            };
+            (col_left(col), col_left(col + 1), border)
+        });
+        draw_border_line(segments, |x1, x2| (x1, line_y, x2, line_y), commands);
+    }
 
-    // 4. Draw internal vertical lines (skip lines inside merged cells)
-    for col_idx in 1..layout.column_widths.len() {
-        let line_x = col_x_positions.get(col_idx).copied().unwrap_or(x);
-
-        // For each row, check if this vertical line should be drawn
-        let mut segment_start_y = y;
-        let mut row_idx = 0;
-
-        while row_idx < layout.row_heights.len() {
-            // Check if there's a cell that spans across this line
-            let mut skip_segment = false;
-            let mut skip_rows = 1;
-
-            // Look for cells in columns to the left that might span right past this line
-            for check_col in 0..col_idx {
-                if let Some(cell) = table.get_cell(row_idx, check_col) {
-                    if !cell.covered && cell.col_span > 1 {
-                        let span_end_col = check_col + cell.col_span;
-                        if span_end_col > col_idx {
-                            // This cell spans past our line
-                            skip_segment = true;
-                            skip_rows = cell.row_span;
-                            break;
-                        }
-                    }
-                }
+    // Vertical edges, between the clip's top and bottom
+    for col in 0..=col_count {
+        let line_x = col_left(col);
+        let segments = (0..row_count).filter_map(|row| {
+            let y1 = row_top(row).max(clip_top);
+            let y2 = row_top(row + 1).min(clip_bottom);
+            if y1 >= y2 {
+                return None;
             }
-
-            if skip_segment {
-                // Draw segment before the merged cell
-                if segment_start_y < row_y_positions.get(row_idx).copied().unwrap_or(y) + border {
-                    commands.push(RenderCommand::DrawTableBorder {
-                        x1: line_x,
-                        y1: segment_start_y,
-                        x2: line_x,
-                        y2: row_y_positions.get(row_idx).copied().unwrap_or(y) + border,
-                        width: border,
-                        color: border_color.clone(),
-                    });
-                }
-                // Skip rows covered by the merge
-                row_idx += skip_rows;
-                if row_idx < row_y_positions.len() {
-                    segment_start_y = row_y_positions.get(row_idx).copied().unwrap_or(y);
-                }
+            let left = col.checked_sub(1).map(|col| origin(row, col));
+            let right = (col < col_count).then(|| origin(row, col));
+            let border = if left.is_some() && left == right {
+                None
             } else {
-                row_idx += 1;
-            }
-        }
-
-        // Draw remaining segment
-        if segment_start_y < y + layout.total_height {
-            commands.push(RenderCommand::DrawTableBorder {
-                x1: line_x,
-                y1: segment_start_y,
-                x2: line_x,
-                y2: y + layout.total_height,
-                width: border,
-                color: border_color.clone(),
-            });
-        }
+                let left = left.and_then(sides).and_then(|b| b.right.as_ref());
+                let right = right.and_then(sides).and_then(|b| b.left.as_ref());
+                collapse_border(&[left, right], &table_border)
+            };
+            Some((y1, y2, border))
+        });
+        draw_border_line(segments, |y1, y2| (line_x, y1, line_x, y2), commands);
     }
 
-    // 5. Draw cell text (only for non-covered cells)
-    commands.push(RenderCommand::SetFont {
-        font: "Arial".to_string(),
-        size: font_size,
-        bold: false,
-        italic: false,
-    });
-    commands.push(RenderCommand::SetFillColor {
-        color: "#202124".to_string(),
-    });
-
+    // 3. Draw cell content (only for non-covered cells)
     for (row_idx, row) in table.rows.iter().enumerate() {
         for (col_idx, cell) in row.cells.iter().enumerate() {
             // Skip covered cells
@@ -941,10 +924,11 @@ fn render_table(
                 continue;
             }
 
-            let cell_x = col_x_positions.get(col_idx).copied().unwrap_or(x) + border + cell_padding;
-            let cell_y = row_y_positions.get(row_idx).copied().unwrap_or(y) + border + cell_padding;
+            let padding = table.padding_of(cell);
+            let cell_x = col_x_positions.get(col_idx).copied().unwrap_or(x) + border + padding.left;
+            let cell_y = row_y_positions.get(row_idx).copied().unwrap_or(y) + border + padding.top;
 
-            // Calculate merged cell width for alignment
+            // Calculate merged cell width
             let mut cell_width = 0.0;
             for span_col in col_idx..(col_idx + cell.col_span).min(layout.column_widths.len()) {
                 cell_width += layout.column_widths.get(span_col).copied().unwrap_or(0.0);
@@ -952,30 +936,109 @@ fn render_table(
             if cell.col_span > 1 {
                 cell_width += (cell.col_span - 1) as f64 * border;
             }
+            let cell_config = cell_config(config, cell_x, cell_y, cell_width - padding.horizontal());
+
+            let lines = layout.cell_lines.get(row_idx).and_then(|r| r.get(col_idx));
+            for dl in lines.into_iter().flatten() {
+                // Lines cut by the clip are drawn with the other part of the table
+                let top = cell_y + dl.y_position;
+                if top < clip_top - 0.5 || top + dl.height > clip_bottom + 0.5 {
+                    continue;
+                }
+                // A cell has no passes for images: they are all drawn in place
+                let image = dl
+                    .image_id
+                    .as_ref()
+                    .and_then(|id| document.images.iter().find(|img| &img.id == id));
+                match image {
+                    Some(image) => commands.push(RenderCommand::DrawImage {
+                        image_id: image.id.clone(),
+                        x: calculate_image_x(image, dl.column_index, &cell_config),
+                        y: top,
+                        width: image.width.min(cell_config.column_width()),
+                        height: image.cropped_height(),
+                        crop_top: image.crop_top,
+                        crop_right: image.crop_right,
+                        crop_bottom: image.crop_bottom,
+                        crop_left: image.crop_left,
+                    }),
+                    None => render_flow_line(dl, &cell.paragraphs, document, &cell_config, commands),
+                }
+            }
+        }
+    }
+}
 
-            // Get cell text lines from layout
-            if let Some(cell_lines) = layout.cell_lines.get(row_idx).and_then(|r| r.get(col_idx)) {
-                let mut text_y = cell_y;
-                for line in cell_lines {
-                    if !line.is_empty() {
-                        // Calculate x position based on alignment
-                        let text_x = match cell.align {
-                            TextAlign::Left => cell_x,
-                            TextAlign::Center => cell_x + (cell_width - 2.0 * cell_padding) / 2.0,
-                            TextAlign::Right => cell_x + cell_width - 2.0 * cell_padding,
-                            TextAlign::Justify => cell_x,
-                        };
+/// The border drawn on an edge of a table, from the sides of the cells that
+/// meet there, top or left first; None if no line is drawn
+///
+/// Borders are collapsed like in CSS: a side set on a cell wins over the
+/// table's border, and of two cells' sides the visible one wins over none,
+/// then the wider one, then the style in the order double, solid, dashed,
+/// dotted. On a tie the cell above or to the left wins.
+///
+/// Layout leaves `table.border_width` pixels between cells whatever their
+/// borders, so the winner is drawn at most that wide: a wider line would
+/// cover the padding and content of the cells it runs between.
+fn collapse_border(sides: &[Option<&CellBorder>], table_border: &CellBorder) -> Option<CellBorder> {
+    let priority = |border: &CellBorder| {
+        let style = match border.style {
+            BorderStyle::None => return (0.0, 0),
+            BorderStyle::Dotted => 1,
+            BorderStyle::Dashed => 2,
+            BorderStyle::Solid => 3,
+            BorderStyle::Double => 4,
+        };
+        (border.width, style)
+    };
+    let border = sides
+        .iter()
+        .flatten()
+        .copied()
+        .reduce(|winner, side| if priority(side) > priority(winner) { side } else { winner })
+        .unwrap_or(table_border);
+    let width = border.width.min(table_border.width);
+    (border.style != BorderStyle::None && width > 0.0).then(|| CellBorder {
+        width,
+        ..border.clone()
+    })
+}
 
-                        commands.push(RenderCommand::DrawText {
-                            text: line.clone(),
-                            x: text_x,
-                            y: text_y,
-                        });
-                    }
-                    text_y += line_height;
+/// Draw the `(start, end, border)` segments of a border line, joining
+/// neighbouring segments that have the same border
+fn draw_border_line(
+    segments: impl Iterator<Item = (f64, f64, Option<CellBorder>)>,
+    ends: impl Fn(f64, f64) -> (f64, f64, f64, f64),
+    commands: &mut Vec<RenderCommand>,
+) {
+    let mut draw = |start: f64, end: f64, border: &CellBorder| {
+        let (x1, y1, x2, y2) = ends(start, end);
+        commands.push(RenderCommand::DrawTableBorder {
+            x1,
+            y1,
+            x2,
+            y2,
+            width: border.width,
+            color: border.color.clone(),
+            style: border.style,
+        });
+    };
+    let mut pending: Option<(f64, f64, CellBorder)> = None;
+    for (start, end, border) in segments {
+        pending = match (pending, border) {
+            (Some((from, to, current)), Some(border)) if current == border && (to - start).abs() < 0.5 => {
+                Some((from, end, current))
+            }
+            (pending, border) => {
+                if let Some((from, to, current)) = pending {
+                    draw(from, to, &current);
                 }
+                border.map(|border| (start, end, border))
             }
-        }
+        };
+    }
+    if let Some((from, to, border)) = pending {
+        draw(from, to, &border);
     }
 }
 
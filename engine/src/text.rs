@@ -19,11 +19,12 @@
 //! - `char_substring()`: Extract a substring by character indices
 //! - `char_to_byte_index()` / `byte_to_char_index()`: Index conversion
 //! - Word boundary detection for Ctrl+Arrow navigation
+//! - Alphabetic and roman numbering for list markers
 //!
-//! # Text Shaping (Future)
+//! # Text Shaping
 //!
-//! The `split_into_runs()` function is a placeholder for future integration with
-//! rustybuzz for proper text shaping. Currently returns the entire text as a single
+//! Shaping with real font data lives in the `font` module (`ShapingMeasurer`).
+//! The `split_into_runs()` function currently returns the entire text as a single
 //! run, but could be extended to:
 //! - Split by script (Latin, Arabic, CJK, etc.)
 //! - Handle bidirectional text
@@ -113,6 +114,41 @@ pub fn prev_word_boundary(text: &str, from_char: usize) -> usize {
     pos
 }
 
+/// Format a number alphabetically: 1 → "a", 26 → "z", 27 → "aa"
+pub fn to_alphabetic(n: usize) -> String {
+    let mut n = n.max(1);
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Format a number as lowercase roman numerals: 4 → "iv", 1999 → "mcmxcix"
+///
+/// Zero has no roman form and is formatted as "0".
+pub fn to_roman(n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"),
+        (100, "c"), (90, "xc"), (50, "l"), (40, "xl"),
+        (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    if n == 0 {
+        return "0".to_string();
+    }
+    let mut n = n;
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            result.push_str(numeral);
+            n -= value;
+        }
+    }
+    result
+}
+
 /// Represents a text run with consistent formatting
 #[derive(Debug, Clone)]
 pub struct TextRun {
@@ -112,6 +112,87 @@ fn test_paragraph_style_at() {
     assert!(para.style_at(7).is_none());
 }
 
+#[test]
+fn test_paragraph_insert_text_shifts_styles() {
+    let mut para = Paragraph::new("Hello World".to_string());
+    para.apply_style(6, 11, |s| s.italic = true);
+
+    para.insert_text(0, "Oh, ");
+
+    assert_eq!(para.text, "Oh, Hello World");
+    assert_eq!((para.styles[0].start, para.styles[0].end), (10, 15));
+}
+
+#[test]
+fn test_paragraph_insert_text_extends_style() {
+    let mut para = Paragraph::new("Hello World".to_string());
+    para.apply_style(0, 5, |s| s.bold = true);
+
+    para.insert_text(5, "!!");
+    para.insert_text(2, "é");
+
+    assert_eq!(para.text, "Heéllo!! World");
+    assert_eq!((para.styles[0].start, para.styles[0].end), (0, 8));
+}
+
+#[test]
+fn test_paragraph_delete_text_adjusts_styles() {
+    let mut para = Paragraph::new("aaa bbb ccc".to_string());
+    para.apply_style(0, 3, |s| s.bold = true);
+    para.apply_style(4, 7, |s| s.italic = true);
+    para.apply_style(8, 11, |s| s.underline = true);
+
+    para.delete_text(2, 8);
+
+    assert_eq!(para.text, "aaccc");
+    assert_eq!(para.styles.len(), 2);
+    assert!(para.styles[0].bold);
+    assert_eq!((para.styles[0].start, para.styles[0].end), (0, 2));
+    assert!(para.styles[1].underline);
+    assert_eq!((para.styles[1].start, para.styles[1].end), (2, 5));
+}
+
+#[test]
+fn test_paragraph_edits_use_char_offsets() {
+    let mut para = Paragraph::new("日本語のテキスト".to_string());
+    para.apply_style(4, 8, |s| s.bold = true);
+
+    para.delete_text(0, 3);
+    assert_eq!(para.text, "のテキスト");
+    assert_eq!((para.styles[0].start, para.styles[0].end), (1, 5));
+
+    para.set_text("のテ".to_string());
+    assert_eq!((para.styles[0].start, para.styles[0].end), (1, 2));
+}
+
+#[test]
+fn test_paragraph_split_off_and_append() {
+    let mut para = Paragraph::new("Hello World".to_string());
+    para.meta.list_type = ListType::Bullet;
+    para.apply_style(3, 8, |s| s.bold = true);
+
+    let tail = para.split_off(6);
+    assert_eq!((para.text.as_str(), tail.text.as_str()), ("Hello ", "World"));
+    assert_eq!((para.styles[0].start, para.styles[0].end), (3, 6));
+    assert_eq!((tail.styles[0].start, tail.styles[0].end), (0, 2));
+    assert_eq!(tail.meta.list_type, ListType::Bullet);
+
+    para.append(tail);
+    assert_eq!(para.text, "Hello World");
+    assert_eq!(para.styles.len(), 1);
+    assert_eq!((para.styles[0].start, para.styles[0].end), (3, 8));
+}
+
+#[test]
+fn test_paragraph_range_has_style() {
+    let mut para = Paragraph::new("Hello World".to_string());
+    para.apply_style(0, 3, |s| s.bold = true);
+
+    assert!(para.range_has_style(0, 3, |s| s.bold));
+    assert!(!para.range_has_style(0, 5, |s| s.bold));
+    assert!(!para.range_has_style(6, 11, |s| s.bold));
+}
+
 #[test]
 fn test_image_cropped_dimensions() {
     let mut image = DocumentImage::new(
@@ -132,3 +213,282 @@ fn test_image_cropped_dimensions() {
     assert_eq!(image.cropped_width(), 80.0);
     assert_eq!(image.cropped_height(), 100.0);
 }
+
+#[test]
+fn test_list_markers_cycle_by_level() {
+    assert_eq!(ListType::Bullet.marker(0, 0).as_deref(), Some("•"));
+    assert_eq!(ListType::Bullet.marker(1, 0).as_deref(), Some("◦"));
+    assert_eq!(ListType::Bullet.marker(2, 0).as_deref(), Some("▪"));
+    assert_eq!(ListType::Bullet.marker(3, 0).as_deref(), Some("•"));
+    assert_eq!(ListType::Numbered.marker(0, 3).as_deref(), Some("3."));
+    assert_eq!(ListType::Numbered.marker(1, 3).as_deref(), Some("c."));
+    assert_eq!(ListType::Numbered.marker(2, 3).as_deref(), Some("iii."));
+    assert_eq!(ListType::Numbered.marker(3, 3).as_deref(), Some("3."));
+    assert!(ListType::None.marker(0, 1).is_none());
+}
+
+#[test]
+fn test_number_formats() {
+    assert_eq!(NumberFormat::Decimal.format(7), "7");
+    assert_eq!(NumberFormat::DecimalZero.format(7), "07");
+    assert_eq!(NumberFormat::DecimalZero.format(12), "12");
+    assert_eq!(NumberFormat::LowerAlpha.format(2), "b");
+    assert_eq!(NumberFormat::UpperAlpha.format(28), "AB");
+    assert_eq!(NumberFormat::LowerRoman.format(4), "iv");
+    assert_eq!(NumberFormat::UpperRoman.format(12), "XII");
+}
+
+#[test]
+fn test_expand_number_template() {
+    let numbers = vec!["1".to_string(), "2".to_string(), "c".to_string()];
+    assert_eq!(expand_number_template("%1.%2.%3", &numbers), "1.2.c");
+    assert_eq!(expand_number_template("(%3)", &numbers), "(c)");
+    assert_eq!(expand_number_template("Article %1.", &numbers), "Article 1.");
+    // Missing levels expand to nothing; other percent signs are kept
+    assert_eq!(expand_number_template("%5%", &numbers), "%");
+    assert_eq!(expand_number_template("100%", &numbers), "100%");
+}
+
+#[test]
+fn test_split_off_keeps_numbering_restart_on_first_half() {
+    let mut para = Paragraph::new("first second".to_string());
+    para.meta.list_type = ListType::Numbered;
+    para.meta.number_format = Some(NumberFormat::UpperRoman);
+    para.meta.list_start = Some(5);
+    para.meta.list_continue = true;
+
+    let tail = para.split_off(6);
+    assert_eq!(tail.meta.number_format, Some(NumberFormat::UpperRoman));
+    assert_eq!(tail.meta.list_start, None);
+    assert!(!tail.meta.list_continue);
+    assert_eq!(para.meta.list_start, Some(5));
+}
+
+#[test]
+fn test_safe_link_urls() {
+    assert!(is_safe_link_url("https://example.com"));
+    assert!(is_safe_link_url("HTTP://example.com/a?b=c"));
+    assert!(is_safe_link_url("mailto:someone@example.com"));
+    assert!(is_safe_link_url("#section-2"));
+
+    assert!(!is_safe_link_url("javascript:alert(1)"));
+    assert!(!is_safe_link_url("data:text/html,hi"));
+    assert!(!is_safe_link_url("example.com"));
+    assert!(!is_safe_link_url("https://"));
+    assert!(!is_safe_link_url("#"));
+    assert!(!is_safe_link_url("https://exa mple.com"));
+    assert!(!is_safe_link_url("https://example.com/\u{0}"));
+}
+
+#[test]
+fn test_link_at_ignores_unsafe_links() {
+    let mut para = Paragraph::new("click here or there".to_string());
+    let link = Link::new(" https://example.com ", Some(String::new())).unwrap();
+    assert_eq!(link.url, "https://example.com");
+    assert!(link.title.is_none());
+    para.apply_style(6, 10, |s| s.link = Some(link.clone()));
+    para.apply_style(14, 19, |s| {
+        s.link = Some(Link { url: "javascript:alert(1)".to_string(), title: None })
+    });
+
+    assert_eq!(para.link_at(6), Some(&link));
+    assert_eq!(para.link_at(10), None);
+    assert_eq!(para.link_at(15), None);
+}
+
+#[test]
+fn test_resolve_style_inherits_from_based_on() {
+    let mut document = Document::new();
+    let heading = document.resolve_style("heading1");
+    assert_eq!(heading.font_scale, Some(2.0));
+    assert_eq!(heading.bold, Some(true));
+
+    // A change to "Normal" reaches every style based on it
+    document.styles[0].properties.color = Some("#333333".to_string());
+    assert_eq!(document.resolve_style("heading1").color.as_deref(), Some("#333333"));
+
+    // Loops and unknown IDs stop the chain
+    document.styles[0].based_on = Some("heading1".to_string());
+    assert_eq!(document.resolve_style("heading1").bold, Some(true));
+    assert!(document.would_cycle("normal", "heading1"));
+    assert_eq!(document.resolve_style("missing"), StyleProperties::default());
+}
+
+#[test]
+fn test_resolve_text_style_applies_character_style() {
+    let mut document = Document::new();
+    document.styles.push(NamedStyle {
+        id: "emphasis".to_string(),
+        name: "Emphasis".to_string(),
+        kind: StyleKind::Character,
+        based_on: None,
+        properties: StyleProperties {
+            italic: Some(true),
+            color: Some("#ff0000".to_string()),
+            font_scale: Some(1.5),
+            ..Default::default()
+        },
+    });
+    let mut style = TextStyle::new(0, 4);
+    style.style_id = Some("emphasis".to_string());
+    style.color = Some("#00ff00".to_string());
+
+    let resolved = document.resolve_text_style(&style, 16.0);
+    assert!(resolved.italic);
+    // Direct formatting wins over the style
+    assert_eq!(resolved.color.as_deref(), Some("#00ff00"));
+    assert_eq!(resolved.font_size, Some(24.0));
+}
+
+#[test]
+fn test_document_without_styles_gets_default_style_sheet() {
+    let json = r#"{"version":1,"paragraphs":[{"text":"Hi","meta":{"align":"center","block_type":"h2","list_type":"none","number_format":null,"number_template":null,"list_start":null,"font_size":null,"text_color":null}}],"images":[]}"#;
+    let document: Document = serde_json::from_str(json).unwrap();
+    assert_eq!(document.styles, default_styles());
+    assert_eq!(document.paragraphs[0].meta.align, Some(TextAlign::Center));
+    assert_eq!(document.paragraphs[0].meta.effective_style_id(), "heading2");
+}
+
+#[test]
+fn test_new_table_fills_column_and_keeps_width_when_columns_change() {
+    let mut table = DocumentTable::new("t".to_string(), 1, 2, 203.0);
+    assert_eq!(table.width_mode, TableWidthMode::Fixed);
+    assert_eq!(table.column_widths, vec![100.0, 100.0]);
+
+    let total = |table: &DocumentTable| table.column_widths.iter().sum::<f64>();
+    table.add_column(2);
+    assert!((total(&table) - 200.0).abs() < 1e-9);
+    assert!(table.delete_column(0));
+    assert!((total(&table) - 200.0).abs() < 1e-9);
+
+    // Without a width the columns share the page column
+    let table = DocumentTable::new("t".to_string(), 1, 4, 0.0);
+    assert_eq!(table.width_mode, TableWidthMode::Percentage);
+    assert_eq!(table.column_widths, vec![25.0; 4]);
+}
+
+#[test]
+fn test_cell_text_from_older_documents_becomes_paragraphs() {
+    let json = r#"{"text":"Bold\nright","styles":[{"start":0,"end":6,"bold":true}],"align":"right","colSpan":1,"rowSpan":1}"#;
+    let cell: TableCell = serde_json::from_str(json).unwrap();
+    assert_eq!(cell.paragraphs.len(), 2);
+    assert_eq!(cell.text(), "Bold\nright");
+    // The style carries over the newline into the second paragraph
+    assert_eq!((cell.paragraphs[0].styles[0].start, cell.paragraphs[0].styles[0].end), (0, 4));
+    assert_eq!((cell.paragraphs[1].styles[0].start, cell.paragraphs[1].styles[0].end), (0, 1));
+    assert!(cell.paragraphs.iter().all(|p| p.meta.align == Some(TextAlign::Right)));
+
+    let round_trip: TableCell = serde_json::from_str(&serde_json::to_string(&cell).unwrap()).unwrap();
+    assert_eq!(round_trip, cell);
+}
+
+#[test]
+fn test_merge_cells_keeps_paragraphs_and_finds_enclosing_tables() {
+    let mut table = DocumentTable::new("outer".to_string(), 1, 2, 0.0);
+    table.get_cell_mut(0, 0).unwrap().set_text("a\nb");
+    table.get_cell_mut(0, 1).unwrap().paragraphs = vec![Paragraph::new("\u{FFFB}inner".to_string())];
+    assert!(table.merge_cells(0, 0, 0, 1));
+    let texts: Vec<&str> = table.get_cell(0, 0).unwrap().paragraphs.iter().map(|p| p.text.as_str()).collect();
+    assert_eq!(texts, vec!["a", "b", "\u{FFFB}inner"]);
+    assert!(table.get_cell(0, 1).unwrap().is_empty());
+
+    let mut document = Document::new();
+    let mut inner = DocumentTable::new("inner".to_string(), 1, 1, 0.0);
+    inner.get_cell_mut(0, 0).unwrap().paragraphs = vec![Paragraph::new("\u{FFFB}innermost".to_string())];
+    document.tables.push(table);
+    document.tables.push(inner);
+    document.tables.push(DocumentTable::new("innermost".to_string(), 1, 1, 0.0));
+    assert_eq!(document.enclosing_tables("innermost"), vec!["inner", "outer"]);
+    assert!(document.enclosing_tables("outer").is_empty());
+}
+
+fn column_texts(table: &DocumentTable, col: usize) -> Vec<String> {
+    table.rows.iter().map(|row| row.cells[col].text()).collect()
+}
+
+#[test]
+fn test_sort_table_rows_below_header() {
+    let mut table = DocumentTable::new("t".to_string(), 6, 2, 0.0);
+    table.header_rows = 1;
+    for (row, (name, amount)) in [("Name", "Amount"), ("beta", "1,200"), ("", "9"), ("Alpha", "n/a"), ("gamma", "30"), ("alpha", "")]
+        .iter()
+        .enumerate()
+    {
+        table.get_cell_mut(row, 0).unwrap().set_text(name);
+        table.get_cell_mut(row, 1).unwrap().set_text(amount);
+    }
+
+    // Text ignores case and keeps equal rows in order; empty cells go last
+    assert!(table.sort_rows(0, true, false));
+    assert_eq!(column_texts(&table, 0), vec!["Name", "Alpha", "alpha", "beta", "gamma", ""]);
+    assert!(table.sort_rows(0, false, false));
+    assert_eq!(column_texts(&table, 0), vec!["Name", "gamma", "beta", "Alpha", "alpha", ""]);
+
+    // Numbers compare by value, before text
+    assert!(table.sort_rows(1, true, true));
+    assert_eq!(column_texts(&table, 1), vec!["Amount", "9", "30", "1,200", "n/a", ""]);
+    assert!(table.sort_rows(1, false, true));
+    assert_eq!(column_texts(&table, 1), vec!["Amount", "1,200", "30", "9", "n/a", ""]);
+    assert!(!table.sort_rows(2, true, true));
+
+    // Cells merged along a row move with it; cells merged across rows can't be sorted
+    table.merge_cells(5, 0, 5, 1);
+    table.get_cell_mut(5, 0).unwrap().set_text("0");
+    assert!(table.sort_rows(1, true, true));
+    assert_eq!(column_texts(&table, 0)[1], "0");
+    assert_eq!(table.get_cell(1, 1).unwrap().covered_by_row, Some(1));
+    table.merge_cells(2, 0, 3, 0);
+    let before = table.clone();
+    assert!(!table.sort_rows(1, true, true));
+    assert_eq!(table, before);
+}
+
+#[test]
+fn test_move_table_rows_and_columns() {
+    let mut table = DocumentTable::new("t".to_string(), 3, 3, 0.0);
+    table.column_widths = vec![10.0, 20.0, 70.0];
+    for row in 0..3 {
+        for col in 0..3 {
+            table.get_cell_mut(row, col).unwrap().set_text(&format!("{}{}", row, col));
+        }
+    }
+
+    assert!(table.move_row(0, 2));
+    assert_eq!(column_texts(&table, 0), vec!["10", "20", "00"]);
+    assert!(table.move_column(2, 0));
+    assert_eq!(table.rows[0].cells.iter().map(TableCell::text).collect::<Vec<_>>(), vec!["12", "10", "11"]);
+    assert_eq!(table.column_widths, vec![70.0, 10.0, 20.0]);
+    assert!(!table.move_row(0, 3));
+
+    // Merges move along the direction they don't span
+    table.merge_cells(0, 1, 1, 1);
+    assert!(!table.move_row(1, 2));
+    assert!(table.move_column(1, 2));
+    assert_eq!(table.get_cell(0, 2).unwrap().row_span, 2);
+    assert_eq!(table.get_cell(1, 2).unwrap().covered_by_col, Some(2));
+    table.merge_cells(2, 0, 2, 1);
+    assert!(!table.move_column(0, 2));
+}
+
+#[test]
+fn test_paragraphs_to_table_and_back() {
+    let mut styled = Paragraph::new("Name\tPrice\tQty".to_string());
+    styled.styles.push(TextStyle { bold: true, ..TextStyle::new(0, 10) });
+    let paragraphs = vec![styled, Paragraph::new("Tea\t3".to_string()), Paragraph::new("\u{FFFC}img".to_string())];
+
+    let table = DocumentTable::from_paragraphs("t".to_string(), &paragraphs, "\t", 0.0);
+    assert_eq!((table.num_rows(), table.num_cols()), (3, 3));
+    assert_eq!(column_texts(&table, 1), vec!["Price", "3", ""]);
+    assert_eq!(column_texts(&table, 0)[2], "\u{FFFC}img");
+    // Styles are split with the text
+    let price = &table.get_cell(0, 1).unwrap().paragraphs[0];
+    assert_eq!((price.styles[0].start, price.styles[0].end), (0, 5));
+    assert!(table.get_cell(0, 2).unwrap().paragraphs[0].styles.is_empty());
+
+    let back = table.to_paragraphs("\t");
+    let texts: Vec<&str> = back.iter().map(|p| p.text.as_str()).collect();
+    assert_eq!(texts, vec!["Name\tPrice\tQty", "Tea\t3\t", "\t\t", "\u{FFFC}img"]);
+    // The tabs come back unstyled
+    let bold: Vec<(usize, usize)> = back[0].styles.iter().map(|s| (s.start, s.end)).collect();
+    assert_eq!(bold, vec![(0, 4), (5, 10)]);
+}
@@ -0,0 +1,776 @@
+//! Tests for the Engine API
+
+use editor_engine::*;
+
+fn engine_with_paragraphs(count: usize) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_page_config(320.0, 300.0, 48.0, 48.0, 48.0, 48.0, 1, 24.0);
+    engine.set_paragraph(0, "Paragraph 0 with some words".to_string());
+    for i in 1..count {
+        engine.insert_paragraph(i, format!("Paragraph {} with some words", i));
+    }
+    engine
+}
+
+fn full_layout(engine: &Engine) -> Vec<DisplayLine> {
+    let config = LayoutConfig {
+        page_width: 320.0,
+        page_height: 300.0,
+        margin_top: 48.0,
+        margin_right: 48.0,
+        margin_bottom: 48.0,
+        margin_left: 48.0,
+        column_gap: 24.0,
+        ..Default::default()
+    };
+    compute_layout(engine.document(), &config, &FixedWidthMeasurer::default())
+}
+
+fn assert_layout_matches_full(engine: &Engine) {
+    let expected = full_layout(engine);
+    let actual = engine.display_lines();
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(&expected) {
+        assert_eq!((a.para_index, a.start_offset, a.page_index), (e.para_index, e.start_offset, e.page_index));
+        assert_eq!(a.text, e.text);
+        assert_eq!(a.y_position, e.y_position);
+    }
+}
+
+#[test]
+fn test_recompute_layout_only_when_dirty() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(3);
+
+    assert!(engine.recompute_layout_with(&measurer));
+    assert!(!engine.recompute_layout_with(&measurer));
+
+    engine.set_paragraph(1, "Changed".to_string());
+    assert!(engine.recompute_layout_with(&measurer));
+    assert_layout_matches_full(&engine);
+}
+
+#[test]
+fn test_edit_reports_only_affected_page() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(30);
+    engine.recompute_layout_with(&measurer);
+    assert!(engine.page_count() > 2);
+
+    let page = engine.get_page_for_position(20, 0);
+    engine.set_paragraph(20, "Paragraph X with some words".to_string());
+    engine.recompute_layout_with(&measurer);
+
+    assert_eq!(engine.changed_pages(), &[page]);
+    assert_eq!(engine.get_changed_pages(), format!("[{}]", page));
+    assert_layout_matches_full(&engine);
+}
+
+#[test]
+fn test_structural_edits_keep_layout_consistent() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(20);
+    engine.recompute_layout_with(&measurer);
+
+    engine.insert_paragraph(3, "New paragraph in the middle of the document".to_string());
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+
+    engine.delete_paragraph(0);
+    engine.toggle_list(4, "numbered");
+    engine.toggle_list(5, "numbered");
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+
+    engine.delete_paragraph(4);
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+}
+
+#[test]
+fn test_page_config_change_relayouts_everything() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(10);
+    engine.recompute_layout_with(&measurer);
+
+    engine.set_font_config(20.0, 1.5, 0.0, 12.0);
+    assert!(engine.recompute_layout_with(&measurer));
+    let pages: Vec<usize> = (0..engine.page_count()).collect();
+    assert_eq!(engine.changed_pages(), pages.as_slice());
+}
+
+#[test]
+fn test_undo_redo_paragraph_edits() {
+    let mut engine = engine_with_paragraphs(2);
+    engine.clear_history();
+
+    engine.set_paragraph(0, "Changed".to_string());
+    engine.set_text_color(0, 0, 3, "#ff0000");
+    assert!(engine.can_undo());
+
+    assert!(engine.undo());
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+    assert!(engine.undo());
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph 0 with some words");
+    assert!(!engine.undo());
+
+    assert!(engine.redo());
+    assert!(engine.redo());
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Changed");
+    assert_eq!(engine.document().paragraphs[0].styles[0].color.as_deref(), Some("#ff0000"));
+    assert!(!engine.can_redo());
+}
+
+#[test]
+fn test_insert_and_delete_text() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.set_paragraph(0, "Çа va bien".to_string());
+    engine.toggle_bold(0, 3, 5);
+    engine.clear_history();
+
+    engine.insert_text(0, 5, "!");
+    engine.delete_text(0, 0, 1);
+    let para = &engine.document().paragraphs[0];
+    assert_eq!(para.text, "а va! bien");
+    assert_eq!((para.styles[0].start, para.styles[0].end), (2, 5));
+
+    engine.undo();
+    engine.undo();
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Çа va bien");
+    assert_eq!(engine.document().paragraphs[0].styles[0].end, 5);
+}
+
+#[test]
+fn test_split_and_join_paragraph() {
+    let mut engine = engine_with_paragraphs(2);
+    engine.toggle_list(0, "numbered");
+    engine.toggle_bold(0, 0, 14);
+
+    assert!(engine.split_paragraph(0, 10));
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph ");
+    assert_eq!(engine.get_paragraph(1).unwrap(), "0 with some words");
+    assert_eq!(engine.get_list_type(1), "numbered");
+    assert_eq!(engine.document().paragraphs[1].styles[0].end, 4);
+
+    engine.undo();
+    assert_eq!(engine.paragraph_count(), 2);
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph 0 with some words");
+
+    engine.split_paragraph(0, 10);
+    assert!(engine.join_with_next(0));
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph 0 with some words");
+    assert_eq!(engine.document().paragraphs[0].styles.len(), 1);
+}
+
+#[test]
+fn test_join_keeps_markers_intact() {
+    let mut engine = engine_with_paragraphs(1);
+    let table_id = engine.create_table(1, 1);
+    engine.insert_table_paragraph(1, &table_id);
+    engine.insert_paragraph(2, String::new());
+
+    assert!(!engine.join_with_next(0));
+    assert!(engine.join_with_next(1));
+    assert_eq!(engine.paragraph_count(), 2);
+    assert!(engine.document().paragraphs[1].is_table());
+
+    engine.split_paragraph(1, 1);
+    assert_eq!(engine.paragraph_count(), 3);
+    assert_eq!(engine.get_paragraph(2).unwrap(), "");
+}
+
+#[test]
+fn test_delete_range_across_paragraphs() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(4);
+    engine.insert_page_break(2);
+    engine.toggle_italic(3, 0, 9);
+    engine.recompute_layout_with(&measurer);
+    engine.clear_history();
+
+    // "Paragraph 0 with some words" .. "Paragraph 2 with some words"
+    assert!(engine.delete_range(0, 12, 3, 10));
+    assert_eq!(engine.paragraph_count(), 2);
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph 0 2 with some words");
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+
+    engine.undo();
+    assert_eq!(engine.paragraph_count(), 5);
+    assert!(engine.document().paragraphs[2].is_page_break());
+    assert_eq!(engine.document().paragraphs[3].styles[0].end, 9);
+}
+
+#[test]
+fn test_delete_range_keeps_uncovered_marker() {
+    let mut engine = engine_with_paragraphs(2);
+    engine.add_image("img", "data:", 100.0, 50.0, 100.0, 50.0);
+    engine.insert_image_paragraph(1, "img");
+
+    assert!(engine.delete_range(0, 5, 1, 0));
+    assert_eq!(engine.paragraph_count(), 3);
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Parag");
+    assert_eq!(engine.document().paragraphs[1].image_id(), Some("img"));
+
+    assert!(engine.delete_range(1, 0, 2, 3));
+    assert_eq!(engine.paragraph_count(), 2);
+    assert_eq!(engine.get_paragraph(1).unwrap(), "agraph 1 with some words");
+}
+
+#[test]
+fn test_toggle_bold_on_partially_bold_range() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.toggle_bold(0, 0, 4);
+    engine.toggle_bold(0, 0, 9);
+    assert!(engine.document().paragraphs[0].range_has_style(0, 9, |s| s.bold));
+
+    engine.toggle_bold(0, 0, 9);
+    assert!(engine.document().paragraphs[0].styles.iter().all(|s| !s.bold));
+}
+
+#[test]
+fn test_hit_test_returns_json_position() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(2);
+    engine.recompute_layout_with(&measurer);
+    let second = engine.display_lines().iter().find(|l| l.para_index == 1).unwrap();
+    let y = 48.0 + second.y_position + 1.0;
+
+    let json: serde_json::Value = serde_json::from_str(&engine.hit_test(0, 48.0, y)).unwrap();
+    assert_eq!(json["position"]["para"], 1);
+    assert_eq!(json["position"]["offset"], 0);
+    assert_eq!(json["affinity"], "downstream");
+    assert_eq!(engine.hit_test(9, 48.0, y), "null");
+}
+
+#[test]
+fn test_render_commands_include_selection_when_enabled() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(2);
+    engine.recompute_layout_with(&measurer);
+    engine.set_selection(0, 2, 1, 4);
+    assert!(!engine.get_render_commands(0).contains("drawSelection"));
+
+    engine.set_render_selection(true);
+    assert!(engine.get_render_commands(0).contains("drawSelection"));
+
+    engine.set_selection(1, 4, 1, 4);
+    let commands = engine.get_render_commands(0);
+    assert!(commands.contains("drawCursor") && !commands.contains("drawSelection"));
+
+    let caret: serde_json::Value = serde_json::from_str(&engine.caret_rect(1, 4)).unwrap();
+    assert_eq!(caret["pageIndex"], 0);
+    let rects: serde_json::Value = serde_json::from_str(&engine.selection_rects(0, 2, 1, 4)).unwrap();
+    assert_eq!(rects.as_array().unwrap().len(), 2);
+
+    engine.clear_selection();
+    assert!(!engine.get_render_commands(0).contains("drawCursor"));
+}
+
+#[test]
+fn test_unchanged_edit_is_not_recorded() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.clear_history();
+
+    engine.set_alignment(0, "");
+    engine.set_paragraph(0, "Paragraph 0 with some words".to_string());
+    assert!(!engine.can_undo());
+}
+
+#[test]
+fn test_group_undoes_as_one_step() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.clear_history();
+
+    engine.begin_group();
+    engine.insert_paragraph(1, "Second".to_string());
+    engine.set_block_type(1, "h2");
+    engine.set_paragraph(0, "First".to_string());
+    engine.end_group();
+
+    assert_eq!(engine.history().undo_len(), 1);
+    engine.undo();
+    assert_eq!(engine.paragraph_count(), 1);
+    assert_eq!(engine.get_paragraph(0).unwrap(), "Paragraph 0 with some words");
+}
+
+#[test]
+fn test_undo_keeps_layout_consistent() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(20);
+    engine.recompute_layout_with(&measurer);
+
+    engine.begin_group();
+    engine.delete_paragraph(2);
+    engine.insert_paragraph(5, "word ".repeat(30));
+    engine.toggle_list(7, "numbered");
+    engine.end_group();
+    engine.recompute_layout_with(&measurer);
+
+    engine.undo();
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+
+    engine.redo();
+    engine.recompute_layout_with(&measurer);
+    assert_layout_matches_full(&engine);
+}
+
+#[test]
+fn test_undo_table_edits() {
+    let mut engine = Engine::new();
+    let table_id = engine.create_table(2, 2);
+    engine.insert_table_paragraph(1, &table_id);
+    engine.set_cell_text(&table_id, 0, 0, "A");
+    assert!(engine.merge_cells(&table_id, 0, 0, 1, 1));
+
+    engine.undo();
+    let table = &engine.document().tables[0];
+    assert!(!table.get_cell(0, 0).unwrap().is_merge_origin());
+    assert_eq!(table.get_cell(0, 0).unwrap().text(), "A");
+
+    engine.undo();
+    engine.undo();
+    assert_eq!(engine.paragraph_count(), 1);
+    engine.undo();
+    assert!(engine.document().tables.is_empty());
+
+    while engine.redo() {}
+    assert_eq!(engine.paragraph_count(), 2);
+    assert!(engine.is_cell_merged(&table_id, 0, 0));
+}
+
+#[test]
+fn test_undo_delete_table_restores_marker() {
+    let mut engine = Engine::new();
+    let table_id = engine.create_table(1, 1);
+    engine.insert_table_paragraph(1, &table_id);
+    engine.delete_table(&table_id);
+    assert!(engine.document().tables.is_empty());
+    assert_eq!(engine.paragraph_count(), 1);
+
+    engine.undo();
+    assert_eq!(engine.document().tables.len(), 1);
+    assert_eq!(engine.document().paragraphs[1].table_id(), Some(table_id.as_str()));
+}
+
+#[test]
+fn test_undo_image_changes() {
+    let mut engine = Engine::new();
+    engine.add_image("img", "data:", 100.0, 50.0, 100.0, 50.0);
+    engine.insert_image_paragraph(1, "img");
+    engine.set_image_wrap_style("img", "square");
+    engine.set_image_position("img", 10.0, 20.0, 0);
+
+    engine.undo();
+    let image = &engine.document().images[0];
+    assert_eq!(image.position_mode, ImagePositionMode::MoveWithText);
+    assert_eq!(image.wrap_style, ImageWrapStyle::Square);
+
+    engine.delete_image("img");
+    assert!(engine.document().images.is_empty());
+    assert_eq!(engine.paragraph_count(), 1);
+
+    engine.undo();
+    assert_eq!(engine.document().images.len(), 1);
+    assert_eq!(engine.document().paragraphs[1].image_id(), Some("img"));
+}
+
+#[test]
+fn test_indent_and_outdent_list_item() {
+    let mut engine = engine_with_paragraphs(2);
+    assert!(!engine.indent_list_item(0));
+    assert!(!engine.outdent_list_item(0));
+
+    engine.toggle_list(0, "bullet");
+    for _ in 0..MAX_LIST_LEVEL {
+        assert!(engine.indent_list_item(0));
+    }
+    assert!(!engine.indent_list_item(0));
+    assert_eq!(engine.document().paragraphs[0].meta.list_level, MAX_LIST_LEVEL);
+
+    // New items inherit the level of the item they were created from
+    engine.insert_paragraph_with_list(1, String::new(), 0);
+    assert_eq!(engine.document().paragraphs[1].meta.list_level, MAX_LIST_LEVEL);
+
+    engine.undo();
+    engine.undo();
+    assert_eq!(engine.document().paragraphs[0].meta.list_level, MAX_LIST_LEVEL - 1);
+
+    assert!(engine.outdent_list_item(0));
+    assert_eq!(engine.document().paragraphs[0].meta.list_level, MAX_LIST_LEVEL - 2);
+
+    // Removing the list resets the level; outdenting at level 0 leaves the list
+    engine.toggle_list(0, "bullet");
+    assert_eq!(engine.document().paragraphs[0].meta.list_level, 0);
+    engine.toggle_list(0, "numbered");
+    assert!(engine.outdent_list_item(0));
+    assert_eq!(engine.get_list_type(0), "none");
+}
+
+#[test]
+fn test_list_numbering_settings() {
+    let mut engine = engine_with_paragraphs(2);
+    engine.toggle_list(0, "numbered");
+    engine.set_number_format(0, "upper-alpha");
+    engine.set_number_template(0, "(%1)");
+    engine.set_list_start(0, Some(3));
+    engine.set_list_continue(0, true);
+
+    let meta = &engine.document().paragraphs[0].meta;
+    assert_eq!(meta.number_format, Some(NumberFormat::UpperAlpha));
+    assert_eq!(meta.number_template.as_deref(), Some("(%1)"));
+    assert_eq!((meta.list_start, meta.list_continue), (Some(3), true));
+
+    // New items share the format but not the restart
+    engine.insert_paragraph_with_list(1, String::new(), 0);
+    let meta = &engine.document().paragraphs[1].meta;
+    assert_eq!(meta.number_format, Some(NumberFormat::UpperAlpha));
+    assert_eq!(meta.number_template.as_deref(), Some("(%1)"));
+    assert_eq!((meta.list_start, meta.list_continue), (None, false));
+
+    engine.set_number_format(0, "default");
+    engine.set_number_template(0, "");
+    engine.set_list_start(0, None);
+    let meta = &engine.document().paragraphs[0].meta;
+    assert_eq!((meta.number_format, meta.number_template.as_deref(), meta.list_start), (None, None, None));
+
+    engine.undo();
+    assert_eq!(engine.document().paragraphs[0].meta.list_start, Some(3));
+}
+
+#[test]
+fn test_toggle_checklist_item_round_trips() {
+    let mut engine = engine_with_paragraphs(2);
+    assert!(!engine.toggle_checklist_item(0));
+
+    engine.toggle_list(0, "checklist");
+    assert_eq!(engine.get_list_type(0), "checklist");
+    assert!(engine.toggle_checklist_item(0));
+    assert!(engine.document().paragraphs[0].meta.checked);
+
+    // Enter after a ticked item starts an unticked one
+    engine.split_paragraph(0, 10);
+    assert_eq!(engine.get_list_type(1), "checklist");
+    assert!(!engine.document().paragraphs[1].meta.checked);
+
+    let json = engine.save_document();
+    let mut loaded = Engine::new();
+    loaded.load_document(&json).unwrap();
+    assert_eq!(loaded.get_list_type(0), "checklist");
+    assert!(loaded.document().paragraphs[0].meta.checked);
+
+    engine.undo();
+    engine.undo();
+    assert!(!engine.document().paragraphs[0].meta.checked);
+}
+
+#[test]
+fn test_set_and_remove_link() {
+    let mut engine = engine_with_paragraphs(1);
+    assert!(!engine.set_link(0, 0, 9, "javascript:alert(1)", None));
+    assert!(!engine.set_link(0, 3, 3, "https://example.com", None));
+    assert_eq!(engine.get_link_at(0, 0), "null");
+
+    engine.toggle_bold(0, 0, 4);
+    assert!(engine.set_link(0, 0, 9, "https://example.com", Some("Example".to_string())));
+    let link: serde_json::Value = serde_json::from_str(&engine.get_link_at(0, 2)).unwrap();
+    assert_eq!(link["url"], "https://example.com");
+    assert_eq!(link["title"], "Example");
+    // Bold is kept on the linked text
+    assert!(engine.document().paragraphs[0].styles.iter().any(|s| s.bold && s.link.is_some()));
+
+    engine.remove_link(0, 0, 4);
+    assert_eq!(engine.get_link_at(0, 2), "null");
+    assert_ne!(engine.get_link_at(0, 5), "null");
+
+    engine.undo();
+    assert_ne!(engine.get_link_at(0, 2), "null");
+}
+
+#[test]
+fn test_set_font_family_size_and_weight() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.set_font_family(0, 0, 9, "Georgia");
+    engine.set_font_size(0, 0, 4, 24.0);
+    engine.set_font_weight(0, 5, 9, 1000);
+
+    let styles = &engine.document().paragraphs[0].styles;
+    assert_eq!(styles.len(), 3);
+    assert_eq!(styles[0].font_size, Some(24.0));
+    assert!(styles.iter().all(|s| s.font_family.as_deref() == Some("Georgia")));
+    assert_eq!(styles[2].font_weight, Some(900));
+
+    engine.set_font_size(0, 0, 4, 0.0);
+    engine.set_font_weight(0, 5, 9, 0);
+    engine.set_font_family(0, 0, 9, "");
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+
+    engine.undo();
+    assert_eq!(engine.document().paragraphs[0].styles.len(), 1);
+    assert_eq!(engine.document().paragraphs[0].styles[0].font_family.as_deref(), Some("Georgia"));
+}
+
+#[test]
+fn test_default_font_family_relayouts() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(3);
+    engine.recompute_layout_with(&measurer);
+
+    engine.set_default_font_family("Georgia");
+    assert!(engine.recompute_layout_with(&measurer));
+    let line = &engine.display_lines()[0];
+    assert_eq!(line.runs[0].font.family.as_deref(), Some("Georgia"));
+}
+
+#[test]
+fn test_toggle_scripts_and_caps() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.toggle_superscript(0, 0, 4);
+    assert_eq!(engine.document().paragraphs[0].styles[0].vertical_align, VerticalAlign::Superscript);
+
+    // Subscript replaces superscript; toggling again returns to the baseline
+    engine.toggle_subscript(0, 0, 4);
+    assert_eq!(engine.document().paragraphs[0].styles[0].vertical_align, VerticalAlign::Subscript);
+    engine.toggle_subscript(0, 0, 4);
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+
+    engine.toggle_small_caps(0, 0, 4);
+    engine.toggle_all_caps(0, 0, 4);
+    assert_eq!(engine.document().paragraphs[0].styles[0].caps, CapsStyle::AllCaps);
+    engine.toggle_all_caps(0, 0, 4);
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+
+    engine.undo();
+    assert_eq!(engine.document().paragraphs[0].styles[0].caps, CapsStyle::AllCaps);
+}
+
+#[test]
+fn test_set_style_restyles_paragraphs_using_it() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = engine_with_paragraphs(2);
+    engine.set_block_type(1, "h2");
+    engine.recompute_layout_with(&measurer);
+
+    assert!(engine.set_style(r#"{"id":"heading2","name":"Heading 2","kind":"paragraph","based_on":"normal","font_scale":2.5,"align":"center"}"#));
+    assert!(engine.recompute_layout_with(&measurer));
+    let line = engine.display_lines().iter().find(|l| l.para_index == 1).unwrap();
+    assert_eq!(line.runs[0].font.size, 16.0 * 2.5);
+    assert!(engine.get_styles().contains("\"font_scale\":2.5"));
+
+    // Undo restores the previous definition
+    engine.undo();
+    assert_eq!(engine.document().resolve_style("heading2").font_scale, Some(1.5));
+
+    // Invalid definitions are rejected
+    assert!(!engine.set_style("not json"));
+    assert!(!engine.set_style(r#"{"id":"normal","name":"Normal","kind":"paragraph","based_on":"heading1"}"#));
+    assert!(!engine.set_style(r#"{"id":"normal","name":"Normal","kind":"character"}"#));
+}
+
+#[test]
+fn test_set_paragraph_and_character_styles() {
+    let mut engine = engine_with_paragraphs(1);
+    assert!(engine.set_style(r#"{"id":"strong","name":"Strong","kind":"character","bold":true}"#));
+
+    assert!(!engine.set_paragraph_style(0, "strong"));
+    assert!(engine.set_paragraph_style(0, "heading3"));
+    assert_eq!(engine.document().paragraphs[0].meta.effective_style_id(), "heading3");
+    // Choosing a block type replaces the paragraph style
+    engine.set_block_type(0, "h1");
+    assert_eq!(engine.document().paragraphs[0].meta.effective_style_id(), "heading1");
+
+    assert!(!engine.set_character_style(0, 0, 4, "heading1"));
+    assert!(engine.set_character_style(0, 0, 4, "strong"));
+    assert_eq!(engine.document().paragraphs[0].styles[0].style_id.as_deref(), Some("strong"));
+    assert!(engine.set_character_style(0, 0, 4, ""));
+    assert!(engine.document().paragraphs[0].styles.is_empty());
+}
+
+#[test]
+fn test_paragraph_indent_and_indent_steps() {
+    let mut engine = engine_with_paragraphs(2);
+    engine.set_paragraph_indent(0, 10.0, -5.0, 24.0, f64::NAN);
+    let indent = engine.document().paragraphs[0].meta.indent;
+    assert_eq!((indent.left, indent.right, indent.first_line, indent.hanging), (10.0, 0.0, 24.0, 0.0));
+
+    // Steps snap to multiples of INDENT_STEP
+    assert!(engine.increase_indent(0));
+    assert_eq!(engine.document().paragraphs[0].meta.indent.left, INDENT_STEP);
+    assert!(engine.increase_indent(0));
+    assert!(engine.decrease_indent(0));
+    assert_eq!(engine.document().paragraphs[0].meta.indent.left, INDENT_STEP);
+    assert!(engine.decrease_indent(0));
+    assert!(!engine.decrease_indent(0));
+    assert_eq!(engine.document().paragraphs[0].meta.indent.left, 0.0);
+
+    // The left indent never eats the whole 224px column
+    while engine.increase_indent(0) {}
+    assert_eq!(engine.document().paragraphs[0].meta.indent.left, 4.0 * INDENT_STEP - 16.0);
+
+    // List items change level instead
+    engine.set_list_type(1, "bullet");
+    assert!(engine.increase_indent(1));
+    assert_eq!(engine.document().paragraphs[1].meta.list_level, 1);
+    assert_eq!(engine.document().paragraphs[1].meta.indent.left, 0.0);
+}
+
+#[test]
+fn test_paragraph_and_line_spacing() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.set_paragraph_spacing(0, Some(-4.0), Some(6.0));
+    let meta = &engine.document().paragraphs[0].meta;
+    assert_eq!((meta.space_before, meta.space_after), (Some(0.0), Some(6.0)));
+    engine.set_paragraph_spacing(0, None, None);
+    assert_eq!(engine.document().paragraphs[0].meta.space_after, None);
+
+    assert!(engine.set_line_spacing(0, "at-least", 20.0));
+    assert_eq!(engine.document().paragraphs[0].meta.line_spacing, Some(LineSpacing::AtLeast(20.0)));
+    assert!(!engine.set_line_spacing(0, "exact", 0.0));
+    assert!(!engine.set_line_spacing(0, "double", 2.0));
+    assert!(engine.set_line_spacing(0, "", 0.0));
+    assert_eq!(engine.document().paragraphs[0].meta.line_spacing, None);
+}
+
+#[test]
+fn test_set_pagination_rules() {
+    let mut engine = engine_with_paragraphs(1);
+    engine.set_pagination(0, Some(true), None, Some(true));
+    engine.set_widow_orphan_lines(0, Some(0), Some(3));
+    let meta = &engine.document().paragraphs[0].meta;
+    assert_eq!((meta.keep_with_next, meta.keep_together, meta.page_break_before), (Some(true), None, Some(true)));
+    assert_eq!((meta.widows, meta.orphans), (Some(1), Some(3)));
+
+    assert!(engine.undo());
+    assert_eq!(engine.document().paragraphs[0].meta.widows, None);
+}
+
+#[test]
+fn test_set_table_width_mode_keeps_laid_out_widths() {
+    let mut engine = engine_with_paragraphs(1);
+    let table_id = engine.create_table(1, 2);
+    engine.insert_table_paragraph(1, &table_id);
+    engine.recompute_layout_with(&FixedWidthMeasurer::default());
+    // 224px page column, three 1px borders
+    assert_eq!(engine.document().tables[0].column_widths, vec![110.5, 110.5]);
+
+    assert!(engine.set_table_width_mode(&table_id, "percentage"));
+    assert_eq!(engine.document().tables[0].column_widths, vec![50.0, 50.0]);
+    assert!(engine.set_table_width_mode(&table_id, "auto"));
+    assert_eq!(engine.document().tables[0].width_mode, TableWidthMode::Auto);
+    assert!(!engine.set_table_width_mode(&table_id, "stretch"));
+    assert!(!engine.set_table_width_mode("missing", "fixed"));
+}
+
+#[test]
+fn test_edit_text_inside_a_cell() {
+    let mut engine = Engine::new();
+    let table_id = engine.create_table(1, 1);
+    engine.insert_table_paragraph(1, &table_id);
+    engine.set_cell_text(&table_id, 0, 0, "Hello");
+    let cell_texts = |engine: &Engine| -> Vec<String> {
+        let cell = engine.document().tables[0].get_cell(0, 0).unwrap();
+        cell.paragraphs.iter().map(|p| p.text.clone()).collect()
+    };
+
+    assert!(engine.insert_cell_text(&table_id, 0, 0, 0, 2, "y\nwor"));
+    assert_eq!(cell_texts(&engine), vec!["Hey", "worllo"]);
+    engine.set_cell_list_type(&table_id, 0, 0, 1, "bullet");
+    assert_eq!(engine.document().tables[0].get_cell(0, 0).unwrap().paragraphs[1].meta.list_type, ListType::Bullet);
+
+    // Deleting across paragraphs joins them
+    assert!(engine.delete_cell_text(&table_id, 0, 0, 0, 2, 1, 3));
+    assert_eq!(cell_texts(&engine), vec!["Hello"]);
+    assert!(!engine.delete_cell_text(&table_id, 0, 0, 0, 2, 0, 2));
+    assert!(!engine.insert_cell_text(&table_id, 0, 0, 3, 0, "x"));
+
+    engine.undo();
+    assert_eq!(cell_texts(&engine), vec!["Hey", "worllo"]);
+    assert_eq!(engine.get_cell_text(&table_id, 0, 0).as_deref(), Some("Hey\nworllo"));
+}
+
+#[test]
+fn test_nested_table_edits_relayout_the_outer_table() {
+    let measurer = FixedWidthMeasurer::default();
+    let mut engine = Engine::new();
+    let outer = engine.create_table(1, 1);
+    engine.insert_table_paragraph(1, &outer);
+    let inner = engine.create_table(1, 1);
+    assert!(engine.insert_cell_table_paragraph(&outer, 0, 0, 1, &inner));
+    // A table cannot end up inside itself
+    assert!(!engine.insert_cell_table_paragraph(&inner, 0, 0, 0, &outer));
+    assert!(!engine.insert_cell_table_paragraph(&outer, 0, 0, 0, &outer));
+    engine.recompute_layout_with(&measurer);
+    let height = |engine: &Engine| engine.display_lines()[1].height;
+    let before = height(&engine);
+
+    engine.set_cell_text(&inner, 0, 0, "a\nb");
+    assert!(engine.recompute_layout_with(&measurer));
+    assert_eq!(height(&engine), before + 24.0);
+    assert_layout_matches_full(&engine);
+}
+
+#[test]
+fn test_cell_formatting() {
+    let mut engine = Engine::new();
+    let table_id = engine.create_table(2, 2);
+    engine.insert_table_paragraph(1, &table_id);
+
+    assert!(engine.set_cell_vertical_align(&table_id, 0, 1, "middle"));
+    assert!(!engine.set_cell_vertical_align(&table_id, 0, 1, "center"));
+    engine.set_cell_padding(&table_id, 0, 1, 2.0, 6.0, -1.0, 6.0);
+    engine.set_table_cell_padding(&table_id, 5.0, 5.0, 5.0, 5.0);
+    assert!(engine.set_cell_border(&table_id, 1, 0, "all", 2.0, "#336699", "double"));
+    assert!(engine.set_cell_border(&table_id, 1, 0, "left", 0.0, "", ""));
+    assert!(!engine.set_cell_border(&table_id, 1, 0, "middle", 1.0, "#000", "solid"));
+    assert!(!engine.set_cell_border(&table_id, 1, 0, "top", 1.0, "#000", "wavy"));
+
+    let table = &engine.document().tables[0];
+    let cell = table.get_cell(0, 1).unwrap();
+    assert_eq!(cell.vertical_align, CellVerticalAlign::Middle);
+    assert_eq!(cell.padding, Some(CellPadding { top: 2.0, right: 6.0, bottom: 0.0, left: 6.0 }));
+    assert_eq!(table.padding_of(table.get_cell(1, 1).unwrap()), CellPadding::uniform(5.0));
+    let borders = &table.get_cell(1, 0).unwrap().borders;
+    assert_eq!(borders.top.as_ref().map(|b| (b.width, b.style)), Some((2.0, BorderStyle::Double)));
+    assert_eq!(borders.left, None);
+
+    engine.clear_cell_padding(&table_id, 0, 1);
+    assert_eq!(engine.document().tables[0].get_cell(0, 1).unwrap().padding, None);
+    engine.undo();
+    assert!(engine.document().tables[0].get_cell(0, 1).unwrap().padding.is_some());
+}
+
+#[test]
+fn test_convert_paragraphs_to_table_and_back() {
+    let mut engine = Engine::new();
+    engine.set_paragraph(0, "Intro".to_string());
+    engine.insert_paragraph(1, "b,2".to_string());
+    engine.insert_paragraph(2, "a,10".to_string());
+    engine.insert_paragraph(3, "End".to_string());
+
+    let table_id = engine.convert_paragraphs_to_table(1, 2, ",").unwrap();
+    assert_eq!(engine.document().paragraphs.len(), 3);
+    assert_eq!(engine.document().paragraphs[1].table_id(), Some(table_id.as_str()));
+    assert!(engine.sort_table(&table_id, 1, false, true));
+    assert_eq!(engine.get_cell_text(&table_id, 0, 0).as_deref(), Some("a"));
+    assert!(engine.move_table_column(&table_id, 1, 0));
+    assert!(!engine.move_table_row(&table_id, 0, 5));
+    assert_eq!(engine.convert_paragraphs_to_table(2, 5, ","), None);
+
+    assert!(engine.convert_table_to_paragraphs(&table_id, ";"));
+    let texts: Vec<&str> = engine.document().paragraphs.iter().map(|p| p.text.as_str()).collect();
+    assert_eq!(texts, vec!["Intro", "10;a", "2;b", "End"]);
+    assert!(engine.document().tables.is_empty());
+
+    // Each conversion is one undo step
+    engine.undo();
+    assert_eq!(engine.document().tables.len(), 1);
+    assert_eq!(engine.document().paragraphs.len(), 3);
+    engine.undo();
+    engine.undo();
+    engine.undo();
+    assert!(engine.document().tables.is_empty());
+    let texts: Vec<&str> = engine.document().paragraphs.iter().map(|p| p.text.as_str()).collect();
+    assert_eq!(texts, vec!["Intro", "b,2", "a,10", "End"]);
+}
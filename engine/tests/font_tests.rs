@@ -0,0 +1,168 @@
+//! Tests for the font module
+//!
+//! Shaping tests use the tiny fonts in `tests/fonts`, generated by
+//! `make_test_fonts.py`: fixed advances, an "AV" kerning pair and an "fi"
+//! ligature, in a regular and a bold face.
+
+use editor_engine::*;
+
+const REGULAR: &[u8] = include_bytes!("fonts/EngineTestSans-Regular.ttf");
+const BOLD: &[u8] = include_bytes!("fonts/EngineTestSans-Bold.ttf");
+
+fn test_registry() -> FontRegistry {
+    let mut registry = FontRegistry::new();
+    registry.register("Engine Test Sans", REGULAR.to_vec()).unwrap();
+    registry.register("Engine Test Sans", BOLD.to_vec()).unwrap();
+    registry
+}
+
+#[test]
+fn test_register_rejects_invalid_data() {
+    let mut registry = FontRegistry::new();
+    let result = registry.register("Broken", vec![0, 1, 2, 3]);
+    assert_eq!(result, Err(FontError::InvalidData));
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn test_register_rejects_empty_name() {
+    let mut registry = FontRegistry::new();
+    assert_eq!(registry.register("  ", Vec::new()), Err(FontError::EmptyName));
+}
+
+#[test]
+fn test_shaping_measurer_falls_back_without_fonts() {
+    let registry = FontRegistry::new();
+    let measurer = ShapingMeasurer::new(&registry);
+    let font = FontSpec::new(10.0);
+    assert_eq!(measurer.measure("abcd", &font), FixedWidthMeasurer::default().measure("abcd", &font));
+    assert!(measurer.shape("abcd", &font).is_none());
+}
+
+#[test]
+fn test_register_reads_weight_from_font() {
+    let registry = test_registry();
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.families(), vec!["Engine Test Sans"]);
+
+    let regular = registry.find(Some("Engine Test Sans"), FONT_WEIGHT_NORMAL, false).unwrap();
+    let bold = registry.find(Some("Engine Test Sans"), FONT_WEIGHT_BOLD, false).unwrap();
+    assert_eq!(registry.fonts()[regular].weight, 400);
+    assert_eq!(registry.fonts()[bold].weight, 700);
+}
+
+#[test]
+fn test_find_unknown_family_uses_first_family() {
+    let registry = test_registry();
+    assert!(registry.find(Some("Nope"), FONT_WEIGHT_NORMAL, false).is_some());
+}
+
+#[test]
+fn test_shaping_width_scales_with_size() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let small = measurer.measure("Hello", &FontSpec::new(10.0));
+    let large = measurer.measure("Hello", &FontSpec::new(20.0));
+    assert!(small > 0.0);
+    assert!((large - small * 2.0).abs() < 0.001);
+}
+
+#[test]
+fn test_shaping_bold_is_wider() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let regular = measurer.measure("Hello", &FontSpec::new(16.0));
+    let bold = measurer.measure("Hello", &FontSpec::with_style(16.0, true, false));
+    assert!(bold > regular);
+}
+
+#[test]
+fn test_shaping_applies_kerning() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let font = FontSpec::new(100.0);
+    let pair = measurer.measure("AV", &font);
+    let separate = measurer.measure("A", &font) + measurer.measure("V", &font);
+    assert!(pair < separate);
+}
+
+#[test]
+fn test_shaping_applies_ligatures() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let font = FontSpec::new(100.0);
+    let shaped = measurer.shape("fix", &font).unwrap();
+    assert_eq!(shaped.glyphs.len(), 2);
+    assert_eq!(shaped.glyphs[1].cluster, 2);
+    let pair = measurer.measure("fi", &font);
+    let separate = measurer.measure("f", &font) + measurer.measure("i", &font);
+    assert!(pair < separate);
+}
+
+#[test]
+fn test_shape_reports_glyph_clusters() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let shaped = measurer.shape("héllo", &FontSpec::new(16.0)).unwrap();
+    assert_eq!(shaped.glyphs.len(), 5);
+    assert_eq!(shaped.glyphs[2].cluster, 3); // 'l' after the 2-byte 'é'
+    let sum: f64 = shaped.glyphs.iter().map(|g| g.x_advance).sum();
+    assert!((sum - shaped.width).abs() < 0.001);
+}
+
+#[test]
+fn test_engine_layout_with_registered_fonts() {
+    let mut engine = Engine::new();
+    engine.register_font("Engine Test Sans", REGULAR).unwrap();
+    engine.set_paragraph(0, "Hello World".to_string());
+
+    assert!(engine.recompute_layout_with_fonts());
+    assert_eq!(engine.display_lines().len(), 1);
+    assert!(!engine.recompute_layout_with_fonts());
+}
+
+#[test]
+fn test_engine_shapes_with_configured_family() {
+    // "Wide" is registered first, but the document's default family is the
+    // regular test font
+    let mut engine = Engine::new();
+    engine.register_font("Wide", BOLD).unwrap();
+    engine.register_font("Engine Test Sans", REGULAR).unwrap();
+    engine.set_default_font_family("Engine Test Sans");
+    engine.set_paragraph(0, "Hello".to_string());
+    assert!(engine.recompute_layout_with_fonts());
+
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let font = FontSpec::new(LayoutConfig::default().font_size);
+    let expected = measurer.prefix_widths("Hello", &font);
+    assert_eq!(engine.display_lines()[0].char_positions, expected);
+}
+
+#[test]
+fn test_text_without_family_uses_measurer_family() {
+    let mut registry = FontRegistry::new();
+    registry.register("Wide", BOLD.to_vec()).unwrap();
+    registry.register("Engine Test Sans", REGULAR.to_vec()).unwrap();
+    let font = FontSpec::new(16.0);
+
+    let default = ShapingMeasurer::new(&registry).measure("Hello", &font);
+    let configured = ShapingMeasurer::new(&registry)
+        .with_family("Engine Test Sans")
+        .measure("Hello", &font);
+    let regular = ShapingMeasurer::new(&registry).measure("Hello", &font.clone().with_family("Engine Test Sans"));
+    assert!(default > configured);
+    assert_eq!(configured, regular);
+}
+
+#[test]
+fn test_metrics_come_from_font() {
+    let registry = test_registry();
+    let measurer = ShapingMeasurer::new(&registry);
+    let metrics = measurer.metrics(&FontSpec::new(20.0));
+
+    // The test font has a tall ascender: more than the 80% approximation
+    assert!(metrics.ascent > 16.0);
+    assert!(metrics.descent > 0.0);
+    assert_eq!(measurer.metrics(&FontSpec::new(40.0)).ascent, metrics.ascent * 2.0);
+}
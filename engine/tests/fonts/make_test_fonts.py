@@ -0,0 +1,174 @@
+#!/usr/bin/env python3
+"""Generate the tiny TrueType fonts used by the font tests.
+
+The fonts have no outlines, only the tables shaping and measuring read:
+advance widths for printable ASCII and 'é', a legacy kern table with an
+"AV" pair, and a GSUB 'liga' lookup turning "fi" into one glyph. The bold
+face has a 700 weight and wider advances.
+
+Run from this directory: python3 make_test_fonts.py
+"""
+
+import struct
+
+UNITS_PER_EM = 1000
+ASCENDER = 900
+DESCENDER = -250
+KERN_AV = -120
+FI_ADVANCE = 480
+
+CHARS = [chr(c) for c in range(0x20, 0x7F)] + ["é"]
+NARROW = set("fijltI.,:;'!|")
+WIDE = set("mwMW")
+
+
+def advance(char):
+    if char == " ":
+        return 300
+    if char in NARROW:
+        return 280
+    if char in WIDE:
+        return 850
+    return 560
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def cmap_table(glyph_of):
+    codes = sorted(glyph_of)
+    segments = [(c, c, glyph_of[c]) for c in codes] + [(0xFFFF, 0xFFFF, 0)]
+    seg_count = len(segments)
+    search_range = 2 * 2 ** (seg_count.bit_length() - 1)
+    entry_selector = seg_count.bit_length() - 1
+    ends = b"".join(struct.pack(">H", end) for _, end, _ in segments)
+    starts = b"".join(struct.pack(">H", start) for start, _, _ in segments)
+    deltas = b"".join(struct.pack(">H", (glyph - start) & 0xFFFF if glyph else 1) for start, _, glyph in segments)
+    offsets = b"\0\0" * seg_count
+    body = (
+        struct.pack(">HHHH", seg_count * 2, search_range, entry_selector, seg_count * 2 - search_range)
+        + ends
+        + b"\0\0"
+        + starts
+        + deltas
+        + offsets
+    )
+    subtable = struct.pack(">HHH", 4, 6 + len(body), 0) + body
+    return struct.pack(">HHHHIHHI", 0, 2, 0, 3, 20, 3, 1, 20) + subtable
+
+
+def name_table(family, style):
+    names = [(1, family), (2, style), (4, "%s %s" % (family, style)), (6, (family + "-" + style).replace(" ", ""))]
+    strings = b""
+    records = b""
+    for name_id, text in names:
+        encoded = text.encode("utf-16-be")
+        records += struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + strings
+
+
+def os2_table(weight, bold, avg_width):
+    fs_selection = 0x20 if bold else 0x40
+    return struct.pack(
+        ">HhHHH8hhhh10s4I4sHHHhhhHHIIhhHHH",
+        4, avg_width, weight, 5, 0,
+        650, 600, 0, 75, 650, 600, 0, 350,
+        50, 300, 0, b"\0" * 10,
+        1, 0, 0, 0, b"TEST",
+        fs_selection, 0x20, 0xE9,
+        ASCENDER, DESCENDER, 0, ASCENDER, -DESCENDER,
+        1, 0,
+        500, 700, 0, 0x20, 2,
+    )
+
+
+def kern_table(left, right):
+    pairs = struct.pack(">HHh", left, right, KERN_AV)
+    subtable = struct.pack(">HHHHHHH", 0, 14 + len(pairs), 0x0001, 1, 6, 0, 0) + pairs
+    return struct.pack(">HH", 0, 1) + subtable
+
+
+def gsub_table(f, i, fi):
+    # Ligature set for 'f': one ligature "f i" -> fi
+    ligature = struct.pack(">HHH", fi, 2, i)
+    ligature_set = struct.pack(">HH", 1, 4) + ligature
+    coverage = struct.pack(">HHH", 1, 1, f)
+    subst = struct.pack(">HHHH", 1, 8, 1, 8 + len(coverage)) + coverage + ligature_set
+    lookup = struct.pack(">HHHH", 4, 0, 1, 8) + subst
+    lookup_list = struct.pack(">HH", 1, 4) + lookup
+    feature = struct.pack(">HHH", 0, 1, 0)
+    feature_list = struct.pack(">H4sH", 1, b"liga", 8) + feature
+    lang_sys = struct.pack(">HHHH", 0, 0xFFFF, 1, 0)
+    script = struct.pack(">HH", 4, 0) + lang_sys
+    script_list = struct.pack(">H4sH4sH", 2, b"DFLT", 14, b"latn", 14) + script
+    header_len = 10
+    return (
+        struct.pack(
+            ">HHHHH",
+            1,
+            0,
+            header_len,
+            header_len + len(script_list),
+            header_len + len(script_list) + len(feature_list),
+        )
+        + script_list
+        + feature_list
+        + lookup_list
+    )
+
+
+def build(family, style, weight, bold, scale):
+    advances = [500] + [round(advance(c) * scale) for c in CHARS] + [round(FI_ADVANCE * scale)]
+    glyph_of = {ord(c): index + 1 for index, c in enumerate(CHARS)}
+    fi = len(advances) - 1
+    num_glyphs = len(advances)
+
+    tables = {
+        "cmap": cmap_table(glyph_of),
+        "glyf": b"\0\0\0\0",
+        "loca": b"\0\0" * (num_glyphs + 1),
+        "hmtx": b"".join(struct.pack(">Hh", width, 0) for width in advances),
+        "hhea": struct.pack(
+            ">IhhhHhhhhhhhhhhhH", 0x00010000, ASCENDER, DESCENDER, 0, max(advances),
+            0, 0, max(advances), 1, 0, 0, 0, 0, 0, 0, 0, num_glyphs,
+        ),
+        "maxp": struct.pack(">IHHHHHHHHHHHHHH", 0x00010000, num_glyphs, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0),
+        "OS/2": os2_table(weight, bold, round(sum(advances) / num_glyphs)),
+        "name": name_table(family, style),
+        "post": struct.pack(">IIhhIIIII", 0x00030000, 0, -100, 50, 0, 0, 0, 0, 0),
+        "kern": kern_table(glyph_of[ord("A")], glyph_of[ord("V")]),
+        "GSUB": gsub_table(glyph_of[ord("f")], glyph_of[ord("i")], fi),
+        "head": struct.pack(
+            ">IIIIHHqqhhhhHHhhh", 0x00010000, 0x00010000, 0, 0x5F0F3CF5, 0x000B, UNITS_PER_EM,
+            0, 0, 0, DESCENDER, max(advances), ASCENDER, 1 if bold else 0, 8, 2, 0, 0,
+        ),
+    }
+
+    tags = sorted(tables)
+    count = len(tags)
+    entry_selector = count.bit_length() - 1
+    search_range = 16 * 2 ** entry_selector
+    directory = struct.pack(">IHHHH", 0x00010000, count, search_range, entry_selector, count * 16 - search_range)
+    offset = 12 + 16 * count
+    records = b""
+    data = b""
+    for tag in tags:
+        table = tables[tag]
+        records += struct.pack(">4sIII", tag.encode(), checksum(table), offset + len(data), len(table))
+        data += table + b"\0" * (-len(table) % 4)
+    font = bytearray(directory + records + data)
+
+    head_offset = offset + sum(len(tables[t]) + (-len(tables[t]) % 4) for t in tags[: tags.index("head")])
+    adjustment = (0xB1B0AFBA - checksum(bytes(font))) & 0xFFFFFFFF
+    font[head_offset + 8 : head_offset + 12] = struct.pack(">I", adjustment)
+    return bytes(font)
+
+
+if __name__ == "__main__":
+    with open("EngineTestSans-Regular.ttf", "wb") as f:
+        f.write(build("Engine Test Sans", "Regular", 400, False, 1.0))
+    with open("EngineTestSans-Bold.ttf", "wb") as f:
+        f.write(build("Engine Test Sans", "Bold", 700, True, 1.1))
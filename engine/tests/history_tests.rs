@@ -0,0 +1,143 @@
+//! Tests for the history module
+
+use editor_engine::*;
+
+fn set_text(index: usize, before: &str, after: &str) -> Operation {
+    Operation::SetParagraph {
+        index,
+        before: Paragraph::new(before.to_string()),
+        after: Paragraph::new(after.to_string()),
+    }
+}
+
+#[test]
+fn test_operation_invert_roundtrip() {
+    let op = Operation::InsertParagraph {
+        index: 1,
+        paragraph: Paragraph::new("New".to_string()),
+    };
+    assert!(matches!(op.invert(), Operation::RemoveParagraph { index: 1, .. }));
+    assert_eq!(op.invert().invert(), op);
+}
+
+#[test]
+fn test_operation_apply_and_revert() {
+    let mut document = Document::new();
+    let op = Operation::InsertParagraph {
+        index: 1,
+        paragraph: Paragraph::new("Second".to_string()),
+    };
+
+    assert!(op.apply(&mut document));
+    assert_eq!(document.paragraphs.len(), 2);
+    assert_eq!(document.paragraphs[1].text, "Second");
+
+    assert!(op.invert().apply(&mut document));
+    assert_eq!(document.paragraphs.len(), 1);
+}
+
+#[test]
+fn test_operation_apply_out_of_range_fails() {
+    let mut document = Document::new();
+    assert!(!set_text(5, "a", "b").apply(&mut document));
+    assert!(!Operation::RemoveParagraph {
+        index: 3,
+        paragraph: Paragraph::new(String::new()),
+    }
+    .apply(&mut document));
+}
+
+#[test]
+fn test_transaction_merges_consecutive_paragraph_edits() {
+    let mut transaction = Transaction::new();
+    transaction.push(set_text(0, "", "H"));
+    transaction.push(set_text(0, "H", "He"));
+    transaction.push(set_text(0, "He", "Hey"));
+
+    assert_eq!(transaction.operations, vec![set_text(0, "", "Hey")]);
+}
+
+#[test]
+fn test_transaction_keeps_edits_to_different_paragraphs() {
+    let mut transaction = Transaction::new();
+    transaction.push(set_text(0, "", "a"));
+    transaction.push(set_text(1, "", "b"));
+    transaction.push(set_text(0, "a", "ab"));
+
+    assert_eq!(transaction.operations.len(), 3);
+}
+
+#[test]
+fn test_transaction_invert_reverses_order() {
+    let mut transaction = Transaction::new();
+    transaction.push(Operation::InsertParagraph {
+        index: 1,
+        paragraph: Paragraph::new("x".to_string()),
+    });
+    transaction.push(set_text(1, "x", "y"));
+
+    let inverse = transaction.invert();
+    assert_eq!(inverse.operations[0], set_text(1, "y", "x"));
+    assert!(matches!(inverse.operations[1], Operation::RemoveParagraph { index: 1, .. }));
+}
+
+#[test]
+fn test_history_undo_redo_stacks() {
+    let mut history = History::new();
+    history.record(set_text(0, "", "a"));
+    history.record(set_text(0, "a", "b"));
+    assert_eq!(history.undo_len(), 2);
+
+    let undone = history.undo().unwrap();
+    assert_eq!(undone.operations, vec![set_text(0, "a", "b")]);
+    assert!(history.can_redo());
+
+    let redone = history.redo().unwrap();
+    assert_eq!(redone, undone);
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn test_history_record_clears_redo() {
+    let mut history = History::new();
+    history.record(set_text(0, "", "a"));
+    history.undo();
+    history.record(set_text(0, "", "b"));
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn test_history_nested_groups_form_one_transaction() {
+    let mut history = History::new();
+    history.begin_group();
+    history.record(set_text(0, "", "a"));
+    history.begin_group();
+    history.record(set_text(1, "", "b"));
+    history.end_group();
+    assert!(history.in_group());
+    history.end_group();
+
+    assert_eq!(history.undo_len(), 1);
+    assert_eq!(history.undo().unwrap().operations.len(), 2);
+}
+
+#[test]
+fn test_history_empty_group_is_not_recorded() {
+    let mut history = History::new();
+    history.begin_group();
+    history.end_group();
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn test_history_limit_drops_oldest() {
+    let mut history = History::with_limit(2);
+    history.record(set_text(0, "", "a"));
+    history.record(set_text(1, "", "b"));
+    history.record(set_text(2, "", "c"));
+
+    assert_eq!(history.undo_len(), 2);
+    assert_eq!(history.undo().unwrap().operations, vec![set_text(2, "", "c")]);
+    assert_eq!(history.undo().unwrap().operations, vec![set_text(1, "", "b")]);
+    assert!(history.undo().is_none());
+}
@@ -68,6 +68,11 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             column_index: 0,
             x_position: 96.0,
             y_position: 0.0,
+            height: 24.0,
+            ascent: 12.8,
+            descent: 3.2,
+            char_positions: Vec::new(),
+            runs: Vec::new(),
             is_page_break: false,
             is_image: false,
             image_id: None,
@@ -76,7 +81,13 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             is_last_line: false,
             block_type: BlockType::Paragraph,
             list_type: ListType::None,
+            list_level: 0,
+            list_marker: None,
             float_reduction: None,
+            is_table: false,
+            table_id: None,
+            table_layout: None,
+            table_fragment: None,
         },
         // Paragraph 0, line 1: "World"
         DisplayLine {
@@ -88,6 +99,11 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             column_index: 0,
             x_position: 96.0,
             y_position: 24.0,
+            height: 24.0,
+            ascent: 12.8,
+            descent: 3.2,
+            char_positions: Vec::new(),
+            runs: Vec::new(),
             is_page_break: false,
             is_image: false,
             image_id: None,
@@ -96,7 +112,13 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             is_last_line: true,
             block_type: BlockType::Paragraph,
             list_type: ListType::None,
+            list_level: 0,
+            list_marker: None,
             float_reduction: None,
+            is_table: false,
+            table_id: None,
+            table_layout: None,
+            table_fragment: None,
         },
         // Paragraph 1, line 0: "Second paragraph"
         DisplayLine {
@@ -108,6 +130,11 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             column_index: 0,
             x_position: 96.0,
             y_position: 48.0,
+            height: 24.0,
+            ascent: 12.8,
+            descent: 3.2,
+            char_positions: Vec::new(),
+            runs: Vec::new(),
             is_page_break: false,
             is_image: false,
             image_id: None,
@@ -116,7 +143,13 @@ fn create_test_display_lines() -> Vec<DisplayLine> {
             is_last_line: true,
             block_type: BlockType::Paragraph,
             list_type: ListType::None,
+            list_level: 0,
+            list_marker: None,
             float_reduction: None,
+            is_table: false,
+            table_id: None,
+            table_layout: None,
+            table_fragment: None,
         },
     ]
 }
@@ -192,3 +225,1035 @@ fn test_display_position_empty_lines() {
     assert_eq!(pos.line, 0);
     assert_eq!(pos.col, 0);
 }
+
+fn single_paragraph_document(text: &str) -> Document {
+    let mut document = Document::new();
+    document.paragraphs[0].text = text.to_string();
+    document
+}
+
+#[test]
+fn test_compute_layout_native_single_line() {
+    let document = single_paragraph_document("Hello World");
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].text, "Hello World");
+    assert!(lines[0].is_last_line);
+    assert_eq!(lines[0].x_position, config.margin_left);
+}
+
+#[test]
+fn test_compute_layout_native_wraps_at_word_boundary() {
+    // 8px per character at 16px font, 80px column => 10 characters per line
+    let document = single_paragraph_document("aaaa bbbb cccc");
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "aaaa bbbb ");
+    assert_eq!(lines[1].start_offset, 10);
+    assert_eq!(lines[1].text, "cccc");
+}
+
+#[test]
+fn test_compute_layout_native_multibyte_text() {
+    // Breaking inside multi-byte text must not panic
+    let document = single_paragraph_document("éééééééééééééééééééé");
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert!(lines.len() > 1);
+    let joined: String = lines.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(joined, "éééééééééééééééééééé");
+}
+
+/// Measurer that counts how often layout asks it for widths
+#[derive(Default)]
+struct CountingMeasurer {
+    measure_calls: std::cell::Cell<usize>,
+    prefix_calls: std::cell::Cell<usize>,
+}
+
+impl TextMeasurer for CountingMeasurer {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        self.measure_calls.set(self.measure_calls.get() + 1);
+        FixedWidthMeasurer::default().measure(text, font)
+    }
+
+    fn prefix_widths(&self, text: &str, font: &FontSpec) -> Vec<f64> {
+        self.prefix_calls.set(self.prefix_calls.get() + 1);
+        FixedWidthMeasurer::default().prefix_widths(text, font)
+    }
+}
+
+#[test]
+fn test_single_line_paragraph_is_measured_once() {
+    let document = single_paragraph_document("The quick brown fox jumps over the lazy dog");
+    let measurer = CountingMeasurer::default();
+    let lines = compute_layout(&document, &LayoutConfig::default(), &measurer);
+
+    assert_eq!(lines.len(), 1);
+    assert_eq!(measurer.prefix_calls.get(), 1);
+    assert_eq!(measurer.measure_calls.get(), 0);
+}
+
+#[test]
+fn test_wrapped_paragraph_is_measured_once() {
+    let document = single_paragraph_document("aaaa bbbb cccc dddd eeee ffff");
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        ..Default::default()
+    };
+    let measurer = CountingMeasurer::default();
+    let lines = compute_layout(&document, &config, &measurer);
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(measurer.prefix_calls.get(), 1);
+    assert_eq!(measurer.measure_calls.get(), 0);
+}
+
+#[test]
+fn test_display_line_offsets_are_chars() {
+    let document = single_paragraph_document("éééééééééééééééééééé");
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines[0].start_offset, 0);
+    assert_eq!(lines[0].end_offset, lines[0].text.chars().count());
+    assert_eq!(lines[1].start_offset, lines[0].end_offset);
+    assert_eq!(lines.last().unwrap().end_offset, 20);
+}
+
+#[test]
+fn test_cell_paragraphs_are_laid_out_like_body_text() {
+    // 8px per character; a 56px text area inside borders and padding
+    let mut table = DocumentTable::new("t".to_string(), 1, 1, 66.0);
+    let cell = table.get_cell_mut(0, 0).unwrap();
+    cell.set_text("one two\nthree four\nx");
+    cell.paragraphs[2].meta.list_type = ListType::Bullet;
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+
+    let lines = &layout.cell_lines[0][0];
+    let texts: Vec<&str> = lines.iter().map(|dl| dl.text.as_str()).collect();
+    assert_eq!(texts, vec!["one two", "three ", "four", "x"]);
+    let paras: Vec<usize> = lines.iter().map(|dl| dl.para_index).collect();
+    assert_eq!(paras, vec![0, 1, 1, 2]);
+    assert_eq!(lines[3].list_marker.as_deref(), Some("•"));
+
+    // Cell paragraphs have no spacing of their own, but can be given some
+    let y: Vec<f64> = lines.iter().map(|dl| dl.y_position).collect();
+    assert_eq!(y, vec![0.0, 24.0, 48.0, 72.0]);
+    assert_eq!(layout.row_heights[0], 96.0 + 8.0);
+
+    table.get_cell_mut(0, 0).unwrap().paragraphs[1].meta.space_before = Some(10.0);
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+    assert_eq!(layout.cell_lines[0][0][1].y_position, 34.0);
+}
+
+#[test]
+fn test_cell_vertical_align_and_padding() {
+    let mut table = DocumentTable::new("t".to_string(), 1, 3, 303.0);
+    table.get_cell_mut(0, 0).unwrap().set_text("a\nb\nc");
+    let middle = table.get_cell_mut(0, 1).unwrap();
+    middle.set_text("x");
+    middle.vertical_align = CellVerticalAlign::Middle;
+    middle.padding = Some(CellPadding { top: 10.0, right: 4.0, bottom: 10.0, left: 20.0 });
+    let bottom = table.get_cell_mut(0, 2).unwrap();
+    bottom.set_text("y");
+    bottom.vertical_align = CellVerticalAlign::Bottom;
+    let measurer = FixedWidthMeasurer::default();
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &measurer);
+
+    // Three lines and the default padding set the row height; the other
+    // cells move their line into the room left inside their padding
+    assert_eq!(layout.row_heights[0], 72.0 + 8.0);
+    assert_eq!(layout.cell_lines[0][0][0].y_position, 0.0);
+    assert_eq!(layout.cell_lines[0][1][0].y_position, (80.0 - 20.0 - 24.0) / 2.0);
+    assert_eq!(layout.cell_lines[0][2][0].y_position, 80.0 - 8.0 - 24.0);
+
+    // The table's padding applies to cells without their own
+    table.cell_padding = CellPadding::uniform(30.0);
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &measurer);
+    assert_eq!(layout.row_heights[0], 72.0 + 60.0);
+    assert_eq!(layout.cell_lines[0][1][0].y_position, (132.0 - 20.0 - 24.0) / 2.0);
+}
+
+#[test]
+fn test_empty_row_height_uses_cell_padding() {
+    let mut table = DocumentTable::new("t".to_string(), 2, 2, 200.0);
+    table.cell_padding = CellPadding::uniform(30.0);
+    for col in 0..2 {
+        table.get_cell_mut(0, col).unwrap().padding = Some(CellPadding::uniform(2.0));
+    }
+    table.get_cell_mut(1, 0).unwrap().padding = Some(CellPadding::uniform(2.0));
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+
+    // Each empty cell is one line plus its own padding; the tallest sets the row
+    assert_eq!(layout.row_heights[0], 24.0 + 4.0);
+    assert_eq!(layout.row_heights[1], 24.0 + 60.0);
+}
+
+#[test]
+fn test_cell_with_image_and_nested_table() {
+    let mut document = Document::new();
+    document.images.push(DocumentImage::new("img".to_string(), "data:".to_string(), 40.0, 50.0));
+    let mut nested = DocumentTable::new("inner".to_string(), 2, 1, 100.0);
+    nested.get_cell_mut(0, 0).unwrap().set_text("a");
+    document.tables.push(nested);
+
+    let mut table = DocumentTable::new("outer".to_string(), 1, 1, 300.0);
+    table.get_cell_mut(0, 0).unwrap().paragraphs = vec![
+        Paragraph::new("\u{FFFC}img".to_string()),
+        Paragraph::new("\u{FFFB}inner".to_string()),
+    ];
+    let config = LayoutConfig::default();
+    let layout = compute_table_layout(&document, &table, &config, &FixedWidthMeasurer::default());
+
+    let lines = &layout.cell_lines[0][0];
+    assert!(lines[0].is_image);
+    // The image takes three whole lines
+    assert_eq!(lines[1].y_position, 72.0);
+    let inner = lines[1].table_layout.as_ref().unwrap();
+    assert_eq!(inner.cell_lines[0][0][0].text, "a");
+    assert_eq!(inner.total_height, 2.0 * 32.0 + 3.0);
+    assert_eq!(layout.row_heights[0], 72.0 + inner.total_height + 8.0);
+}
+
+#[test]
+fn test_table_nested_in_itself_is_not_laid_out_forever() {
+    let mut document = Document::new();
+    let mut table = DocumentTable::new("t".to_string(), 1, 1, 300.0);
+    table.get_cell_mut(0, 0).unwrap().paragraphs = vec![Paragraph::new("\u{FFFB}t".to_string())];
+    document.tables.push(table.clone());
+    let layout = compute_table_layout(&document, &table, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+    assert!(layout.total_height > 0.0);
+
+    // Past the nesting limit the marker is an empty line, not literal text
+    let mut lines = &layout.cell_lines[0][0];
+    while let Some(inner) = lines[0].table_layout.as_ref() {
+        lines = &inner.cell_lines[0][0];
+    }
+    assert_eq!(lines.len(), 1);
+    assert!(!lines[0].is_table);
+    assert_eq!(lines[0].text, "");
+    assert_eq!(lines[0].height, 0.0);
+}
+
+#[test]
+fn test_compute_table_layout_native() {
+    let table = DocumentTable::new("t".to_string(), 2, 2, 624.0);
+    let config = LayoutConfig::default();
+    let layout = compute_table_layout(&Document::new(), &table, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(layout.row_heights.len(), 2);
+    assert_eq!(layout.column_widths.len(), 2);
+    assert!(layout.total_height > 0.0);
+}
+
+fn two_column_table(mode: TableWidthMode, widths: [f64; 2], texts: [&str; 2]) -> DocumentTable {
+    let mut table = DocumentTable::new("t".to_string(), 1, 2, 0.0);
+    table.width_mode = mode;
+    table.column_widths = widths.to_vec();
+    for (col, text) in texts.iter().enumerate() {
+        table.get_cell_mut(0, col).unwrap().set_text(text);
+    }
+    table
+}
+
+fn config_with_column(width: f64) -> LayoutConfig {
+    LayoutConfig {
+        page_width: width + 192.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_table_fixed_and_percentage_column_widths() {
+    let measurer = FixedWidthMeasurer::default();
+    let fixed = two_column_table(TableWidthMode::Fixed, [100.0, 200.0], ["", ""]);
+    let layout = compute_table_layout(&Document::new(), &fixed, &LayoutConfig::default(), &measurer);
+    assert_eq!(layout.column_widths, vec![100.0, 200.0]);
+    assert_eq!(layout.total_width, 303.0);
+
+    // A narrower page column shrinks the columns in proportion
+    let layout = compute_table_layout(&Document::new(), &fixed, &config_with_column(153.0), &measurer);
+    assert_eq!(layout.column_widths, vec![50.0, 100.0]);
+
+    let percentage = two_column_table(TableWidthMode::Percentage, [25.0, 75.0], ["", ""]);
+    let layout = compute_table_layout(&Document::new(), &percentage, &config_with_column(403.0), &measurer);
+    assert_eq!(layout.column_widths, vec![100.0, 300.0]);
+    assert_eq!(layout.total_width, 403.0);
+}
+
+#[test]
+fn test_table_auto_column_widths_follow_content() {
+    let measurer = FixedWidthMeasurer::default();
+    // Cell padding is 8px; text is 8px per character
+    let table = two_column_table(TableWidthMode::Auto, [50.0, 50.0], ["aaaa bbbb cccc dddd", "x"]);
+
+    // Room for the longest lines: the table is only as wide as its content
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &measurer);
+    assert_eq!(layout.column_widths, vec![160.0, 16.0]);
+    assert_eq!(layout.total_width, 179.0);
+
+    // Between the widest words (40, 16) and the longest lines (160, 16)
+    let layout = compute_table_layout(&Document::new(), &table, &config_with_column(103.0), &measurer);
+    assert_eq!(layout.column_widths, vec![84.0, 16.0]);
+    assert_eq!(layout.cell_lines[0][0].len(), 2);
+
+    // Less room than the widest words: scaled down
+    let layout = compute_table_layout(&Document::new(), &table, &config_with_column(59.0), &measurer);
+    assert_eq!(layout.column_widths, vec![40.0, 16.0]);
+    let layout = compute_table_layout(&Document::new(), &table, &config_with_column(31.0), &measurer);
+    assert!((layout.column_widths[0] - 40.0 * 28.0 / 56.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_table_auto_widths_use_paragraph_fonts_and_indents() {
+    let measurer = FixedWidthMeasurer::default();
+    let config = LayoutConfig::default();
+    let mut table = two_column_table(TableWidthMode::Auto, [50.0, 50.0], ["Title", "item"]);
+    table.get_cell_mut(0, 0).unwrap().paragraphs[0].meta.block_type = BlockType::Heading1;
+    table.get_cell_mut(0, 1).unwrap().paragraphs[0].meta.list_type = ListType::Bullet;
+    let document = Document::new();
+    let layout = compute_table_layout(&document, &table, &config, &measurer);
+
+    // The heading is measured in its own, larger font and fits on one line
+    let heading = &table.get_cell(0, 0).unwrap().paragraphs[0];
+    let heading_size = paragraph_format(&document, &heading.meta, &config).font.size;
+    assert!(heading_size > config.font_size);
+    assert_eq!(layout.column_widths[0], 8.0 + 5.0 * heading_size * 0.5);
+    assert_eq!(layout.cell_lines[0][0].len(), 1);
+    assert_eq!(layout.cell_lines[0][0][0].text, "Title");
+
+    // The list item also needs room for its marker
+    let marker = list_indent(ListType::Bullet, 0, config.font_size);
+    assert_eq!(layout.column_widths[1], 8.0 + marker + 4.0 * 8.0);
+    assert_eq!(layout.cell_lines[0][1].len(), 1);
+}
+
+#[test]
+fn test_table_auto_widths_with_merged_cell() {
+    let mut table = DocumentTable::new("t".to_string(), 2, 2, 0.0);
+    table.width_mode = TableWidthMode::Auto;
+    table.get_cell_mut(0, 0).unwrap().set_text("abcdefghijk");
+    table.merge_cells(0, 0, 0, 1);
+    table.get_cell_mut(1, 0).unwrap().set_text("a");
+    table.get_cell_mut(1, 1).unwrap().set_text("a");
+    let layout = compute_table_layout(&Document::new(), &table, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+
+    // The merged cell needs 96px across both columns and the border between them
+    assert_eq!(layout.column_widths, vec![47.5, 47.5]);
+}
+
+fn multi_paragraph_document(count: usize) -> Document {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    for i in 0..count {
+        document
+            .paragraphs
+            .push(Paragraph::new(format!("Paragraph {} with some words to wrap", i)));
+    }
+    document
+}
+
+fn narrow_config() -> LayoutConfig {
+    LayoutConfig {
+        page_width: 120.0 + 192.0,
+        page_height: 300.0,
+        ..Default::default()
+    }
+}
+
+fn assert_same_layout(actual: &[DisplayLine], expected: &[DisplayLine]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert_eq!(a.para_index, e.para_index);
+        assert_eq!(a.start_offset, e.start_offset);
+        assert_eq!(a.text, e.text);
+        assert_eq!(a.page_index, e.page_index);
+        assert_eq!(a.y_position, e.y_position);
+    }
+}
+
+#[test]
+fn test_update_layout_full_pass_matches_compute_layout() {
+    let document = multi_paragraph_document(20);
+    let config = narrow_config();
+    let measurer = FixedWidthMeasurer::default();
+
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    let update = update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+    assert_eq!(update.paragraphs_laid_out, 20);
+    assert!(!cache.needs_layout());
+}
+
+#[test]
+fn test_update_layout_only_relayouts_dirty_paragraph() {
+    let mut document = multi_paragraph_document(20);
+    let config = narrow_config();
+    let measurer = FixedWidthMeasurer::default();
+
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    // Same number of lines: following paragraphs keep their positions
+    document.paragraphs[10].text = "Paragraph X with some words to wrap".to_string();
+    cache.mark_dirty(10);
+    let update = update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    assert_eq!(update.first_paragraph, 10);
+    assert_eq!(update.paragraphs_laid_out, 1);
+    let page = lines.iter().find(|l| l.para_index == 10).unwrap().page_index;
+    assert!(update.changed_pages.contains(&page));
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+}
+
+#[test]
+fn test_update_layout_reflows_following_pages_when_lines_grow() {
+    let mut document = multi_paragraph_document(20);
+    let config = narrow_config();
+    let measurer = FixedWidthMeasurer::default();
+
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    let pages_before = lines.last().unwrap().page_index;
+
+    document.paragraphs[2].text = "word ".repeat(40);
+    cache.mark_dirty(2);
+    let update = update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+    let pages_after = lines.last().unwrap().page_index;
+    assert!(pages_after > pages_before);
+    assert!(update.changed_pages.contains(&pages_after));
+}
+
+#[test]
+fn test_update_layout_after_insert_and_remove() {
+    let mut document = multi_paragraph_document(12);
+    let config = narrow_config();
+    let measurer = FixedWidthMeasurer::default();
+
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    document.paragraphs.insert(5, Paragraph::new("Inserted".to_string()));
+    cache.insert_paragraph(5);
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+
+    document.paragraphs.remove(0);
+    cache.remove_paragraph(0);
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+
+    document.paragraphs.pop();
+    cache.remove_paragraph(document.paragraphs.len());
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+}
+
+#[test]
+fn test_update_layout_clean_cache_does_nothing() {
+    let document = multi_paragraph_document(5);
+    let config = narrow_config();
+    let measurer = FixedWidthMeasurer::default();
+
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    let update = update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    assert_eq!(update.paragraphs_laid_out, 0);
+    assert!(update.changed_pages.is_empty());
+}
+
+#[test]
+fn test_heading_line_is_taller_than_body_line() {
+    let mut document = Document::new();
+    document.paragraphs[0].text = "Title".to_string();
+    document.paragraphs[0].meta.block_type = BlockType::Heading1;
+    document.paragraphs.push(Paragraph::new("Body".to_string()));
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // 32px heading at 1.5 line height, 16px body
+    assert_eq!(lines[0].height, 48.0);
+    assert_eq!(lines[0].ascent, 32.0 * 0.8);
+    assert_eq!(lines[0].descent, 32.0 * 0.2);
+    assert_eq!(lines[1].height, 24.0);
+    // The body line starts below the full heading line box
+    assert_eq!(lines[1].y_position, 48.0 + config.paragraph_spacing);
+}
+
+#[test]
+fn test_paragraph_font_size_sets_line_height() {
+    let mut document = single_paragraph_document("aaaa bbbb cccc");
+    document.paragraphs[0].meta.font_size = Some(20.0);
+    let config = LayoutConfig {
+        page_width: 100.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].height, 30.0);
+    assert_eq!(lines[1].y_position, 30.0);
+}
+
+#[test]
+fn test_display_line_baseline_centers_glyphs() {
+    let document = single_paragraph_document("Hello");
+    let lines = compute_layout(&document, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+
+    // 24px line box, 16px of glyphs: 4px of leading above, ascent of 12.8
+    assert_eq!(lines[0].baseline(), 4.0 + 12.8);
+}
+
+#[test]
+fn test_pagination_uses_line_heights() {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    for _ in 0..3 {
+        let mut para = Paragraph::new("Heading".to_string());
+        para.meta.block_type = BlockType::Heading1;
+        para.meta.keep_with_next = Some(false);
+        document.paragraphs.push(para);
+    }
+    // Room for two 48px heading lines, but not three
+    let config = LayoutConfig {
+        page_height: 120.0 + 192.0,
+        paragraph_spacing: 0.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines[1].page_index, 0);
+    assert_eq!(lines[1].y_position, 48.0);
+    assert_eq!(lines[2].page_index, 1);
+    assert_eq!(lines[2].y_position, 0.0);
+}
+
+#[test]
+fn test_fixed_float_overlap_uses_line_heights() {
+    let mut document = Document::new();
+    document.paragraphs[0].text = "Title".to_string();
+    document.paragraphs[0].meta.block_type = BlockType::Heading1;
+    document.paragraphs.push(Paragraph::new("Body".to_string()));
+
+    // Float starts below the heading's 48px line box
+    let mut image = DocumentImage::new("img".to_string(), "src".to_string(), 100.0, 40.0);
+    image.wrap_style = ImageWrapStyle::Square;
+    image.position_mode = ImagePositionMode::FixedPosition;
+    image.x = Some(0.0);
+    image.y = Some(50.0);
+    image.page_index = Some(0);
+    document.images.push(image);
+
+    let config = LayoutConfig {
+        paragraph_spacing: 0.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert!(lines[0].float_reduction.is_none());
+    assert!(lines[1].float_reduction.is_some());
+}
+
+#[test]
+fn test_nested_list_counters_continue_after_sublist() {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    for (text, level) in [("one", 0), ("two", 0), ("sub a", 1), ("sub b", 1), ("three", 0), ("deep", 2)] {
+        let mut para = Paragraph::new(text.to_string());
+        para.meta.list_type = ListType::Numbered;
+        para.meta.list_level = level;
+        document.paragraphs.push(para);
+    }
+    let lines = compute_layout(&document, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+
+    let markers: Vec<_> = lines.iter().map(|l| l.list_marker.clone().unwrap()).collect();
+    assert_eq!(markers, ["1.", "2.", "a.", "b.", "3.", "i."]);
+    assert_eq!(lines[2].list_level, 1);
+}
+
+#[test]
+fn test_list_indent_grows_with_level() {
+    assert_eq!(list_indent(ListType::None, 3, 16.0), 0.0);
+    assert_eq!(list_indent(ListType::Bullet, 0, 16.0), 24.0);
+    assert_eq!(list_indent(ListType::Bullet, 1, 16.0), 48.0);
+    // Levels past the maximum are clamped
+    assert_eq!(list_indent(ListType::Numbered, 20, 16.0), list_indent(ListType::Numbered, MAX_LIST_LEVEL, 16.0));
+}
+
+fn list_document(items: &[(&str, ListType, u8)]) -> Document {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    for (text, list_type, level) in items {
+        let mut para = Paragraph::new(text.to_string());
+        para.meta.list_type = *list_type;
+        para.meta.list_level = *level;
+        document.paragraphs.push(para);
+    }
+    document
+}
+
+fn list_markers(document: &Document) -> Vec<Option<String>> {
+    let lines = compute_layout(document, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+    lines.iter().map(|l| l.list_marker.clone()).collect()
+}
+
+#[test]
+fn test_interrupted_list_restarts_unless_continued() {
+    let mut document = list_document(&[
+        ("one", ListType::Numbered, 0),
+        ("two", ListType::Numbered, 0),
+        ("note", ListType::None, 0),
+        ("three", ListType::Numbered, 0),
+    ]);
+    assert_eq!(list_markers(&document)[3].as_deref(), Some("1."));
+
+    document.paragraphs[3].meta.list_continue = true;
+    let markers = list_markers(&document);
+    assert_eq!(markers[2], None);
+    assert_eq!(markers[3].as_deref(), Some("3."));
+}
+
+#[test]
+fn test_list_start_value_and_formats() {
+    let mut document = list_document(&[
+        ("one", ListType::Numbered, 0),
+        ("two", ListType::Numbered, 0),
+        ("three", ListType::Numbered, 0),
+    ]);
+    for para in &mut document.paragraphs {
+        para.meta.number_format = Some(NumberFormat::UpperRoman);
+        para.meta.number_template = Some("Article %1.".to_string());
+    }
+    document.paragraphs[0].meta.list_start = Some(5);
+    document.paragraphs[2].meta.list_start = Some(1);
+    document.paragraphs[2].meta.number_format = Some(NumberFormat::DecimalZero);
+    document.paragraphs[2].meta.number_template = Some("(%1)".to_string());
+
+    let lines = compute_layout(&document, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+    assert_eq!(lines[0].list_marker.as_deref(), Some("Article V."));
+    assert_eq!(lines[1].list_marker.as_deref(), Some("Article VI."));
+    assert_eq!(lines[1].list_number, Some(6));
+    assert_eq!(lines[2].list_marker.as_deref(), Some("(01)"));
+}
+
+#[test]
+fn test_outline_numbering() {
+    let mut document = list_document(&[
+        ("1.", ListType::Numbered, 0),
+        ("1.1", ListType::Numbered, 1),
+        ("1.1.1", ListType::Numbered, 2),
+        ("1.2", ListType::Numbered, 1),
+        ("2.", ListType::Numbered, 0),
+        ("2.1", ListType::Numbered, 1),
+    ]);
+    for para in &mut document.paragraphs {
+        para.meta.number_format = Some(NumberFormat::Decimal);
+        para.meta.number_template = Some(match para.meta.list_level {
+            0 => "%1.".to_string(),
+            1 => "%1.%2".to_string(),
+            _ => "%1.%2.%3".to_string(),
+        });
+    }
+
+    let markers: Vec<String> = list_markers(&document).into_iter().map(Option::unwrap).collect();
+    let expected: Vec<String> = document.paragraphs.iter().map(|p| p.text.clone()).collect();
+    assert_eq!(markers, expected);
+}
+
+#[test]
+fn test_mixed_font_sizes_measure_each_run() {
+    // 8px per character at 16px, 16px per character at 32px; 140px column
+    let mut document = single_paragraph_document("aaaa bbbb cccc");
+    document.paragraphs[0].apply_style(5, 9, |s| s.font_size = Some(32.0));
+    let config = LayoutConfig {
+        page_width: 140.0 + 192.0,
+        paragraph_spacing: 0.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // "aaaa " (40px) + "bbbb" (64px) + " " fit; "cccc" would need 32px more,
+    // which it would not if every character were measured at 16px
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "aaaa bbbb ");
+    assert_eq!(lines[0].char_positions[9], 40.0 + 64.0);
+    assert_eq!(lines[0].runs.len(), 3);
+    assert_eq!(lines[0].runs[1].font.size, 32.0);
+    assert_eq!((lines[0].runs[1].start, lines[0].runs[1].end), (5, 9));
+
+    // The line holding the large run is as tall as its font needs
+    assert_eq!(lines[0].height, 32.0 * config.line_height);
+    assert_eq!(lines[0].ascent, 32.0 * 0.8);
+    assert_eq!(lines[1].height, 16.0 * config.line_height);
+    assert_eq!(lines[1].y_position, lines[0].height);
+}
+
+#[test]
+fn test_style_fonts_resolve_against_paragraph_font() {
+    let mut document = single_paragraph_document("plain bold family");
+    document.paragraphs[0].meta.block_type = BlockType::Heading2;
+    document.paragraphs[0].apply_style(6, 10, |s| s.font_weight = Some(300));
+    document.paragraphs[0].apply_style(11, 17, |s| {
+        s.font_family = Some("Georgia".to_string());
+        s.italic = true;
+    });
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let runs = &lines[0].runs;
+
+    let heading_size = config.font_size * BlockType::Heading2.font_size_multiplier();
+    assert_eq!(runs[0].font.family.as_deref(), Some("Arial"));
+    assert_eq!(runs[0].font.size, heading_size);
+    assert_eq!(runs[0].font.weight, FONT_WEIGHT_BOLD);
+    assert_eq!(runs[1].font.weight, 300);
+    let last = runs.last().unwrap();
+    assert_eq!(last.font.family.as_deref(), Some("Georgia"));
+    assert!(last.font.italic);
+    assert_eq!(lines[0].run_at(8).unwrap().font.weight, 300);
+}
+
+#[test]
+fn test_superscript_and_subscript_shrink_and_shift_runs() {
+    let mut document = single_paragraph_document("x2 H2O");
+    document.paragraphs[0].apply_style(1, 2, |s| s.vertical_align = VerticalAlign::Superscript);
+    document.paragraphs[0].apply_style(4, 5, |s| s.vertical_align = VerticalAlign::Subscript);
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let line = &lines[0];
+
+    let script_size = 16.0 * SCRIPT_SIZE_RATIO;
+    let sup = line.run_at(2).unwrap();
+    assert!((sup.font.size - script_size).abs() < 1e-9);
+    assert!(sup.rise > 0.0);
+    assert!((sup.ascent - (script_size * 0.8 + sup.rise)).abs() < 1e-9);
+    let sub = line.run_at(5).unwrap();
+    assert!(sub.rise < 0.0);
+    assert!((sub.descent - (script_size * 0.2 - sub.rise)).abs() < 1e-9);
+
+    // Scripts are measured at their reduced size
+    assert!((line.char_positions[2] - (8.0 + script_size * 0.5)).abs() < 1e-9);
+    // The raised run extends the line's ascent
+    assert_eq!(line.ascent, sup.ascent.max(16.0 * 0.8));
+}
+
+#[test]
+fn test_caps_transforms_measure_uppercase_text() {
+    let mut document = single_paragraph_document("Abc def");
+    document.paragraphs[0].apply_style(0, 3, |s| s.caps = CapsStyle::SmallCaps);
+    document.paragraphs[0].apply_style(4, 7, |s| s.caps = CapsStyle::AllCaps);
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let runs = &lines[0].runs;
+
+    // Small caps: the capital keeps its size, lowercase letters become smaller capitals
+    assert_eq!((runs[0].start, runs[0].end, runs[0].uppercase), (0, 1, false));
+    assert_eq!((runs[1].start, runs[1].end, runs[1].uppercase), (1, 3, true));
+    assert!((runs[1].font.size - 16.0 * SMALL_CAPS_SIZE_RATIO).abs() < 1e-9);
+    assert!((lines[0].char_positions[3] - (8.0 + 2.0 * 8.0 * SMALL_CAPS_SIZE_RATIO)).abs() < 1e-9);
+
+    // All caps keeps the font size
+    let all_caps = lines[0].run_at(5).unwrap();
+    assert!(all_caps.uppercase);
+    assert_eq!(all_caps.font.size, 16.0);
+    assert_eq!(all_caps.display_text("def"), "DEF");
+}
+
+#[test]
+fn test_layout_resolves_paragraph_and_character_styles() {
+    let mut document = single_paragraph_document("Title text");
+    document.paragraphs[0].meta.block_type = BlockType::Heading1;
+    document.paragraphs.push(Paragraph::new("Body".to_string()));
+    document.styles.push(NamedStyle {
+        id: "code".to_string(),
+        name: "Code".to_string(),
+        kind: StyleKind::Character,
+        based_on: None,
+        properties: StyleProperties {
+            font_family: Some("Courier".to_string()),
+            ..Default::default()
+        },
+    });
+    document.paragraphs[1].apply_style(0, 4, |s| s.style_id = Some("code".to_string()));
+    // Restyling "Heading 1" changes every heading
+    let heading = document.styles.iter_mut().find(|s| s.id == "heading1").unwrap();
+    heading.properties.font_scale = Some(3.0);
+    heading.properties.font_family = Some("Georgia".to_string());
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    let title = &lines[0].runs[0].font;
+    assert_eq!(title.size, 48.0);
+    assert_eq!(title.weight, FONT_WEIGHT_BOLD);
+    assert_eq!(title.family.as_deref(), Some("Georgia"));
+    assert_eq!(lines[1].runs[0].font.family.as_deref(), Some("Courier"));
+
+    // Direct formatting overrides the paragraph style
+    document.paragraphs[0].meta.font_size = Some(10.0);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(lines[0].runs[0].font.size, 30.0);
+}
+
+#[test]
+fn test_first_line_and_hanging_indents_change_line_widths() {
+    // 160px column, 8px characters
+    let mut document = single_paragraph_document(&"abc ".repeat(20));
+    document.paragraphs[0].meta.indent = ParagraphIndent {
+        left: 8.0,
+        right: 8.0,
+        first_line: 64.0,
+        hanging: 32.0,
+    };
+    let config = LayoutConfig {
+        page_width: 160.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // First line: 160 - 8 - 8 - 64 = 80px (10 characters); later lines:
+    // 160 - 8 - 8 - 32 = 112px (14 characters)
+    assert_eq!(lines[0].text, "abc abc ");
+    assert_eq!(lines[1].text, "abc abc abc ");
+    assert_eq!(lines[2].text, "abc abc abc ");
+}
+
+#[test]
+fn test_paragraph_spacing_overrides_and_collapses_at_page_top() {
+    let mut document = single_paragraph_document("one");
+    for text in ["two", "three"] {
+        document.paragraphs.push(Paragraph::new(text.to_string()));
+    }
+    document.paragraphs[0].meta.space_before = Some(40.0);
+    document.paragraphs[0].meta.space_after = Some(0.0);
+    document.paragraphs[1].meta.space_before = Some(10.0);
+    // Content height 104px: three 24px lines and the spacing below fit on one page
+    let config = LayoutConfig {
+        page_height: 104.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // Space before collapses at the top of the page
+    assert_eq!(lines[0].y_position, 0.0);
+    assert_eq!(lines[1].y_position, 24.0 + 10.0);
+    // The default space after (12px) still applies to the second paragraph
+    assert_eq!(lines[2].y_position, 24.0 + 10.0 + 24.0 + 12.0);
+
+    // Space before that pushes a paragraph off the page is dropped there
+    document.paragraphs[2].meta.space_before = Some(30.0);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!((lines[2].page_index, lines[2].y_position), (1, 0.0));
+}
+
+#[test]
+fn test_line_spacing_modes() {
+    let line_height = |spacing: Option<LineSpacing>| {
+        let mut document = single_paragraph_document("text");
+        document.paragraphs[0].meta.line_spacing = spacing;
+        let lines = compute_layout(&document, &LayoutConfig::default(), &FixedWidthMeasurer::default());
+        lines[0].height
+    };
+    assert_eq!(line_height(None), 16.0 * 1.5);
+    assert_eq!(line_height(Some(LineSpacing::Multiple(2.0))), 32.0);
+    // Multiples never go below the glyphs' height
+    assert_eq!(line_height(Some(LineSpacing::Multiple(0.5))), 16.0);
+    assert_eq!(line_height(Some(LineSpacing::Exact(10.0))), 10.0);
+    assert_eq!(line_height(Some(LineSpacing::AtLeast(10.0))), 24.0);
+    assert_eq!(line_height(Some(LineSpacing::AtLeast(30.0))), 30.0);
+}
+
+#[test]
+fn test_spacing_comes_from_paragraph_style() {
+    let mut document = single_paragraph_document("Intro");
+    document.paragraphs.push(Paragraph::new("Heading".to_string()));
+    document.paragraphs[1].meta.block_type = BlockType::Heading2;
+    let heading = document.styles.iter_mut().find(|s| s.id == "heading2").unwrap();
+    heading.properties.space_before = Some(20.0);
+    heading.properties.line_spacing = Some(LineSpacing::Exact(30.0));
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    assert_eq!(lines[1].y_position, 24.0 + 12.0 + 20.0);
+    assert_eq!(lines[1].height, 30.0);
+}
+
+/// Paragraphs of `lines` one-word lines each, on pages of 80px columns with
+/// room for `page_lines` lines and no paragraph spacing
+fn pagination_document(lines: &[usize], page_lines: usize) -> (Document, LayoutConfig) {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    for &count in lines {
+        document.paragraphs.push(Paragraph::new(vec!["abcdefghi"; count].join(" ")));
+    }
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        page_height: page_lines as f64 * 24.0 + 192.0,
+        paragraph_spacing: 0.0,
+        ..Default::default()
+    };
+    (document, config)
+}
+
+fn paragraph_pages(lines: &[DisplayLine], para_index: usize) -> Vec<usize> {
+    lines.iter().filter(|dl| dl.para_index == para_index).map(|dl| dl.page_index).collect()
+}
+
+#[test]
+fn test_orphan_control_moves_paragraph_start() {
+    let (mut document, config) = pagination_document(&[4, 3], 5);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 1), vec![1, 1, 1]);
+
+    document.paragraphs[1].meta.orphans = Some(1);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 1), vec![0, 1, 1]);
+}
+
+#[test]
+fn test_widow_control_carries_lines_over() {
+    let (mut document, config) = pagination_document(&[3, 4], 6);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 1), vec![0, 0, 1, 1]);
+    assert_eq!(lines[5].y_position, 0.0);
+
+    document.paragraphs[1].meta.widows = Some(1);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 1), vec![0, 0, 0, 1]);
+}
+
+#[test]
+fn test_keep_together_and_page_break_before() {
+    let (mut document, config) = pagination_document(&[2, 4, 1], 5);
+    document.paragraphs[1].meta.keep_together = Some(true);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 1), vec![1, 1, 1, 1]);
+
+    // A paragraph too long for one page is split anyway
+    let (mut document, config) = pagination_document(&[7], 5);
+    document.paragraphs[0].meta.keep_together = Some(true);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(paragraph_pages(&lines, 0), vec![0, 0, 0, 0, 0, 1, 1]);
+
+    // No blank page before a paragraph already at the top of one
+    let (mut document, config) = pagination_document(&[1, 1], 5);
+    document.paragraphs[0].meta.page_break_before = Some(true);
+    document.paragraphs[1].meta.page_break_before = Some(true);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!((lines[0].page_index, lines[1].page_index), (0, 1));
+    assert_eq!(lines[1].y_position, 0.0);
+}
+
+#[test]
+fn test_heading_kept_with_next_paragraph() {
+    let (mut document, config) = pagination_document(&[4, 1, 3], 6);
+    document.paragraphs[1].text = "Title".to_string();
+    document.paragraphs[1].meta.block_type = BlockType::Heading1;
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // The 48px heading fits below the first paragraph, but its next paragraph does not
+    assert_eq!((lines[4].para_index, lines[4].page_index, lines[4].y_position), (1, 1, 0.0));
+    assert_eq!(paragraph_pages(&lines, 2), vec![1, 1, 1]);
+
+    document.paragraphs[1].meta.keep_with_next = Some(false);
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    assert_eq!(lines[4].page_index, 0);
+}
+
+#[test]
+fn test_update_layout_moves_heading_when_next_paragraph_grows() {
+    let (mut document, config) = pagination_document(&[3, 1, 1], 6);
+    document.paragraphs[1].text = "Title".to_string();
+    document.paragraphs[1].meta.block_type = BlockType::Heading1;
+    let measurer = FixedWidthMeasurer::default();
+    let mut cache = LayoutCache::new();
+    let mut lines = Vec::new();
+    update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+    assert_eq!(lines[3].page_index, 0);
+
+    document.paragraphs[2].text = "abcdefghi abcdefghi".to_string();
+    cache.mark_dirty(2);
+    let update = update_layout(&document, &config, &measurer, &mut cache, &mut lines);
+
+    assert_eq!(update.first_paragraph, 1);
+    assert_eq!(lines[3].page_index, 1);
+    assert_same_layout(&lines, &compute_layout(&document, &config, &measurer));
+}
+
+fn table_document(table: DocumentTable) -> Document {
+    let mut document = Document::new();
+    document.paragraphs.clear();
+    document.paragraphs.push(Paragraph::new(format!("\u{FFFB}{}", table.id)));
+    document.tables.push(table);
+    document
+}
+
+#[test]
+fn test_table_splits_between_rows_and_repeats_header() {
+    // 32px rows with 1px borders: row tops every 33px, 331px in all
+    let mut table = DocumentTable::new("t".to_string(), 10, 1, 0.0);
+    table.header_rows = 1;
+    let document = table_document(table);
+    let config = LayoutConfig {
+        page_height: 100.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    let pages: Vec<usize> = lines.iter().map(|dl| dl.page_index).collect();
+    assert_eq!(pages, vec![0, 1, 2, 3, 4]);
+    assert!(lines.iter().all(|dl| dl.is_table && dl.y_position == 0.0 && dl.height <= 100.0));
+    assert_eq!(lines[0].table_fragment, Some(TableFragment { start: 0.0, end: 99.0, header_height: 0.0 }));
+    assert_eq!(lines[1].table_fragment, Some(TableFragment { start: 99.0, end: 165.0, header_height: 33.0 }));
+    assert_eq!(lines[4].table_fragment.unwrap().end, 331.0);
+    assert_eq!(lines.iter().filter(|dl| dl.is_last_line).count(), 1);
+    assert!(lines[4].is_last_line);
+}
+
+#[test]
+fn test_tall_table_row_splits_between_text_lines() {
+    let mut table = DocumentTable::new("t".to_string(), 1, 1, 0.0);
+    table.get_cell_mut(0, 0).unwrap().set_text("a\nb\nc\nd\ne\nf");
+    let document = table_document(table);
+    let config = LayoutConfig {
+        page_height: 100.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    // Text lines start 5px into the table, every 24px
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].table_fragment.unwrap().end, 77.0);
+    assert_eq!((lines[1].page_index, lines[1].height), (1, 154.0 - 77.0));
+
+    // A table that fits is one line, moved to the next page if need be
+    let mut document = table_document(DocumentTable::new("t".to_string(), 2, 1, 0.0));
+    document.paragraphs.insert(0, Paragraph::new(vec!["word"; 60].join(" ")));
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let table_lines: Vec<&DisplayLine> = lines.iter().filter(|dl| dl.is_table).collect();
+    assert_eq!(table_lines.len(), 1);
+    assert!(table_lines[0].table_fragment.is_none());
+    assert_eq!((table_lines[0].page_index, table_lines[0].y_position), (1, 0.0));
+}
@@ -0,0 +1,153 @@
+//! Tests for the measure module
+
+use std::cell::Cell;
+
+use editor_engine::*;
+
+#[test]
+fn test_font_spec_new_is_regular() {
+    let font = FontSpec::new(16.0);
+    assert_eq!(font.size, 16.0);
+    assert_eq!(font.weight, FONT_WEIGHT_NORMAL);
+    assert!(!font.italic);
+    assert!(!font.is_bold());
+}
+
+#[test]
+fn test_font_spec_with_style() {
+    let font = FontSpec::with_style(12.0, true, true);
+    assert_eq!(font.weight, FONT_WEIGHT_BOLD);
+    assert!(font.is_bold());
+    assert!(font.italic);
+}
+
+#[test]
+fn test_font_spec_family() {
+    assert!(FontSpec::new(12.0).family.is_none());
+    let font = FontSpec::with_style(12.0, true, false).with_family("Georgia");
+    assert_eq!(font.family.as_deref(), Some("Georgia"));
+    assert!(font.is_bold());
+}
+
+#[test]
+fn test_fixed_width_measurer_counts_chars() {
+    let measurer = FixedWidthMeasurer::default();
+    let font = FontSpec::new(10.0);
+    assert_eq!(measurer.measure("hello", &font), 25.0);
+    // Multi-byte characters count as one advance each
+    assert_eq!(measurer.measure("héllo", &font), 25.0);
+    assert_eq!(measurer.measure("", &font), 0.0);
+}
+
+#[test]
+fn test_fixed_width_measurer_custom_width() {
+    let measurer = FixedWidthMeasurer::new(1.0);
+    assert_eq!(measurer.measure("abc", &FontSpec::new(8.0)), 24.0);
+}
+
+/// A measurer defined outside the crate can drive layout
+struct WideMeasurer;
+
+impl TextMeasurer for WideMeasurer {
+    fn measure(&self, text: &str, font: &FontSpec) -> f64 {
+        text.chars().count() as f64 * font.size
+    }
+}
+
+#[test]
+fn test_custom_measurer_drives_layout() {
+    let mut document = Document::new();
+    document.paragraphs[0].text = "aaaa bbbb cccc dddd".to_string();
+    // 10px per char at 10px font: "aaaa bbbb " is exactly 100px wide
+    let config = LayoutConfig {
+        page_width: 100.0 + 192.0,
+        font_size: 10.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &WideMeasurer);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "aaaa bbbb ");
+    assert_eq!(lines[1].text, "cccc dddd");
+}
+
+#[test]
+fn test_default_metrics_are_approximate() {
+    let metrics = FixedWidthMeasurer::default().metrics(&FontSpec::new(20.0));
+    assert_eq!(metrics, FontMetrics::approximate(20.0));
+    assert_eq!(metrics.ascent + metrics.descent, 20.0);
+}
+
+#[test]
+fn test_default_prefix_widths_measure_each_prefix() {
+    let widths = WideMeasurer.prefix_widths("aé b", &FontSpec::new(10.0));
+    assert_eq!(widths, vec![0.0, 10.0, 20.0, 30.0, 40.0]);
+}
+
+#[test]
+fn test_fixed_width_prefix_widths_match_measure() {
+    let measurer = FixedWidthMeasurer::default();
+    let font = FontSpec::new(10.0);
+    let widths = measurer.prefix_widths("héllo", &font);
+    assert_eq!(widths.len(), 6);
+    assert_eq!(widths[0], 0.0);
+    assert_eq!(widths[5], measurer.measure("héllo", &font));
+}
+
+fn wrapped_document() -> (Document, LayoutConfig) {
+    let mut document = Document::new();
+    document.paragraphs[0].text = "aaaa bbbb cccc dddd".to_string();
+    let config = LayoutConfig {
+        page_width: 80.0 + 192.0,
+        ..Default::default()
+    };
+    (document, config)
+}
+
+#[test]
+fn test_callback_measurer_measures_prefixes_in_one_call() {
+    // Callbacks shaped like the JavaScript API, counting how often they are called
+    let measure_calls = Cell::new(0);
+    let prefix_calls = Cell::new(0);
+    let measurer = CallbackMeasurer::new(|text, font| {
+        measure_calls.set(measure_calls.get() + 1);
+        Some(FixedWidthMeasurer::default().measure(text, font))
+    })
+    .with_prefixes(|text, font| {
+        prefix_calls.set(prefix_calls.get() + 1);
+        Some(FixedWidthMeasurer::default().prefix_widths(text, font))
+    });
+    let (document, config) = wrapped_document();
+    let lines = compute_layout(&document, &config, &measurer);
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(prefix_calls.get(), 1);
+    assert_eq!(measure_calls.get(), 0);
+}
+
+#[test]
+fn test_callback_measurer_without_prefixes_measures_each_prefix() {
+    let measure_calls = Cell::new(0);
+    let measurer = CallbackMeasurer::new(|text, font| {
+        measure_calls.set(measure_calls.get() + 1);
+        Some(FixedWidthMeasurer::default().measure(text, font))
+    });
+    let (document, config) = wrapped_document();
+    compute_layout(&document, &config, &measurer);
+
+    assert_eq!(measure_calls.get(), document.paragraphs[0].text.chars().count());
+}
+
+#[test]
+fn test_callback_measurer_rejects_wrong_prefix_count() {
+    let measurer = CallbackMeasurer::new(|text, font| Some(WideMeasurer.measure(text, font)))
+        .with_prefixes(|_, _| Some(vec![0.0, 1.0]));
+    let widths = measurer.prefix_widths("abc", &FontSpec::new(10.0));
+    assert_eq!(widths, vec![0.0, 10.0, 20.0, 30.0]);
+}
+
+#[test]
+fn test_callback_measurer_falls_back_when_measure_fails() {
+    let measurer = CallbackMeasurer::new(|_, _| None);
+    let font = FontSpec::new(10.0);
+    assert_eq!(measurer.measure("abcd", &font), FixedWidthMeasurer::default().measure("abcd", &font));
+}
@@ -0,0 +1,384 @@
+//! Tests for the position module
+
+use editor_engine::*;
+
+/// 300px wide column starting at x = 50; characters are 8px wide
+fn test_config() -> LayoutConfig {
+    LayoutConfig {
+        page_width: 400.0,
+        page_height: 600.0,
+        margin_top: 50.0,
+        margin_right: 50.0,
+        margin_bottom: 50.0,
+        margin_left: 50.0,
+        font_size: 16.0,
+        ..Default::default()
+    }
+}
+
+fn layout(document: &Document, config: &LayoutConfig) -> Vec<DisplayLine> {
+    compute_layout(document, config, &FixedWidthMeasurer::default())
+}
+
+fn document_with(text: &str) -> Document {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new(text.to_string());
+    document
+}
+
+fn line_center_y(line: &DisplayLine, config: &LayoutConfig) -> f64 {
+    config.margin_top + line.y_position + line.height / 2.0
+}
+
+#[test]
+fn test_hit_test_maps_x_to_nearest_character() {
+    let document = document_with("Hello world");
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let y = line_center_y(&lines[0], &config);
+
+    let hit = hit_test(&lines, &document, &config, 0, 50.0 + 8.0 * 4.0 + 3.0, y).unwrap();
+    assert_eq!((hit.position.para, hit.position.offset), (0, 4));
+    assert_eq!(hit.affinity, Affinity::Downstream);
+    assert!(hit.image_id.is_none() && hit.table.is_none());
+
+    // Left of the text and past its end clamp to the line
+    let hit = hit_test(&lines, &document, &config, 0, 0.0, y).unwrap();
+    assert_eq!(hit.position.offset, 0);
+    let hit = hit_test(&lines, &document, &config, 0, 390.0, y).unwrap();
+    assert_eq!(hit.position.offset, 11);
+}
+
+#[test]
+fn test_hit_test_end_of_wrapped_line_is_upstream() {
+    let document = document_with(&"word ".repeat(20));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    assert!(lines.len() > 1);
+
+    let hit = hit_test(&lines, &document, &config, 0, 349.0, line_center_y(&lines[0], &config)).unwrap();
+    assert_eq!(hit.position.offset, lines[0].end_offset);
+    assert_eq!(hit.affinity, Affinity::Upstream);
+
+    let hit = hit_test(&lines, &document, &config, 0, 50.0, line_center_y(&lines[1], &config)).unwrap();
+    assert_eq!(hit.position.offset, lines[1].start_offset);
+    assert_eq!(hit.affinity, Affinity::Downstream);
+}
+
+#[test]
+fn test_hit_test_below_content_uses_last_line() {
+    let document = document_with("Hello");
+    let config = test_config();
+    let lines = layout(&document, &config);
+
+    let hit = hit_test(&lines, &document, &config, 0, 400.0, 500.0).unwrap();
+    assert_eq!(hit.position.offset, 5);
+    assert!(hit_test(&lines, &document, &config, 3, 100.0, 100.0).is_none());
+}
+
+#[test]
+fn test_hit_test_respects_alignment_and_list_indent() {
+    let mut document = document_with("abcd");
+    document.paragraphs[0].meta.align = Some(TextAlign::Right);
+    document.paragraphs.push(Paragraph::new("abcd".to_string()));
+    document.paragraphs[1].meta.list_type = ListType::Bullet;
+    let config = test_config();
+    let lines = layout(&document, &config);
+
+    // Right aligned: text occupies [318, 350)
+    let hit = hit_test(&lines, &document, &config, 0, 318.0 + 8.0, line_center_y(&lines[0], &config)).unwrap();
+    assert_eq!(hit.position.offset, 1);
+
+    // Bullet list: text starts after a 24px indent
+    let hit = hit_test(&lines, &document, &config, 0, 50.0 + 24.0 + 16.0, line_center_y(&lines[1], &config)).unwrap();
+    assert_eq!((hit.position.para, hit.position.offset), (1, 2));
+}
+
+#[test]
+fn test_line_placement_justifies_words() {
+    let mut document = document_with(&"word ".repeat(20));
+    document.paragraphs[0].meta.align = Some(TextAlign::Justify);
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+
+    let first = line_placement(&lines[0], &format, &config);
+    assert!(first.word_spacing > 0.0);
+    let end = first.x_at(&lines[0], lines[0].text.trim_end().chars().count());
+    assert!((end - 350.0).abs() < 0.001);
+
+    let last = line_placement(lines.last().unwrap(), &format, &config);
+    assert_eq!(last.word_spacing, 0.0);
+}
+
+#[test]
+fn test_hit_test_text_beside_float() {
+    let mut document = Document::new();
+    let mut image = DocumentImage::new("img".to_string(), "data:".to_string(), 100.0, 100.0);
+    image.wrap_style = ImageWrapStyle::Square;
+    document.images.push(image);
+    document.paragraphs[0] = Paragraph::new("\u{FFFC}img".to_string());
+    document.paragraphs.push(Paragraph::new("Text beside".to_string()));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let text_line = lines.iter().find(|l| l.para_index == 1).unwrap();
+    assert!(text_line.float_reduction.is_some());
+
+    // The float covers [50, 150); text starts after the 10px gap
+    let y = line_center_y(text_line, &config);
+    let hit = hit_test(&lines, &document, &config, 0, 160.0 + 8.0, y).unwrap();
+    assert_eq!((hit.position.para, hit.position.offset), (1, 1));
+
+    let hit = hit_test(&lines, &document, &config, 0, 60.0, y).unwrap();
+    assert_eq!(hit.image_id.as_deref(), Some("img"));
+    assert_eq!(hit.position.para, 0);
+}
+
+#[test]
+fn test_hit_test_table_cell() {
+    let mut document = Document::new();
+    let mut table = DocumentTable::new("t1".to_string(), 2, 2, 100.0);
+    table.get_cell_mut(1, 1).unwrap().set_text("Cell");
+    table.merge_cells(0, 0, 0, 1);
+    document.tables.push(table);
+    document.paragraphs.push(Paragraph::new("\u{FFFB}t1".to_string()));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let table_line = lines.iter().find(|l| l.is_table).unwrap();
+    let layout = table_line.table_layout.as_ref().unwrap();
+    let border = document.tables[0].border_width;
+
+    let table_top = config.margin_top + table_line.y_position;
+    let row1_y = table_top + border + layout.row_heights[0] + border + 10.0;
+    let col1_x = 50.0 + border + layout.column_widths[0] + border + CELL_TEXT_PADDING;
+    let hit = hit_test(&lines, &document, &config, 0, col1_x + 8.0 * 2.0 + 1.0, row1_y).unwrap();
+    let cell = hit.table.unwrap();
+    assert_eq!((cell.table_id.as_str(), cell.row, cell.col, cell.offset), ("t1", 1, 1, 2));
+    assert_eq!(hit.position.para, 1);
+
+    // The covered cell maps to the merged cell's origin
+    let hit = hit_test(&lines, &document, &config, 0, col1_x, table_top + 10.0).unwrap();
+    let cell = hit.table.unwrap();
+    assert_eq!((cell.row, cell.col), (0, 0));
+}
+
+#[test]
+fn test_caret_rect_follows_line_placement() {
+    let mut document = document_with("abcd");
+    document.paragraphs[0].meta.align = Some(TextAlign::Center);
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+    let placement = line_placement(&lines[0], &format, &config);
+
+    let caret = caret_rect(&lines, &document, &config, 0, 2, Affinity::Downstream).unwrap();
+    assert_eq!(caret.page_index, 0);
+    assert_eq!(caret.x, placement.x + 16.0);
+    assert_eq!(caret.height, lines[0].ascent + lines[0].descent);
+    assert_eq!(caret.y, config.margin_top + lines[0].y_position + lines[0].baseline() - lines[0].ascent);
+    assert!(caret_rect(&lines, &document, &config, 5, 0, Affinity::Downstream).is_none());
+}
+
+#[test]
+fn test_caret_rect_affinity_at_wrap() {
+    let document = document_with(&"word ".repeat(20));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let boundary = lines[0].end_offset;
+
+    let up = caret_rect(&lines, &document, &config, 0, boundary, Affinity::Upstream).unwrap();
+    let down = caret_rect(&lines, &document, &config, 0, boundary, Affinity::Downstream).unwrap();
+    assert!(up.x > down.x);
+    assert!(up.y < down.y);
+    assert_eq!(down.x, 50.0);
+}
+
+#[test]
+fn test_selection_rects_across_paragraphs() {
+    let mut document = document_with("Hello world");
+    document.paragraphs.push(Paragraph::new(String::new()));
+    document.paragraphs.push(Paragraph::new("Second".to_string()));
+    let config = test_config();
+    let lines = layout(&document, &config);
+
+    let rects = selection_rects(
+        &lines,
+        &document,
+        &config,
+        ParagraphPosition { para: 2, offset: 3 },
+        ParagraphPosition { para: 0, offset: 6 },
+    );
+    assert_eq!(rects.len(), 3);
+    // "world" plus the selected paragraph break
+    assert_eq!(rects[0].x, 50.0 + 48.0);
+    assert!(rects[0].width > 40.0);
+    // The empty paragraph shows only its break
+    assert!(rects[1].width > 0.0);
+    assert_eq!(rects[2].x, 50.0);
+    assert_eq!(rects[2].width, 24.0);
+    assert_eq!(rects[2].height, lines[2].height);
+
+    let none = ParagraphPosition { para: 0, offset: 1 };
+    assert!(selection_rects(&lines, &document, &config, none, none).is_empty());
+}
+
+#[test]
+fn test_hit_test_reports_checklist_checkbox() {
+    let mut document = document_with("Buy milk");
+    document.paragraphs[0].meta.list_type = ListType::Checklist;
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+    let checkbox = checkbox_rect(&lines[0], &format, &config).unwrap();
+    // The box sits in the 24px list gutter
+    assert!(checkbox.x >= 50.0 && checkbox.x + checkbox.width <= 74.0);
+
+    let hit = hit_test(&lines, &document, &config, 0, checkbox.x + 2.0, checkbox.y + 2.0).unwrap();
+    assert!(hit.checkbox);
+    assert_eq!((hit.position.para, hit.position.offset), (0, 0));
+
+    let hit = hit_test(&lines, &document, &config, 0, 74.0 + 16.0, line_center_y(&lines[0], &config)).unwrap();
+    assert!(!hit.checkbox);
+    assert_eq!(hit.position.offset, 2);
+}
+
+#[test]
+fn test_hit_test_reports_link_under_point() {
+    let mut document = document_with("see docs here");
+    let link = Link::new("#docs", None).unwrap();
+    document.paragraphs[0].apply_style(4, 8, |s| s.link = Some(link.clone()));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let y = line_center_y(&lines[0], &config);
+
+    // Right half of "d": the caret goes after it, but the link is still hit
+    let hit = hit_test(&lines, &document, &config, 0, 50.0 + 8.0 * 4.0 + 6.0, y).unwrap();
+    assert_eq!(hit.position.offset, 5);
+    assert_eq!(hit.link, Some(link));
+
+    let hit = hit_test(&lines, &document, &config, 0, 50.0 + 8.0 * 8.0 + 2.0, y).unwrap();
+    assert!(hit.link.is_none());
+}
+
+#[test]
+fn test_caret_height_follows_font_run() {
+    let mut document = document_with("small BIG");
+    document.paragraphs[0].apply_style(6, 9, |s| s.font_size = Some(32.0));
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let baseline = config.margin_top + lines[0].y_position + lines[0].baseline();
+
+    let small = caret_rect(&lines, &document, &config, 0, 3, Affinity::Downstream).unwrap();
+    assert_eq!(small.height, 16.0);
+    assert_eq!(small.y + 16.0 * 0.8, baseline);
+
+    // After a character of the large run the caret takes its size
+    let big = caret_rect(&lines, &document, &config, 0, 8, Affinity::Downstream).unwrap();
+    assert_eq!(big.height, 32.0);
+    assert_eq!(big.y + 32.0 * 0.8, baseline);
+    assert_eq!(big.x, 50.0 + 6.0 * 8.0 + 2.0 * 16.0);
+}
+
+#[test]
+fn test_line_placement_applies_paragraph_indents() {
+    let mut document = document_with(&"word ".repeat(20));
+    document.paragraphs[0].meta.indent = ParagraphIndent {
+        left: 20.0,
+        right: 10.0,
+        first_line: 30.0,
+        hanging: 0.0,
+    };
+    document.paragraphs[0].meta.align = Some(TextAlign::Right);
+    let config = test_config();
+    let lines = layout(&document, &config);
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+
+    // Right aligned lines end at the right indent
+    for line in &lines {
+        let placement = line_placement(line, &format, &config);
+        let end = placement.x_at(line, line.text.trim_end().chars().count());
+        assert!((end - (350.0 - 10.0)).abs() < 0.001);
+    }
+
+    document.paragraphs[0].meta.align = None;
+    let lines = layout(&document, &config);
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+    assert_eq!(line_placement(&lines[0], &format, &config).x, 50.0 + 20.0 + 30.0);
+    assert_eq!(line_placement(&lines[1], &format, &config).x, 50.0 + 20.0);
+    let hit = hit_test(&lines, &document, &config, 0, 50.0 + 20.0 + 8.0, line_center_y(&lines[1], &config)).unwrap();
+    assert_eq!(hit.position.offset, lines[1].start_offset + 1);
+}
+
+#[test]
+fn test_hit_test_continued_table_maps_to_table_rows() {
+    let mut table = DocumentTable::new("t".to_string(), 6, 1, 0.0);
+    table.header_rows = 1;
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("\u{FFFB}t".to_string());
+    document.tables.push(table);
+    let config = LayoutConfig {
+        page_height: 100.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let x = config.margin_left + 10.0;
+
+    // Page 1 shows the header row, then rows 3 and 4
+    let cell = |y: f64| hit_test(&lines, &document, &config, 1, x, config.margin_top + y).unwrap().table.unwrap();
+    assert_eq!(cell(10.0).row, 0);
+    assert_eq!(cell(40.0).row, 3);
+    assert_eq!(cell(80.0).row, 4);
+}
+
+#[test]
+fn test_table_cell_at_uses_laid_out_rows_and_wrapped_lines() {
+    let mut table = DocumentTable::new("t".to_string(), 3, 2, 203.0);
+    // 100px columns, 92px for text: "alpha beta " and "gamma" wrap
+    table.get_cell_mut(0, 0).unwrap().set_text("alpha beta gamma");
+    table.get_cell_mut(1, 1).unwrap().set_text("x");
+    table.merge_cells(2, 0, 2, 1);
+    let config = LayoutConfig::default();
+    let document = Document::new();
+    let layout = compute_table_layout(&document, &table, &config, &FixedWidthMeasurer::default());
+    assert_eq!(layout.row_heights[0], 2.0 * 24.0 + 8.0);
+
+    // Second text line of the wrapped cell, after "ga"
+    let text_x = 1.0 + CELL_TEXT_PADDING;
+    let hit = table_cell_at(&document, &table, &layout, &config, text_x + 17.0, 1.0 + 4.0 + 24.0 + 5.0);
+    assert_eq!((hit.row, hit.col, hit.offset), (0, 0, "alpha beta ".len() + 2));
+
+    // Below the tall first row
+    let row1_top = 1.0 + layout.row_heights[0] + 1.0;
+    let hit = table_cell_at(&document, &table, &layout, &config, 150.0, row1_top + 10.0);
+    assert_eq!((hit.row, hit.col), (1, 1));
+
+    // The covered half of the merged last row reports its origin
+    let row2_top = row1_top + layout.row_heights[1] + 1.0;
+    let hit = table_cell_at(&document, &table, &layout, &config, 150.0, row2_top + 10.0);
+    assert_eq!((hit.row, hit.col), (2, 0));
+}
+
+#[test]
+fn test_table_cell_at_finds_cell_paragraphs_and_nested_tables() {
+    let mut document = Document::new();
+    let mut inner = DocumentTable::new("inner".to_string(), 2, 1, 100.0);
+    inner.get_cell_mut(1, 0).unwrap().set_text("xyz");
+    document.tables.push(inner);
+    let mut table = DocumentTable::new("outer".to_string(), 1, 1, 203.0);
+    let cell = table.get_cell_mut(0, 0).unwrap();
+    cell.set_text("ab\ncd");
+    cell.paragraphs.push(Paragraph::new("\u{FFFB}inner".to_string()));
+    let config = LayoutConfig::default();
+    let layout = compute_table_layout(&document, &table, &config, &FixedWidthMeasurer::default());
+
+    // Second paragraph, after "c"
+    let text_left = 1.0 + CELL_TEXT_PADDING;
+    let hit = table_cell_at(&document, &table, &layout, &config, text_left + 9.0, text_left + 24.0 + 5.0);
+    assert_eq!((hit.table_id.as_str(), hit.para, hit.offset), ("outer", 1, 1));
+
+    // The nested table starts below the two text lines
+    let inner_top = text_left + 48.0;
+    let inner_text = inner_top + 1.0 + 32.0 + 1.0 + CELL_TEXT_PADDING;
+    let hit = table_cell_at(&document, &table, &layout, &config, text_left + 5.0 + 17.0, inner_text + 5.0);
+    assert_eq!((hit.table_id.as_str(), hit.row, hit.col, hit.para, hit.offset), ("inner", 1, 0, 0, 2));
+}
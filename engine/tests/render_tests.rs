@@ -16,6 +16,165 @@ fn test_empty_page_has_page_number() {
         .any(|c| matches!(c, RenderCommand::DrawPageNumber { .. })));
 }
 
+#[test]
+fn test_styled_multibyte_text_renders_by_char() {
+    let mut document = Document::new();
+    let mut para = Paragraph::new("Grüße aus Köln".to_string());
+    para.apply_style(6, 9, |s| s.bold = true);
+    document.paragraphs[0] = para;
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+    let texts: Vec<&str> = commands
+        .iter()
+        .filter_map(|c| match c {
+            RenderCommand::DrawText { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    assert!(texts.contains(&"Grüße "));
+    assert!(texts.contains(&"aus"));
+    assert!(texts.contains(&" Köln"));
+}
+
+#[test]
+fn test_styled_segments_and_alignment_use_line_placement() {
+    let mut document = Document::new();
+    let mut para = Paragraph::new("abcdef".to_string());
+    para.meta.align = Some(TextAlign::Center);
+    para.apply_style(3, 6, |s| s.bold = true);
+    document.paragraphs[0] = para;
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+    let xs: Vec<f64> = commands
+        .iter()
+        .filter_map(|c| match c {
+            RenderCommand::DrawText { x, .. } => Some(*x),
+            _ => None,
+        })
+        .collect();
+
+    let format = paragraph_format(&document, &document.paragraphs[0].meta, &config);
+    let placement = line_placement(&lines[0], &format, &config);
+    assert!(placement.x > config.margin_left);
+    assert_eq!(xs[0], placement.x);
+    assert_eq!(xs[1], placement.x_at(&lines[0], 3));
+}
+
+#[test]
+fn test_checklist_item_draws_checkbox_and_strikes_when_checked() {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("Buy milk".to_string());
+    document.paragraphs[0].meta.list_type = ListType::Checklist;
+    let mut config = LayoutConfig::default();
+    let strikes = |document: &Document, config: &LayoutConfig| {
+        let lines = compute_layout(document, config, &FixedWidthMeasurer::default());
+        let commands = generate_render_commands(&lines, document, config, 0);
+        let format = paragraph_format(document, &document.paragraphs[0].meta, config);
+        let checkbox = checkbox_rect(&lines[0], &format, config).unwrap();
+        assert!(commands.iter().any(|c| matches!(c, RenderCommand::StrokeRect { x, .. } if *x == checkbox.x)));
+        let ticks = commands.iter().filter(|c| matches!(c, RenderCommand::DrawLine { .. })).count();
+        let strikes = commands.iter().filter(|c| matches!(c, RenderCommand::DrawStrikethrough { .. })).count();
+        (ticks, strikes)
+    };
+
+    assert_eq!(strikes(&document, &config), (0, 0));
+    document.paragraphs[0].meta.checked = true;
+    assert_eq!(strikes(&document, &config), (2, 1));
+    config.strike_checked_items = false;
+    assert_eq!(strikes(&document, &config), (2, 0));
+}
+
+#[test]
+fn test_links_render_underlined_in_link_color() {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("go home now".to_string());
+    let link = Link::new("https://example.com", None).unwrap();
+    document.paragraphs[0].apply_style(3, 7, |s| s.link = Some(link.clone()));
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+
+    let home = commands
+        .iter()
+        .position(|c| matches!(c, RenderCommand::DrawText { text, .. } if text == "home"))
+        .unwrap();
+    assert!(matches!(&commands[home - 1], RenderCommand::SetFillColor { color } if color == LINK_COLOR));
+    assert!(matches!(commands[home + 2], RenderCommand::DrawUnderline { .. }));
+    assert_eq!(commands.iter().filter(|c| matches!(c, RenderCommand::DrawUnderline { .. })).count(), 1);
+}
+
+#[test]
+fn test_set_font_carries_run_family_size_and_weight() {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("body title".to_string());
+    document.paragraphs[0].apply_style(5, 10, |s| {
+        s.font_family = Some("Georgia".to_string());
+        s.font_size = Some(32.0);
+        s.font_weight = Some(600);
+    });
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+
+    let fonts: Vec<(String, f64, u16)> = commands
+        .iter()
+        .filter_map(|c| match c {
+            RenderCommand::SetFont { font, size, weight, .. } => Some((font.clone(), *size, *weight)),
+            _ => None,
+        })
+        .collect();
+    assert!(fonts.contains(&("Arial".to_string(), 16.0, 400)));
+    assert!(fonts.contains(&("Georgia".to_string(), 32.0, 600)));
+
+    // Both runs sit on the same baseline, so the larger text starts higher
+    let text_y = |wanted: &str| {
+        commands.iter().find_map(|c| match c {
+            RenderCommand::DrawText { text, y, .. } if text == wanted => Some(*y),
+            _ => None,
+        })
+    };
+    let body = text_y("body ").unwrap();
+    let title = text_y("title").unwrap();
+    assert!((body - title - (32.0 - 16.0) * 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn test_overlay_draws_selection_under_text_and_caret_last() {
+    let document = Document::new();
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let rect = PageRect { page_index: 0, x: 10.0, y: 20.0, width: 30.0, height: 40.0 };
+    let overlay = SelectionOverlay {
+        caret: Some(rect),
+        selection: vec![rect, PageRect { page_index: 1, ..rect }],
+    };
+
+    let commands = generate_render_commands_with_overlay(&lines, &document, &config, 0, &overlay);
+    let selections = commands
+        .iter()
+        .filter(|c| matches!(c, RenderCommand::DrawSelection { .. }))
+        .count();
+    let caret = commands
+        .iter()
+        .position(|c| matches!(c, RenderCommand::DrawCursor { .. }))
+        .unwrap();
+    let page_number = commands
+        .iter()
+        .position(|c| matches!(c, RenderCommand::DrawPageNumber { .. }))
+        .unwrap();
+
+    assert_eq!(selections, 1);
+    assert!(caret < page_number);
+    assert!(generate_render_commands(&lines, &document, &config, 0)
+        .iter()
+        .all(|c| !matches!(c, RenderCommand::DrawCursor { .. })));
+}
+
 #[test]
 fn test_render_commands_serialization() {
     let cmd = RenderCommand::SetFillColor {
@@ -65,6 +224,7 @@ fn test_set_font_command() {
         font: "Arial".to_string(),
         size: 16.0,
         bold: true,
+        weight: 700,
         italic: false,
     };
 
@@ -73,3 +233,148 @@ fn test_set_font_command() {
     assert!(json.contains("Arial"));
     assert!(json.contains("bold"));
 }
+
+#[test]
+fn test_heading_text_is_vertically_centered_in_line() {
+    let mut document = Document::new();
+    document.paragraphs[0].text = "Title".to_string();
+    document.paragraphs[0].meta.block_type = BlockType::Heading1;
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+    let text_y = commands
+        .iter()
+        .find_map(|c| match c {
+            RenderCommand::DrawText { text, y, .. } if text == "Title" => Some(*y),
+            _ => None,
+        })
+        .unwrap();
+
+    // 48px line box with 32px of glyphs leaves 8px above the text
+    assert_eq!(text_y, config.margin_top + 8.0);
+}
+
+#[test]
+fn test_scripts_and_caps_change_drawn_text() {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("E=mc2 nasa".to_string());
+    document.paragraphs[0].apply_style(4, 5, |s| s.vertical_align = VerticalAlign::Superscript);
+    document.paragraphs[0].apply_style(6, 10, |s| s.caps = CapsStyle::AllCaps);
+    let config = LayoutConfig::default();
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let commands = generate_render_commands(&lines, &document, &config, 0);
+
+    let text_y = |wanted: &str| {
+        commands.iter().find_map(|c| match c {
+            RenderCommand::DrawText { text, y, .. } if text == wanted => Some(*y),
+            _ => None,
+        })
+    };
+    // The superscript is drawn smaller and above the surrounding text
+    let sup = lines[0].run_at(5).unwrap();
+    let base_y = text_y("E=mc").unwrap();
+    let sup_y = text_y("2").unwrap();
+    assert!(sup_y < base_y);
+    assert!(commands.iter().any(|c| matches!(c, RenderCommand::SetFont { size, .. } if *size == sup.font.size)));
+
+    // Caps transforms change only the drawn text
+    assert!(text_y("NASA").is_some());
+    assert_eq!(document.paragraphs[0].text, "E=mc2 nasa");
+}
+
+#[test]
+fn test_split_table_draws_header_on_each_page() {
+    let mut table = DocumentTable::new("t".to_string(), 6, 1, 0.0);
+    table.header_rows = 1;
+    for (row, text) in ["Name", "r1", "r2", "r3", "r4", "r5"].iter().enumerate() {
+        table.get_cell_mut(row, 0).unwrap().set_text(text);
+    }
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("\u{FFFB}t".to_string());
+    document.tables.push(table);
+    let config = LayoutConfig {
+        page_height: 100.0 + 192.0,
+        ..Default::default()
+    };
+    let lines = compute_layout(&document, &config, &FixedWidthMeasurer::default());
+    let texts = |page: usize| -> Vec<(String, f64)> {
+        generate_render_commands(&lines, &document, &config, page)
+            .into_iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawText { text, y, .. } => Some((text, y)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // Rows every 33px; cell text is centered in its 24px line box like body
+    // text, 9px below each row's top
+    let top = config.margin_top;
+    assert_eq!(texts(0), vec![("Name".to_string(), top + 9.0), ("r1".to_string(), top + 42.0), ("r2".to_string(), top + 75.0)]);
+    assert_eq!(texts(1), vec![("Name".to_string(), top + 9.0), ("r3".to_string(), top + 42.0), ("r4".to_string(), top + 75.0)]);
+    assert_eq!(texts(2), vec![("Name".to_string(), top + 9.0), ("r5".to_string(), top + 42.0)]);
+}
+
+#[test]
+fn test_table_borders_collapse_cell_sides() {
+    let mut document = Document::new();
+    document.paragraphs[0] = Paragraph::new("\u{FFFB}t".to_string());
+    document.tables.push(DocumentTable::new("t".to_string(), 2, 2, 203.0));
+    let config = LayoutConfig::default();
+    let borders = |document: &Document| -> Vec<(f64, f64, f64, f64, f64, BorderStyle)> {
+        let lines = compute_layout(document, &config, &FixedWidthMeasurer::default());
+        let (x, y) = (config.margin_left, config.margin_top);
+        generate_render_commands(&lines, document, &config, 0)
+            .into_iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawTableBorder { x1, y1, x2, y2, width, style, .. } => {
+                    Some((x1 - x, y1 - y, x2 - x, y2 - y, width, style))
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    // Two 100px columns and two 32px rows; each grid line is one border
+    let solid = BorderStyle::Solid;
+    assert_eq!(
+        borders(&document),
+        vec![
+            (0.0, 0.0, 203.0, 0.0, 1.0, solid),
+            (0.0, 33.0, 203.0, 33.0, 1.0, solid),
+            (0.0, 67.0, 203.0, 67.0, 1.0, solid),
+            (0.0, 0.0, 0.0, 67.0, 1.0, solid),
+            (101.0, 0.0, 101.0, 67.0, 1.0, solid),
+            (203.0, 0.0, 203.0, 67.0, 1.0, solid),
+        ]
+    );
+
+    // The wider of two cell sides wins, and a side set to none hides the
+    // table's border. The winner is drawn no wider than the 1px the table
+    // leaves between its cells.
+    let table = &mut document.tables[0];
+    let dashed = CellBorder { width: 3.0, color: "#ff0000".to_string(), style: BorderStyle::Dashed };
+    table.get_cell_mut(0, 0).unwrap().borders.bottom = Some(dashed);
+    let thin = CellBorder { width: 2.0, color: "#000000".to_string(), style: solid };
+    table.get_cell_mut(1, 0).unwrap().borders.top = Some(thin);
+    let none = CellBorder { width: 1.0, color: "#000000".to_string(), style: BorderStyle::None };
+    table.get_cell_mut(0, 1).unwrap().borders.left = Some(none);
+    assert_eq!(
+        borders(&document),
+        vec![
+            (0.0, 0.0, 203.0, 0.0, 1.0, solid),
+            (0.0, 33.0, 101.0, 33.0, 1.0, BorderStyle::Dashed),
+            (101.0, 33.0, 203.0, 33.0, 1.0, solid),
+            (0.0, 67.0, 203.0, 67.0, 1.0, solid),
+            (0.0, 0.0, 0.0, 67.0, 1.0, solid),
+            (101.0, 33.0, 101.0, 67.0, 1.0, solid),
+            (203.0, 0.0, 203.0, 67.0, 1.0, solid),
+        ]
+    );
+
+    // No border inside a merged cell
+    document.tables[0].merge_cells(0, 0, 1, 0);
+    let inner: Vec<_> = borders(&document).into_iter().filter(|b| b.1 == 33.0 && b.3 == 33.0).collect();
+    assert_eq!(inner, vec![(101.0, 33.0, 203.0, 33.0, 1.0, solid)]);
+}
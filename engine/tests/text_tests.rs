@@ -119,3 +119,20 @@ fn test_split_into_runs_simple() {
     assert_eq!(runs[0].start, 0);
     assert_eq!(runs[0].end, 5);
 }
+
+#[test]
+fn test_to_alphabetic() {
+    assert_eq!(to_alphabetic(1), "a");
+    assert_eq!(to_alphabetic(26), "z");
+    assert_eq!(to_alphabetic(27), "aa");
+    assert_eq!(to_alphabetic(53), "ba");
+}
+
+#[test]
+fn test_to_roman() {
+    assert_eq!(to_roman(1), "i");
+    assert_eq!(to_roman(4), "iv");
+    assert_eq!(to_roman(9), "ix");
+    assert_eq!(to_roman(14), "xiv");
+    assert_eq!(to_roman(1994), "mcmxciv");
+}
@@ -73,5 +73,3 @@ export * from './image-manager';
 // Canvas rendering utilities
 export * from './canvas-renderer';
 
-// Undo/Redo history management
-export * from './history-manager';
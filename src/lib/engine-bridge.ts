@@ -20,6 +20,7 @@ export interface SetFontCommand {
   size: number;
   bold: boolean;
   italic: boolean;
+  weight?: number;
 }
 
 export interface SetFillColorCommand {
@@ -129,6 +130,7 @@ export interface DrawTableBorderCommand {
   y2: number;
   width: number;
   color: string;
+  style: BorderStyle;
 }
 
 export interface FillCellBackgroundCommand {
@@ -157,26 +159,115 @@ export interface ParagraphPosition {
 // Paragraph metadata types
 export type TextAlign = 'left' | 'center' | 'right' | 'justify';
 export type BlockType = 'p' | 'h1' | 'h2' | 'h3' | 'h4' | 'blockquote';
-export type ListType = 'none' | 'bullet' | 'numbered';
+export type ListType = 'none' | 'bullet' | 'numbered' | 'checklist';
+export type NumberFormat = 'decimal' | 'decimal-zero' | 'lower-alpha' | 'upper-alpha' | 'lower-roman' | 'upper-roman';
+
+export interface ParagraphIndent {
+  left: number;
+  right: number;
+  first_line: number;
+  hanging: number;
+}
+
+export type LineSpacing =
+  | { mode: 'multiple'; value: number }
+  | { mode: 'exact'; value: number }
+  | { mode: 'at-least'; value: number };
 
 export interface ParagraphMeta {
+  style: string;
   align: TextAlign;
   blockType: BlockType;
   listType: ListType;
+  listLevel: number;
+  numberFormat: NumberFormat | null;
+  numberTemplate: string | null;
+  listStart: number | null;
+  listContinue: boolean;
+  checked: boolean;
+  indent: ParagraphIndent;
+  spaceBefore: number;
+  spaceAfter: number;
+  lineSpacing: LineSpacing;
+  keepWithNext: boolean;
+  keepTogether: boolean;
+  pageBreakBefore: boolean;
+  widows: number;
+  orphans: number;
+}
+
+// Style sheet types
+export type StyleKind = 'paragraph' | 'character';
+
+export interface NamedStyle {
+  id: string;
+  name: string;
+  kind: StyleKind;
+  based_on?: string | null;
+  align?: TextAlign | null;
+  font_size?: number | null;
+  font_scale?: number | null;
+  bold?: boolean | null;
+  italic?: boolean | null;
+  underline?: boolean | null;
+  strikethrough?: boolean | null;
+  color?: string | null;
+  background?: string | null;
+  font_family?: string | null;
+  font_weight?: number | null;
+  space_before?: number | null;
+  space_after?: number | null;
+  line_spacing?: LineSpacing | null;
+  keep_with_next?: boolean | null;
+  keep_together?: boolean | null;
+  page_break_before?: boolean | null;
+  widows?: number | null;
+  orphans?: number | null;
 }
 
 // Table types
 export type TableWidthMode = 'fixed' | 'percentage' | 'auto';
 
-export interface TableCell {
+export interface CellParagraph {
   text: string;
-  align: TextAlign;
+  meta: ParagraphMeta;
+  styles: Array<{ start: number; end: number } & Record<string, unknown>>;
+}
+
+export type CellVerticalAlign = 'top' | 'middle' | 'bottom';
+export type BorderStyle = 'solid' | 'dashed' | 'dotted' | 'double' | 'none';
+
+export interface CellPadding {
+  top: number;
+  right: number;
+  bottom: number;
+  left: number;
+}
+
+export interface CellBorder {
+  width: number;
+  color: string;
+  style: BorderStyle;
+}
+
+export interface CellBorders {
+  top: CellBorder | null;
+  right: CellBorder | null;
+  bottom: CellBorder | null;
+  left: CellBorder | null;
+}
+
+export interface TableCell {
+  paragraphs: CellParagraph[];
   background: string | null;
   col_span: number;
   row_span: number;
   covered: boolean;
   covered_by_row: number | null;
   covered_by_col: number | null;
+  verticalAlign: CellVerticalAlign;
+  padding: CellPadding | null;
+  borders: CellBorders;
 }
 
 export interface CellMergeInfo {
@@ -199,6 +290,8 @@ export interface TableInfo {
   border_width: number;
   border_color: string;
   width_mode: TableWidthMode;
+  headerRows: number;
+  cellPadding: CellPadding;
 }
 
 export interface TableDimensions {
@@ -207,8 +300,11 @@ export interface TableDimensions {
 }
 
 export interface CellPosition {
+  tableId: string;
   row: number;
   col: number;
+  para: number;
+  offset: number;
 }
 
 // Engine type (will be filled when WASM loads)
@@ -235,7 +331,16 @@ export interface Engine {
   set_paragraph(index: number, text: string): void;
   insert_paragraph(index: number, text: string): void;
   delete_paragraph(index: number): void;
-  recompute_layout(measureFn: (text: string, fontSize: number) => number): boolean;
+  insert_text(paraIndex: number, offset: number, text: string): void;
+  delete_text(paraIndex: number, start: number, end: number): void;
+  split_paragraph(paraIndex: number, offset: number): boolean;
+  join_with_next(paraIndex: number): boolean;
+  delete_range(startPara: number, startOffset: number, endPara: number, endOffset: number): boolean;
+  recompute_layout(measureFn: MeasureFn, measurePrefixesFn?: MeasurePrefixesFn): boolean;
+  register_font(name: string, bytes: Uint8Array): void;
+  get_font_families(): string;
+  recompute_layout_with_fonts(): boolean;
+  get_changed_pages(): string;
   page_count(): number;
   get_render_commands(pageIndex: number): string;
   get_display_lines_json(): string;
@@ -244,28 +349,80 @@ export interface Engine {
   // Position mapping functions
   para_to_display_pos(paraIndex: number, charOffset: number): string | null;
   display_to_para(line: number, col: number): string | null;
+  hit_test(pageIndex: number, x: number, y: number): string;
+  caret_rect(paraIndex: number, charOffset: number): string;
+  selection_rects(startPara: number, startOffset: number, endPara: number, endOffset: number): string;
+  set_selection(anchorPara: number, anchorOffset: number, focusPara: number, focusOffset: number): void;
+  clear_selection(): void;
+  set_render_selection(enabled: boolean): void;
   get_page_for_position(paraIndex: number, charOffset: number): number;
 
   // Document I/O
   load_document(json: string): void;
   save_document(): string;
 
+  // History
+  undo(): boolean;
+  redo(): boolean;
+  can_undo(): boolean;
+  can_redo(): boolean;
+  begin_group(): void;
+  end_group(): void;
+  clear_history(): void;
+
   // Paragraph metadata functions
   get_paragraph_meta(index: number): string | null;
   set_block_type(index: number, blockType: string): void;
   set_alignment(index: number, align: string): void;
   set_list_type(index: number, listType: string): void;
   toggle_list(index: number, listType: string): void;
+  indent_list_item(index: number): boolean;
+  outdent_list_item(index: number): boolean;
+  set_number_format(index: number, format: string): void;
+  set_number_template(index: number, template: string): void;
+  set_list_start(index: number, start: number | undefined): void;
+  set_list_continue(index: number, continuePrevious: boolean): void;
+  set_paragraph_indent(index: number, left: number, right: number, firstLine: number, hanging: number): void;
+  increase_indent(index: number): boolean;
+  decrease_indent(index: number): boolean;
+  set_paragraph_spacing(index: number, before: number | undefined, after: number | undefined): void;
+  set_line_spacing(index: number, mode: string, value: number): boolean;
+  set_pagination(
+    index: number,
+    keepWithNext: boolean | undefined,
+    keepTogether: boolean | undefined,
+    pageBreakBefore: boolean | undefined
+  ): void;
+  set_widow_orphan_lines(index: number, widows: number | undefined, orphans: number | undefined): void;
+  toggle_checklist_item(index: number): boolean;
+  set_strike_checked_items(strike: boolean): void;
 
   // Text styling functions
   toggle_bold(paraIndex: number, start: number, end: number): void;
   toggle_italic(paraIndex: number, start: number, end: number): void;
   toggle_underline(paraIndex: number, start: number, end: number): void;
   toggle_strikethrough(paraIndex: number, start: number, end: number): void;
+  toggle_superscript(paraIndex: number, start: number, end: number): void;
+  toggle_subscript(paraIndex: number, start: number, end: number): void;
+  toggle_small_caps(paraIndex: number, start: number, end: number): void;
+  toggle_all_caps(paraIndex: number, start: number, end: number): void;
   set_text_color(paraIndex: number, start: number, end: number, color: string): void;
   set_highlight_color(paraIndex: number, start: number, end: number, color: string): void;
+  set_link(paraIndex: number, start: number, end: number, url: string, title?: string): boolean;
+  remove_link(paraIndex: number, start: number, end: number): void;
+  get_link_at(paraIndex: number, offset: number): string;
+  set_font_family(paraIndex: number, start: number, end: number, family: string): void;
+  set_font_size(paraIndex: number, start: number, end: number, size: number): void;
+  set_font_weight(paraIndex: number, start: number, end: number, weight: number): void;
+  set_default_font_family(family: string): void;
   get_paragraph_styles(index: number): string;
 
+  // Style sheet functions
+  get_styles(): string;
+  set_style(json: string): boolean;
+  set_paragraph_style(index: number, styleId: string): boolean;
+  set_character_style(paraIndex: number, start: number, end: number, styleId: string): boolean;
+
   // Image functions
   add_image(id: string, src: string, width: number, height: number, naturalWidth: number, naturalHeight: number): void;
   insert_image_paragraph(index: number, imageId: string): void;
@@ -294,6 +451,41 @@ export interface Engine {
   set_cell_text(tableId: string, row: number, col: number, text: string): void;
   set_cell_background(tableId: string, row: number, col: number, color: string): void;
   set_cell_align(tableId: string, row: number, col: number, align: string): void;
+  get_cell_paragraphs(tableId: string, row: number, col: number): string | null;
+  insert_cell_text(tableId: string, row: number, col: number, para: number, offset: number, text: string): boolean;
+  delete_cell_text(
+    tableId: string,
+    row: number,
+    col: number,
+    startPara: number,
+    startOffset: number,
+    endPara: number,
+    endOffset: number
+  ): boolean;
+  set_cell_list_type(tableId: string, row: number, col: number, para: number, listType: string): void;
+  insert_cell_image_paragraph(tableId: string, row: number, col: number, index: number, imageId: string): void;
+  insert_cell_table_paragraph(tableId: string, row: number, col: number, index: number, nestedTableId: string): boolean;
+  set_cell_vertical_align(tableId: string, row: number, col: number, align: CellVerticalAlign): boolean;
+  set_cell_padding(
+    tableId: string,
+    row: number,
+    col: number,
+    top: number,
+    right: number,
+    bottom: number,
+    left: number
+  ): void;
+  clear_cell_padding(tableId: string, row: number, col: number): void;
+  set_table_cell_padding(tableId: string, top: number, right: number, bottom: number, left: number): void;
+  set_cell_border(
+    tableId: string,
+    row: number,
+    col: number,
+    side: 'top' | 'right' | 'bottom' | 'left' | 'all',
+    width: number,
+    color: string,
+    style: BorderStyle | ''
+  ): boolean;
   add_table_row(tableId: string, atIndex: number): void;
   add_table_column(tableId: string, atIndex: number): void;
   delete_table_row(tableId: string, row: number): boolean;
@@ -301,7 +493,14 @@ export interface Engine {
   delete_table(id: string): void;
   set_column_width(tableId: string, col: number, width: number): void;
   set_table_border(tableId: string, width: number, color: string): void;
+  set_table_header_rows(tableId: string, rows: number): void;
+  set_table_width_mode(tableId: string, mode: TableWidthMode): boolean;
   get_table_dimensions(tableId: string): string | null;
+  sort_table(tableId: string, col: number, ascending: boolean, numeric: boolean): boolean;
+  move_table_row(tableId: string, from: number, to: number): boolean;
+  move_table_column(tableId: string, from: number, to: number): boolean;
+  convert_paragraphs_to_table(start: number, end: number, delimiter: string): string | undefined;
+  convert_table_to_paragraphs(tableId: string, delimiter: string): boolean;
   get_cell_at_position(tableId: string, relX: number, relY: number): string | null;
 
   // Cell merge/split functions
@@ -424,6 +623,31 @@ export function isEngineLoaded(): boolean {
   return engineInstance !== null;
 }
 
+/**
+ * Text measurement callback passed to the engine.
+ * The engine calls it with the font weight (100-900), italic flag and font family of the run being measured.
+ */
+export type MeasureFn = (
+  text: string,
+  fontSize: number,
+  fontWeight?: number,
+  italic?: boolean,
+  fontFamily?: string
+) => number;
+
+/**
+ * Optional companion to MeasureFn, called with the same arguments.
+ * Returns the width of every character prefix of the text (code points, starting with 0),
+ * so the engine crosses into JavaScript once per font run instead of once per character.
+ */
+export type MeasurePrefixesFn = (
+  text: string,
+  fontSize: number,
+  fontWeight?: number,
+  italic?: boolean,
+  fontFamily?: string
+) => Float64Array;
+
 /**
  * Create a text measurement function for the engine
  */
@@ -467,7 +691,7 @@ export function executeRenderCommands(
       case 'setFont': {
         const c = cmd as unknown as SetFontCommand;
         const style = c.italic ? 'italic ' : '';
-        const weight = c.bold ? 'bold ' : '';
+        const weight = c.weight ? `${c.weight} ` : c.bold ? 'bold ' : '';
         ctx.font = `${style}${weight}${c.size}px ${c.font || fontFamily}`;
         break;
       }
@@ -617,11 +841,29 @@ export function executeRenderCommands(
       case 'drawTableBorder': {
         const c = cmd as unknown as DrawTableBorderCommand;
         ctx.strokeStyle = c.color;
-        ctx.lineWidth = c.width;
-        ctx.beginPath();
-        ctx.moveTo(c.x1, c.y1);
-        ctx.lineTo(c.x2, c.y2);
-        ctx.stroke();
+        ctx.setLineDash(
+          c.style === 'dashed' ? [3 * c.width, 2 * c.width] : c.style === 'dotted' ? [c.width, c.width] : []
+        );
+        if (c.style === 'double') {
+          // Two lines a third of the width each, with a third between them
+          const length = Math.hypot(c.x2 - c.x1, c.y2 - c.y1) || 1;
+          const nx = ((c.y1 - c.y2) / length) * (c.width / 3);
+          const ny = ((c.x2 - c.x1) / length) * (c.width / 3);
+          ctx.lineWidth = c.width / 3;
+          ctx.beginPath();
+          for (const side of [-1, 1]) {
+            ctx.moveTo(c.x1 + side * nx, c.y1 + side * ny);
+            ctx.lineTo(c.x2 + side * nx, c.y2 + side * ny);
+          }
+          ctx.stroke();
+        } else {
+          ctx.lineWidth = c.width;
+          ctx.beginPath();
+          ctx.moveTo(c.x1, c.y1);
+          ctx.lineTo(c.x2, c.y2);
+          ctx.stroke();
+        }
+        ctx.setLineDash([]);
         break;
       }
 